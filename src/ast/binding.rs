@@ -1,41 +1,56 @@
 use crate::{
     ast::{
-        lib::{convert_iter, wrap_scope, AyNode, ComparisonOperator, Multiplier, Node},
+        lib::{
+            convert_iter, number_word, wrap_scope, AyNode, BinaryOperator, ComparisonOperator,
+            Node, StructuralEq,
+        },
         parsing::{Expr as PExpr, Statement as PStatement},
     },
     error::{
         span::Span,
-        trace::{Stage, Trace, TraceError},
+        trace::{Severity, Stage, Trace, TraceError},
         trace_error::Error,
     },
+    interp::{builtins, value::Value},
+    visit::{walk, walk_expr, walk_statement, Visitor},
 };
 
+use std::{collections::HashMap, ops::Deref, rc::Rc};
+
 use {pest::error::LineColLocation, quickscope::ScopeMap};
 
-#[derive(PartialEq, Eq, Default, Debug, Clone)]
+#[derive(PartialEq, Default, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunDec {
-    name: String,
-    args: Vec<String>,
-    body: Vec<AyNode<Statement>>,
+    pub name: String,
+    pub args: Vec<String>,
+    pub body: Vec<AyNode<Statement>>,
+    /// Declarations of the `tsengit` clause, to be run before the body.
+    pub helpers: Vec<AyNode<Statement>>,
 }
 
 #[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Tense {
     Present,
     Imminent,
     Future,
 }
 
-#[derive(PartialEq, Eq, Debug, Default, Clone)]
+#[derive(PartialEq, Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VarDec {
-    names: Vec<String>,
-    values: Vec<AyNode<Expr>>,
+    pub names: Vec<String>,
+    pub values: Vec<AyNode<Expr>>,
 }
 
 /// A statement is anything that cannot be expected to return a value.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Statement {
-    FunDec(FunDec),
+    /// Only shared with the `Binder`'s cache and scopes, a tree holds each declaration once and
+    /// serializing it by value duplicates nothing.
+    FunDec(Rc<FunDec>),
     VarDec(VarDec),
     Expr(AyNode<Expr>),
     If {
@@ -46,13 +61,82 @@ pub enum Statement {
     Loop {
         cond: Option<AyNode<Expr>>,
         body: Vec<AyNode<Statement>>,
+        label: Option<String>,
     },
+    /// Always within a loop, with the given label if any.
+    Break(Option<String>),
+    /// Always within a loop, with the given label if any.
+    Continue(Option<String>),
+    /// Checked at runtime unless its condition only depends on literals.
+    Assert(AyNode<Expr>),
+    /// Updates existing variables all at once, only produced by rewrites such as
+    /// [`iterate`](crate::ast::iteration::iterate).
+    Assign(VarDec),
 }
 impl Node for Statement {}
 
+impl StructuralEq for FunDec {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.args == other.args
+            && self.body.structural_eq(&other.body)
+            && self.helpers.structural_eq(&other.helpers)
+    }
+}
+
+impl StructuralEq for VarDec {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self.names == other.names && self.values.structural_eq(&other.values)
+    }
+}
+
+/// Variants without children are left to `PartialEq`.
+impl StructuralEq for Statement {
+    fn structural_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Statement::FunDec(dec), Statement::FunDec(other)) => dec.structural_eq(other),
+            (Statement::VarDec(dec), Statement::VarDec(other))
+            | (Statement::Assign(dec), Statement::Assign(other)) => dec.structural_eq(other),
+            (Statement::Expr(expr), Statement::Expr(other))
+            | (Statement::Assert(expr), Statement::Assert(other)) => expr.structural_eq(other),
+            (
+                Statement::If {
+                    cond,
+                    then,
+                    otherwise,
+                },
+                Statement::If {
+                    cond: other_cond,
+                    then: other_then,
+                    otherwise: other_otherwise,
+                },
+            ) => {
+                cond.structural_eq(other_cond)
+                    && then.structural_eq(other_then)
+                    && otherwise.structural_eq(other_otherwise)
+            }
+            (
+                Statement::Loop { cond, body, label },
+                Statement::Loop {
+                    cond: other_cond,
+                    body: other_body,
+                    label: other_label,
+                },
+            ) => {
+                cond.structural_eq(other_cond)
+                    && body.structural_eq(other_body)
+                    && label == other_label
+            }
+            _ => self == other,
+        }
+    }
+}
+
 /// An expression is anything that is or returns a value.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
+    /// `name` is the declared name, the call's spelling is given back by its `tense`.
     FunCall {
         tense: Tense,
         name: String,
@@ -66,227 +150,2795 @@ pub enum Expr {
         right: Box<AyNode<Expr>>,
         operator: ComparisonOperator,
     },
+    BinOp {
+        op: BinaryOperator,
+        left: Box<AyNode<Expr>>,
+        right: Box<AyNode<Expr>>,
+    },
     Number(i64),
+    Float(f64),
     String(String),
+    Bool(bool),
     Var(String),
     Negated(Box<AyNode<Expr>>),
+    None,
+    HasValue(Box<AyNode<Expr>>),
+    Fallback {
+        value: Box<AyNode<Expr>>,
+        fallback: Box<AyNode<Expr>>,
+    },
+    Index {
+        value: Box<AyNode<Expr>>,
+        index: Box<AyNode<Expr>>,
+    },
+    /// Fields are not resolved, they depend on the value's type.
+    Field {
+        value: Box<AyNode<Expr>>,
+        name: String,
+    },
+    /// Builtin used as a value, such as `(+)`.
+    FunRef(String),
 }
 impl Node for Expr {}
 
-pub fn convert(mut ast: &Vec<AyNode<PStatement>>) -> Result<Vec<AyNode<Statement>>, Trace> {
-    let mut vars = ScopeMap::<String, ()>::new();
-    let mut funs = ScopeMap::<String, ()>::new();
+/// Variants without children are left to `PartialEq`.
+impl StructuralEq for Expr {
+    fn structural_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Expr::FunCall { tense, name, args },
+                Expr::FunCall {
+                    tense: other_tense,
+                    name: other_name,
+                    args: other_args,
+                },
+            ) => tense == other_tense && name == other_name && args.structural_eq(other_args),
+            (Expr::Array { items }, Expr::Array { items: other_items }) => {
+                items.structural_eq(other_items)
+            }
+            (
+                Expr::Comparison {
+                    left,
+                    right,
+                    operator,
+                },
+                Expr::Comparison {
+                    left: other_left,
+                    right: other_right,
+                    operator: other_operator,
+                },
+            ) => {
+                operator == other_operator
+                    && left.structural_eq(other_left)
+                    && right.structural_eq(other_right)
+            }
+            (
+                Expr::BinOp { op, left, right },
+                Expr::BinOp {
+                    op: other_op,
+                    left: other_left,
+                    right: other_right,
+                },
+            ) => {
+                op == other_op && left.structural_eq(other_left) && right.structural_eq(other_right)
+            }
+            (Expr::Negated(expr), Expr::Negated(other))
+            | (Expr::HasValue(expr), Expr::HasValue(other)) => expr.structural_eq(other),
+            (
+                Expr::Fallback { value, fallback },
+                Expr::Fallback {
+                    value: other_value,
+                    fallback: other_fallback,
+                },
+            )
+            | (
+                Expr::Index {
+                    value,
+                    index: fallback,
+                },
+                Expr::Index {
+                    value: other_value,
+                    index: other_fallback,
+                },
+            ) => value.structural_eq(other_value) && fallback.structural_eq(other_fallback),
+            (
+                Expr::Field { value, name },
+                Expr::Field {
+                    value: other_value,
+                    name: other_name,
+                },
+            ) => name == other_name && value.structural_eq(other_value),
+            _ => self == other,
+        }
+    }
+}
+
+/// Top-level function declarations are identified by their source text and the names visible to
+/// them, as binding their body cannot depend on anything else.
+///
+/// Their location is left out for edits above them not to invalidate them, reused ones keeping
+/// the spans of their first binding. It is only kept when building a [`ResolutionTable`], which is
+/// looked up by position.
+type CacheKey = (String, Option<(usize, usize)>, Vec<String>);
 
-    ast.iter()
-        .map(move |node| convert_statement(node, &mut vars, &mut funs))
-        .collect::<Result<Vec<AyNode<Statement>>, Trace>>()
+/// What binding knows of a function in scope, builtins having neither a fixed number of
+/// parameters nor a declaration.
+#[derive(Clone, Debug)]
+struct Signature {
+    arity: Option<usize>,
+    span: Option<Span>,
 }
 
-fn convert_statement(
-    AyNode { span, inner }: &AyNode<PStatement>,
-    mut vars: &mut ScopeMap<String, ()>,
-    mut funs: &mut ScopeMap<String, ()>,
-) -> Result<AyNode<Statement>, Trace> {
-    match inner {
-        PStatement::VarDec { names, values } => {
-            names.iter().for_each(|name| vars.define(name.clone(), ()));
+impl Signature {
+    const BUILTIN: Signature = Signature {
+        arity: None,
+        span: None,
+    };
+}
 
-            Ok(AyNode {
-                span: span.clone(),
-                inner: Statement::VarDec(VarDec {
-                    names: names.clone(),
-                    values: convert_iter!(expr values | vars funs)?,
-                }),
-            })
+/// Functions in scope, along with every spelling they can be called with so that resolving a call
+/// is a single lookup rather than a scan of the scope.
+///
+/// Mutations go through it to keep both in sync, lookups by declared name go to the underlying
+/// `ScopeMap`.
+#[derive(Clone)]
+struct FunScope<T> {
+    decs: ScopeMap<String, T>,
+    /// Tense and declared name of every spelling, layered like `decs`.
+    calls: ScopeMap<String, (Tense, String)>,
+}
+
+impl<T> Default for FunScope<T> {
+    fn default() -> Self {
+        Self {
+            decs: ScopeMap::new(),
+            calls: ScopeMap::new(),
         }
-        PStatement::FunDec { name, args, body } => {
-            funs.define(name.clone(), ());
-            Ok(AyNode {
-                span: span.clone(),
-                inner: Statement::FunDec(FunDec {
-                    name: name.clone(),
-                    args: args.clone(),
-                    body: wrap_scope!(
-                        vars,
-                        funs | {
-                            args.iter().for_each(|var| vars.define(var.clone(), ()));
-                            convert_iter!(statement body | vars funs)?
-                        }
-                    ),
-                }),
-            })
+    }
+}
+
+impl<T> Deref for FunScope<T> {
+    type Target = ScopeMap<String, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.decs
+    }
+}
+
+impl<T> FunScope<T> {
+    /// Within a scope, a spelling that is the present tense of a function keeps calling it when
+    /// another function is spelled the same in another tense.
+    fn define(&mut self, name: String, value: T) {
+        for (tense, spelling) in spellings(&name) {
+            let taken = self.calls.contains_key_at_top(&spelling)
+                && self
+                    .calls
+                    .get(&spelling)
+                    .is_some_and(|(other_tense, other)| {
+                        *other_tense == Tense::Present && *other != name
+                    });
+
+            if tense == Tense::Present || !taken {
+                self.calls.define(spelling, (tense, name.clone()));
+            }
         }
-        PStatement::If {
-            cond,
-            then,
-            otherwise,
-        } => Ok(AyNode {
-            span: span.clone(),
-            inner: Statement::If {
-                cond: convert_expr(cond, vars, funs)?,
-                then: wrap_scope!(vars, funs | { convert_iter!(statement then | vars funs)? }),
-                otherwise: wrap_scope!(
-                    vars,
-                    funs | { convert_iter!(statement otherwise | vars funs)? }
-                ),
-            },
-        }),
-        PStatement::Loop { cond, body } => Ok(AyNode {
-            span: span.clone(),
-            inner: Statement::Loop {
-                cond: cond
-                    .clone()
-                    .map(|cond| convert_expr(&cond, vars, funs))
-                    .transpose()?,
-                body: wrap_scope!(vars, funs | { convert_iter!(statement body | vars funs)? }),
-            },
-        }),
-        PStatement::Expr(expr) => Ok(AyNode {
-            span: span.clone(),
-            inner: Statement::Expr(convert_expr(expr, vars, funs)?),
-        }),
+
+        self.decs.define(name, value);
+    }
+
+    fn push_layer(&mut self) {
+        self.decs.push_layer();
+        self.calls.push_layer();
+    }
+
+    fn pop_layer(&mut self) {
+        self.decs.pop_layer();
+        self.calls.pop_layer();
+    }
+
+    fn clear_all(&mut self) {
+        self.decs.clear_all();
+        self.calls.clear_all();
+    }
+
+    /// Finds the declaration called by `call`, returning the tense it is called with and its
+    /// declared name.
+    fn resolve(&self, call: &str) -> Option<(Tense, String)> {
+        self.calls.get(call).cloned()
     }
 }
 
-fn convert_expr(
-    AyNode { span, inner }: &AyNode<PExpr>,
-    mut vars: &mut ScopeMap<String, ()>,
-    mut funs: &mut ScopeMap<String, ()>,
-) -> Result<AyNode<Expr>, Trace> {
-    match inner {
-        PExpr::Ident(name) => {
-            if let Some(rc) = vars.get(name) {
-                Ok(AyNode {
-                    span: span.clone(),
-                    inner: Expr::Var(name.clone()),
+/// Binds programs while keeping the top-level functions of the last run around so that unchanged
+/// ones can be reused instead of being converted again.
+///
+/// The top-level declarations of the last run can also be looked up, e.g. for editor tooling.
+#[derive(Default)]
+pub struct Binder {
+    cache: HashMap<CacheKey, Rc<FunDec>>,
+    vars: ScopeMap<String, Rc<VarDec>>,
+    funs: FunScope<Rc<FunDec>>,
+    /// Whether every use of a name is resolved, see [`Binder::with_resolutions`].
+    resolve: bool,
+    resolutions: Option<ResolutionTable>,
+}
+
+/// `ScopeMap` is not `Debug`, only the declared names are shown.
+impl std::fmt::Debug for Binder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Binder")
+            .field("cache", &self.cache)
+            .field("vars", &self.vars.keys().collect::<Vec<_>>())
+            .field("funs", &self.funs.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Binder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also builds the [`ResolutionTable`] of each program bound without errors.
+    pub fn with_resolutions(mut self) -> Self {
+        self.resolve = true;
+        self
+    }
+
+    pub fn convert(&mut self, ast: &[AyNode<PStatement>]) -> Result<Vec<AyNode<Statement>>, Trace> {
+        self.convert_all(ast)
+            .map_err(|errors| errors.into_iter().next().unwrap())
+    }
+
+    /// Binds the whole program even past errors, returning all of them in source order.
+    ///
+    /// Undefined names are bound as if they existed for the rest of their statement to be
+    /// checked, other errors skip the top-level statement they are found in.
+    pub fn convert_all(
+        &mut self,
+        ast: &[AyNode<PStatement>],
+    ) -> Result<Vec<AyNode<Statement>>, Vec<Trace>> {
+        check_loop_controls(ast, &mut vec![]).map_err(|trace| vec![trace])?;
+
+        let mut vars = ScopeMap::<String, ()>::new();
+        let mut funs = FunScope::<Signature>::default();
+        let mut cache = HashMap::new();
+        let mut errors = vec![];
+
+        builtins::names().for_each(|name| funs.define(name.to_owned(), Signature::BUILTIN));
+        hoist_funs(ast, &mut funs);
+
+        let res =
+            ast.iter()
+                .filter_map(|node| match &node.inner {
+                    PStatement::FunDec { name, args, .. } => {
+                        let key = cache_key(&node.span, self.resolve, &vars, &funs);
+                        let from = errors.len();
+
+                        let dec =
+                            match self.cache.get(&key) {
+                                Some(dec) => declare_fun(&mut funs, name, args, &node.span)
+                                    .map(|_| dec.clone()),
+                                None => convert_statement(node, &mut vars, &mut funs, &mut errors)
+                                    .map(|node| match node.inner {
+                                        Statement::FunDec(dec) => dec,
+                                        _ => unreachable!(),
+                                    }),
+                            };
+
+                        match dec {
+                            Ok(dec) => {
+                                // Undefined names inside are collected rather than returned
+                                if errors.len() == from {
+                                    cache.insert(key, dec.clone());
+                                }
+
+                                Some(AyNode {
+                                    span: node.span.clone(),
+                                    inner: Statement::FunDec(dec),
+                                })
+                            }
+                            Err(trace) => {
+                                errors.push(trace);
+
+                                // Still callable, so that calls to it are not reported as well
+                                if !funs.contains_key_at_top(name)
+                                    || funs.get(name).and_then(|sig| sig.span.as_ref())
+                                        == Some(&node.span)
+                                {
+                                    funs.define(name.clone(), Signature::BUILTIN);
+                                }
+                                None
+                            }
+                        }
+                    }
+                    _ => convert_statement(node, &mut vars, &mut funs, &mut errors)
+                        .map_err(|trace| errors.push(trace))
+                        .ok(),
                 })
-            } else {
-                Err(Trace::new(
-                    Stage::Binding,
-                    Error::from_span(
-                        span.clone(),
-                        format!("Undefined variable: '{name}'{}", closest(vars, name)).as_ref(),
-                    ),
-                ))
+                .collect::<Vec<AyNode<Statement>>>();
+
+        // Only a program that bound entirely is known to have been checked in full
+        if errors.is_empty() {
+            self.cache = cache;
+        }
+        self.vars.clear_all();
+        self.funs.clear_all();
+        self.resolutions = None;
+
+        if !errors.is_empty() {
+            errors.sort_by_key(|trace| {
+                trace.frames().next().map(|(_, err)| match err.line_col() {
+                    LineColLocation::Pos(start) | LineColLocation::Span(start, _) => start,
+                })
+            });
+            return Err(errors);
+        }
+
+        Ok(res).inspect(|ast| {
+            ast.iter().for_each(|node| match &node.inner {
+                Statement::VarDec(dec) => {
+                    let dec = Rc::new(dec.clone());
+                    dec.names
+                        .iter()
+                        .for_each(|name| self.vars.define(name.clone(), dec.clone()));
+                }
+                Statement::FunDec(dec) => self.funs.define(dec.name.clone(), dec.clone()),
+                _ => {}
+            });
+
+            if self.resolve {
+                self.resolutions = Some(ResolutionTable::new(ast));
             }
+        })
+    }
+
+    /// Uses of names in the last program, if it bound and the binder was made
+    /// [`with_resolutions`](Binder::with_resolutions).
+    pub fn resolutions(&self) -> Option<&ResolutionTable> {
+        self.resolutions.as_ref()
+    }
+
+    /// Declaration of the top-level variable `name` from the last run.
+    pub fn resolve_var(&self, name: &str) -> Option<&Rc<VarDec>> {
+        self.vars.get(name)
+    }
+
+    /// Top-level function called by `name` in the last run, along with the tense of the call.
+    pub fn resolve_fun(&self, name: &str) -> Option<(Tense, Rc<FunDec>)> {
+        self.funs
+            .resolve(name)
+            .and_then(|(tense, key)| self.funs.get(&key).map(|dec| (tense, dec.clone())))
+    }
+}
+
+/// Binds a program piece by piece, e.g. line by line in the REPL, each piece seeing the names
+/// declared by the previous ones.
+#[derive(Clone)]
+pub struct Session {
+    vars: ScopeMap<String, ()>,
+    funs: FunScope<Signature>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        let mut funs = FunScope::default();
+        builtins::names().for_each(|name| funs.define(name.to_owned(), Signature::BUILTIN));
+
+        Self {
+            vars: ScopeMap::new(),
+            funs,
         }
-        PExpr::FunCall { name, args } => match match_function(name, funs) {
-            Some(tense) => Ok(AyNode {
-                span: span.clone(),
-                inner: Expr::FunCall {
-                    tense,
-                    name: name.clone(),
-                    args: convert_iter!(expr args | vars funs)?,
-                },
-            }),
-            None => Err(Trace::new(
-                Stage::Binding,
-                Error::from_span(
-                    span.clone(),
-                    format!("Undefined function: '{name}'{}", closest(funs, name)).as_ref(),
-                ),
-            )),
-        },
-        PExpr::Number(num) => Ok(AyNode {
-            span: span.clone(),
-            inner: Expr::Number(*num),
-        }),
-        PExpr::String(string) => Ok(AyNode {
-            span: span.clone(),
-            inner: Expr::String(string.clone()),
-        }),
-        PExpr::Negated(expr) => Ok(AyNode {
-            span: span.clone(),
-            inner: Expr::Negated(Box::new(convert_expr(expr, vars, funs)?)),
-        }),
-        PExpr::Comparison {
-            left,
-            right,
-            operator,
-        } => Ok(AyNode {
-            span: span.clone(),
-            inner: Expr::Comparison {
-                left: Box::new(convert_expr(left, vars, funs)?),
-                right: Box::new(convert_expr(right, vars, funs)?),
-                operator: operator.clone(),
-            },
-        }),
-        PExpr::Array { items } => Ok(AyNode {
-            span: span.clone(),
-            inner: Expr::Array {
-                items: convert_iter!(expr items | vars funs)?,
-            },
-        }),
     }
 }
 
-fn closest<T>(scope_map: &ScopeMap<String, T>, name: &str) -> String {
-    scope_map
-        .keys()
-        .map(|key| (key, distance::levenshtein(name, key)))
-        .min_by(|(_, d1), (_, d2)| usize::cmp(d1, d2))
-        .filter(|(key, dist)| *dist * 2 < key.len())
-        .map(|(key, _)| format!(". Maybe you meant: '{}'?", key.replace('.', "")))
-        .unwrap_or_else(|| "".to_owned())
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds the next piece of the program, whose declarations are only kept if it binds.
+    pub fn convert(&mut self, ast: &[AyNode<PStatement>]) -> Result<Vec<AyNode<Statement>>, Trace> {
+        check_loop_controls(ast, &mut vec![])?;
+
+        let mut vars = self.vars.clone();
+        let mut funs = self.funs.clone();
+        let mut errors = vec![];
+
+        hoist_funs(ast, &mut funs);
+
+        let res = ast
+            .iter()
+            .map(|node| convert_statement(node, &mut vars, &mut funs, &mut errors))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(trace) = errors.into_iter().next() {
+            return Err(trace);
+        }
+
+        self.vars = vars;
+        self.funs = funs;
+
+        Ok(res)
+    }
+}
+
+/// Declaration a name is bound to, see [`ResolutionTable`].
+#[derive(Debug, Clone)]
+pub enum Declaration {
+    Var(Rc<VarDec>),
+    /// Parameter of the function.
+    Param(Rc<FunDec>),
+    Fun(Rc<FunDec>),
+}
+
+/// Declaration a use of a name is bound to, along with the span of the statement declaring it.
+#[derive(Debug, Clone)]
+pub struct Resolution {
+    pub span: Span,
+    pub declaration: Declaration,
+}
+
+/// Declarations the variables and function calls of a bound program refer to, by where they are
+/// used, e.g. for go-to-definition.
+///
+/// Calls to builtins are left out, as well as assignments, whose names have no span of their own.
+#[derive(Debug, Default)]
+pub struct ResolutionTable {
+    uses: Vec<(Span, Resolution)>,
+}
+
+impl ResolutionTable {
+    /// Follows the scoping of the binder: functions are visible from the start of their block and
+    /// variables once declared, the values of a declaration still seeing the names it shadows.
+    pub fn new(ast: &[AyNode<Statement>]) -> Self {
+        let mut resolver = Resolver::default();
+        resolver.block(ast);
+
+        Self {
+            uses: resolver.uses,
+        }
+    }
+
+    /// Declaration of the innermost use of a name found at `line` and `col`, both starting at 1.
+    pub fn resolve_at(&self, line: usize, col: usize) -> Option<&Resolution> {
+        self.uses
+            .iter()
+            .filter(|(span, _)| span.start() <= (line, col) && (line, col) < span.end())
+            .max_by_key(|(span, _)| (span.start(), std::cmp::Reverse(span.end())))
+            .map(|(_, resolution)| resolution)
+    }
+
+    /// Every use of a name along with its declaration, in source order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Span, &Resolution)> {
+        self.uses
+            .iter()
+            .map(|(span, resolution)| (span, resolution))
+    }
 }
 
-fn match_function(name: &str, funs: &ScopeMap<String, ()>) -> Option<Tense> {
-    funs.iter().find_map(|(key, fun)| {
-        if key.contains('.') {
-            let (left, right) = key.split_once('.').unwrap();
+#[derive(Default)]
+struct Resolver {
+    vars: ScopeMap<String, Resolution>,
+    funs: ScopeMap<String, Resolution>,
+    uses: Vec<(Span, Resolution)>,
+}
 
-            if format!("{left}{right}") == name {
-                Some(Tense::Present)
-            } else if format!("{left}ìy{right}") == name {
-                Some(Tense::Imminent)
-            } else if format!("{left}ay{right}") == name {
-                Some(Tense::Future)
-            } else {
-                None
+impl Resolver {
+    /// Functions declared in `stmts`, visible from anywhere in their block.
+    fn hoist(&mut self, stmts: &[AyNode<Statement>]) {
+        for AyNode { span, inner } in stmts {
+            if let Statement::FunDec(dec) = inner {
+                self.funs.define(
+                    dec.name.clone(),
+                    Resolution {
+                        span: span.clone(),
+                        declaration: Declaration::Fun(dec.clone()),
+                    },
+                );
             }
-        } else {
-            (key == name).then(|| Tense::Present)
         }
-    })
+    }
+
+    fn block(&mut self, stmts: &[AyNode<Statement>]) {
+        self.vars.push_layer();
+        self.funs.push_layer();
+
+        self.hoist(stmts);
+        walk(stmts, self);
+
+        self.vars.pop_layer();
+        self.funs.pop_layer();
+    }
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+impl Visitor<'_> for Resolver {
+    fn visit_statement(&mut self, node: &AyNode<Statement>) {
+        match &node.inner {
+            Statement::VarDec(dec) => {
+                dec.values.iter().for_each(|value| self.visit_expr(value));
 
-    #[test]
-    /// Test:
-    /// ````
-    /// fn scope {
-    ///    fn t.aron {}
-    ///    taron()   // valid
-    ///    tìyaron() // valid
-    ///    tayaron() // valid
-    /// }
-    /// taron() -- invalid
-    /// ````
-    fn test_match_function() {
-        let mut funs = ScopeMap::<String, ()>::new();
+                let resolution = Resolution {
+                    span: node.span.clone(),
+                    declaration: Declaration::Var(Rc::new(dec.clone())),
+                };
+                dec.names
+                    .iter()
+                    .for_each(|name| self.vars.define(name.clone(), resolution.clone()));
+            }
+            // Helpers share the scope of the body, coming first
+            Statement::FunDec(dec) => {
+                self.vars.push_layer();
+                self.funs.push_layer();
 
-        wrap_scope!(
-            funs | {
-                funs.define("scope".to_owned(), ());
+                let resolution = Resolution {
+                    span: node.span.clone(),
+                    declaration: Declaration::Param(dec.clone()),
+                };
+                dec.args
+                    .iter()
+                    .for_each(|arg| self.vars.define(arg.clone(), resolution.clone()));
+                self.hoist(&dec.helpers);
+                self.hoist(&dec.body);
+                walk_statement(self, node);
 
-                wrap_scope!(
-                    funs | {
-                        funs.define("t.aron".to_owned(), ());
+                self.vars.pop_layer();
+                self.funs.pop_layer();
+            }
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                self.visit_expr(cond);
+                self.block(then);
+                self.block(otherwise);
+            }
+            Statement::Loop { cond, body, .. } => {
+                cond.iter().for_each(|cond| self.visit_expr(cond));
+                self.block(body);
+            }
+            _ => walk_statement(self, node),
+        }
+    }
 
-                        vec!["taron", "tìyaron", "tayaron"]
-                            .iter()
-                            .map(|name| (name, match_function(name, &funs)))
-                            .for_each(|(name, res)| {
-                                assert!(res.is_some(), "Function not found: '{}'", name)
-                            });
+    fn visit_expr(&mut self, node: &AyNode<Expr>) {
+        let resolution = match &node.inner {
+            Expr::Var(name) => self.vars.get(name),
+            Expr::FunCall { name, .. } => self.funs.get(name),
+            _ => None,
+        };
+
+        if let Some(resolution) = resolution {
+            self.uses.push((node.span.clone(), resolution.clone()));
+        }
+        walk_expr(self, node)
+    }
+}
+
+/// Makes sure every `hum` and `kä` is within a loop, with the label they target if any.
+///
+/// `loops` holds the labels of the enclosing loops of the current function, innermost last.
+fn check_loop_controls(
+    ast: &[AyNode<PStatement>],
+    loops: &mut Vec<Option<String>>,
+) -> Result<(), Trace> {
+    ast.iter()
+        .try_for_each(|AyNode { span, inner }| match inner {
+            PStatement::Break(label) | PStatement::Continue(label) => {
+                let message = match label {
+                    None if loops.is_empty() => {
+                        "Cannot leave or continue outside of a loop".to_owned()
                     }
-                );
+                    Some(label) if !loops.contains(&Some(label.clone())) => {
+                        format!("No enclosing loop named '{label}'")
+                    }
+                    _ => return Ok(()),
+                };
 
-                assert!(match_function("taron", &funs).is_none());
+                Err(Trace::new(
+                    Stage::Binding,
+                    Error::from_span(span.clone(), message.as_ref()),
+                ))
+            }
+            PStatement::Loop { body, label, .. } => {
+                loops.push(label.clone());
+                let res = check_loop_controls(body, loops);
+                loops.pop();
+                res
+            }
+            PStatement::If {
+                then, otherwise, ..
+            } => {
+                check_loop_controls(then, loops)?;
+                check_loop_controls(otherwise, loops)
             }
+            // Loops do not extend into the functions declared inside of them
+            PStatement::FunDec { body, helpers, .. } => {
+                check_loop_controls(helpers, &mut vec![])?;
+                check_loop_controls(body, &mut vec![])
+            }
+            PStatement::VarDec { .. } | PStatement::Expr(_) | PStatement::Assert(_) => Ok(()),
+        })
+}
+
+fn cache_key(
+    span: &Span,
+    located: bool,
+    vars: &ScopeMap<String, ()>,
+    funs: &FunScope<Signature>,
+) -> CacheKey {
+    // Arities are part of the key for calls to be checked again when one changes
+    let mut names = vars
+        .keys()
+        .cloned()
+        .chain(
+            funs.iter()
+                .map(|(name, Signature { arity, .. })| format!("{name}/{arity:?}")),
+        )
+        .collect::<Vec<_>>();
+    names.sort();
+
+    (
+        span.as_str().to_owned(),
+        located.then(|| span.start()),
+        names,
+    )
+}
+
+pub fn convert(ast: &[AyNode<PStatement>]) -> Result<Vec<AyNode<Statement>>, Trace> {
+    Binder::new().convert(ast)
+}
+
+pub fn convert_all(ast: &[AyNode<PStatement>]) -> Result<Vec<AyNode<Statement>>, Vec<Trace>> {
+    Binder::new().convert_all(ast)
+}
+
+/// Binds a lone expression, with only the builtins in scope.
+pub fn convert_standalone_expr(expr: &AyNode<PExpr>) -> Result<AyNode<Expr>, Trace> {
+    let mut funs = FunScope::default();
+    builtins::names().for_each(|name| funs.define(name.to_owned(), Signature::BUILTIN));
+
+    let mut errors = vec![];
+    let res = convert_expr(expr, &mut ScopeMap::new(), &mut funs, &mut errors)?;
+
+    match errors.into_iter().next() {
+        Some(trace) => Err(trace),
+        None => Ok(res),
+    }
+}
+
+/// Adds `note` as a frame to the errors raised while binding the construct at `span`, the
+/// returned one as well as those collected along the way.
+fn in_context<T>(
+    res: Result<T, Trace>,
+    errors: &mut [Trace],
+    span: &Span,
+    note: String,
+) -> Result<T, Trace> {
+    errors
+        .iter_mut()
+        .for_each(|trace| trace.push_context(Stage::Binding, span.clone(), note.clone()));
+
+    res.map_err(|mut trace| {
+        trace.push_context(Stage::Binding, span.clone(), note);
+        trace
+    })
+}
+
+fn convert_statement(
+    node: &AyNode<PStatement>,
+    vars: &mut ScopeMap<String, ()>,
+    funs: &mut FunScope<Signature>,
+    errors: &mut Vec<Trace>,
+) -> Result<AyNode<Statement>, Trace> {
+    let span = &node.span;
+
+    node.as_ref().try_map(|inner| match inner {
+        PStatement::VarDec { names, values } => {
+            number_names(names, span)?;
+
+            // Names are bound simultaneously, values only seeing what was there before. New ones
+            // are known while binding the values for reads of them not to pass for typos.
+            let fresh = names
+                .iter()
+                .filter(|name| vars.get(*name).is_none())
+                .cloned()
+                .collect::<Vec<_>>();
+            let values = wrap_scope!(
+                vars | {
+                    fresh.iter().for_each(|name| vars.define(name.clone(), ()));
+                    convert_iter!(expr values | vars funs errors)
+                }
+            )?;
+            self_references(&fresh, &values)?;
+
+            names.iter().for_each(|name| vars.define(name.clone(), ()));
+
+            Ok(Statement::VarDec(VarDec {
+                names: names.clone(),
+                values,
+            }))
+        }
+        PStatement::FunDec {
+            name,
+            args,
+            body,
+            helpers,
+        } => {
+            if let Some(arg) = args
+                .iter()
+                .enumerate()
+                .find_map(|(index, arg)| args[..index].contains(arg).then_some(arg))
+            {
+                return Err(Trace::new(
+                    Stage::Binding,
+                    Error::from_span(
+                        span.clone(),
+                        format!("Duplicate parameter '{arg}' in declaration of '{name}'").as_ref(),
+                    ),
+                ));
+            }
+
+            number_names(args, span)?;
+
+            if !valid_fun_name(name) {
+                return Err(Trace::new(
+                    Stage::Binding,
+                    Error::from_span(
+                        span.clone(),
+                        format!(
+                            "Invalid function name '{name}', a single `.` between two parts of \
+                             the name marks where the tense infix goes"
+                        )
+                        .as_ref(),
+                    ),
+                ));
+            }
+
+            declare_fun(funs, name, args, span)?;
+
+            let from = errors.len();
+
+            // Helpers come after the body but have to be bound first for it to see them
+            let bodies = wrap_scope!(
+                vars,
+                funs | {
+                    args.iter().for_each(|var| vars.define(var.clone(), ()));
+                    hoist_funs(helpers, funs);
+                    hoist_funs(body, funs);
+                    convert_iter!(statement helpers | vars funs errors).and_then(|helpers| {
+                        Ok((helpers, convert_iter!(statement body | vars funs errors)?))
+                    })
+                }
+            );
+            let (helpers, body) = in_context(
+                bodies,
+                &mut errors[from..],
+                span,
+                format!("While binding the body of '{name}'"),
+            )?;
+
+            Ok(Statement::FunDec(Rc::new(FunDec {
+                name: name.clone(),
+                args: args.clone(),
+                body,
+                helpers,
+            })))
+        }
+        PStatement::If {
+            cond,
+            then,
+            otherwise,
+        } => {
+            let cond = convert_expr(cond, vars, funs, errors)?;
+            let from = errors.len();
+
+            let branches = wrap_scope!(
+                vars,
+                funs | {
+                    hoist_funs(then, funs);
+                    convert_iter!(statement then | vars funs errors)
+                }
+            )
+            .and_then(|then| {
+                let otherwise = wrap_scope!(
+                    vars,
+                    funs | {
+                        hoist_funs(otherwise, funs);
+                        convert_iter!(statement otherwise | vars funs errors)
+                    }
+                )?;
+                Ok((then, otherwise))
+            });
+            let (then, otherwise) = in_context(
+                branches,
+                &mut errors[from..],
+                span,
+                "While binding the branches of a condition".to_owned(),
+            )?;
+
+            Ok(Statement::If {
+                cond,
+                then,
+                otherwise,
+            })
+        }
+        PStatement::Loop { cond, body, label } => {
+            let cond = cond
+                .as_ref()
+                .map(|cond| convert_expr(cond, vars, funs, errors))
+                .transpose()?;
+
+            // Only literals are known not to be truth values before typing
+            if let Some((cond, ty)) = cond
+                .as_ref()
+                .and_then(|cond| literal_type(&cond.inner).map(|ty| (cond, ty)))
+            {
+                return Err(Trace::new(
+                    Stage::Binding,
+                    Error::from_span(
+                        cond.span.clone(),
+                        format!(
+                            "Loop condition is a {ty} literal, expected `srane`, `kehe` or a \
+                             comparison"
+                        )
+                        .as_ref(),
+                    ),
+                ));
+            }
+
+            let from = errors.len();
+            let body = wrap_scope!(
+                vars,
+                funs | {
+                    hoist_funs(body, funs);
+                    convert_iter!(statement body | vars funs errors)
+                }
+            );
+            let note = match label {
+                Some(label) => format!("While binding the body of loop '{label}'"),
+                None => "While binding the body of a loop".to_owned(),
+            };
+
+            Ok(Statement::Loop {
+                cond,
+                body: in_context(body, &mut errors[from..], span, note)?,
+                label: label.clone(),
+            })
+        }
+        PStatement::Break(label) => Ok(Statement::Break(label.clone())),
+        PStatement::Continue(label) => Ok(Statement::Continue(label.clone())),
+        PStatement::Expr(expr) => Ok(Statement::Expr(convert_expr(expr, vars, funs, errors)?)),
+        PStatement::Assert(cond) => {
+            let cond = convert_expr(cond, vars, funs, errors)?;
+
+            if constant_truth(&cond.inner) == Some(false) {
+                return Err(Trace::new(
+                    Stage::Binding,
+                    Error::from_span(cond.span, "Assertion can never hold"),
+                ));
+            }
+
+            Ok(Statement::Assert(cond))
+        }
+    })
+}
+
+/// Declares the functions of a block ahead of its statements, for them to be callable before
+/// their declaration and from each other.
+///
+/// Only the first of several declarations sharing a name is hoisted, the others being reported
+/// when reached.
+fn hoist_funs(stmts: &[AyNode<PStatement>], funs: &mut FunScope<Signature>) {
+    stmts.iter().for_each(|AyNode { span, inner }| {
+        if let PStatement::FunDec { name, args, .. } = inner {
+            let _ = declare_fun(funs, name, args, span);
+        }
+    })
+}
+
+/// Rejects values reading one of the `names` their declaration introduces, which only exist once
+/// all of its values are known.
+fn self_references(names: &[String], values: &[AyNode<Expr>]) -> Result<(), Trace> {
+    struct Reads<'a>(&'a [String], Option<&'a AyNode<Expr>>);
+
+    impl<'a> Visitor<'a> for Reads<'a> {
+        fn visit_expr(&mut self, node: &'a AyNode<Expr>) {
+            match &node.inner {
+                Expr::Var(name) if self.0.contains(name) => {
+                    self.1.get_or_insert(node);
+                }
+                _ => walk_expr(self, node),
+            }
+        }
+    }
+
+    let mut reads = Reads(names, None);
+    values.iter().for_each(|value| reads.visit_expr(value));
+
+    match reads.1 {
+        Some(AyNode {
+            span,
+            inner: Expr::Var(name),
+        }) => Err(Trace::new(
+            Stage::Binding,
+            Error::from_span(
+                span.clone(),
+                format!(
+                    "Variable '{name}' is read in its own declaration, names are only bound once \
+                     all values are"
+                )
+                .as_ref(),
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Brings a function into scope, unless one with the same name was declared in the same scope.
+/// Builtins and declarations from enclosing scopes may be shadowed.
+fn declare_fun(
+    funs: &mut FunScope<Signature>,
+    name: &str,
+    args: &[String],
+    span: &Span,
+) -> Result<(), Trace> {
+    if let Some(Signature {
+        span: Some(original),
+        ..
+    }) = funs
+        .get(name)
+        .filter(|_| funs.contains_key_at_top(name))
+        // Hoisted by `hoist_funs`
+        .filter(|signature| signature.span.as_ref() != Some(span))
+    {
+        let mut trace = Trace::new(
+            Stage::Binding,
+            Error::from_span(
+                span.clone(),
+                format!("Function '{name}' is already declared in this scope").as_ref(),
+            ),
+        );
+        trace.push(
+            Stage::Binding,
+            Error::from_span(
+                original.clone(),
+                format!("'{name}' first declared here").as_ref(),
+            ),
+        );
+
+        return Err(trace);
+    }
+
+    funs.define(
+        name.to_owned(),
+        Signature {
+            arity: Some(args.len()),
+            span: Some(span.clone()),
+        },
+    );
+
+    Ok(())
+}
+
+/// Evaluates conditions that only depend on literals.
+fn constant_truth(expr: &Expr) -> Option<bool> {
+    fn constant(expr: &Expr) -> Option<Expr> {
+        match expr {
+            Expr::Number(_) | Expr::Float(_) | Expr::String(_) | Expr::Bool(_) => {
+                Some(expr.clone())
+            }
+            Expr::Negated(inner) => match constant(&inner.inner)? {
+                Expr::Number(number) => Some(Expr::Number(-number)),
+                Expr::Float(number) => Some(Expr::Float(-number)),
+                Expr::Bool(value) => Some(Expr::Bool(!value)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    match expr {
+        Expr::Comparison {
+            left,
+            right,
+            operator: ComparisonOperator::Equals,
+        } => Some(constant(&left.inner)? == constant(&right.inner)?),
+        expr => match constant(expr)? {
+            Expr::Bool(value) => Some(value),
+            _ => None,
+        },
+    }
+}
+
+fn convert_expr(
+    node: &AyNode<PExpr>,
+    vars: &mut ScopeMap<String, ()>,
+    funs: &mut FunScope<Signature>,
+    errors: &mut Vec<Trace>,
+) -> Result<AyNode<Expr>, Trace> {
+    let span = &node.span;
+
+    node.as_ref().try_map(|inner| match inner {
+        PExpr::Ident(name) => {
+            if vars.get(name).is_none() {
+                errors.push(Trace::new(
+                    Stage::Binding,
+                    Error::from_span(
+                        span.clone(),
+                        format!("Undefined variable: '{name}'{}", closest(vars, name)).as_ref(),
+                    ),
+                ));
+            }
+
+            // Bound regardless, the result is dropped anyway if anything was reported
+            Ok(Expr::Var(name.clone()))
+        }
+        PExpr::FunCall { name: call, args } => match funs.resolve(call) {
+            Some((tense, name)) => {
+                // Every tense resolves to the same declaration, hence the same arity
+                if let Some(Signature {
+                    arity: Some(arity), ..
+                }) = funs.get(&name)
+                {
+                    if *arity != args.len() {
+                        return Err(Trace::new(
+                            Stage::Binding,
+                            Error::from_span(
+                                span.clone(),
+                                format!("'{call}' expects {arity} arguments, found {}", args.len())
+                                    .as_ref(),
+                            ),
+                        ));
+                    }
+                }
+
+                Ok(Expr::FunCall {
+                    tense,
+                    name,
+                    args: convert_iter!(expr args | vars funs errors)?,
+                })
+            }
+            // Through a variable, which holds a function once typed
+            None if vars.get(call).is_some() => Ok(Expr::FunCall {
+                tense: Tense::Present,
+                name: call.clone(),
+                args: convert_iter!(expr args | vars funs errors)?,
+            }),
+            None => {
+                let hint = match closest(funs, call) {
+                    hint if hint.is_empty() => declared(funs, call),
+                    hint => hint,
+                };
+
+                errors.push(Trace::new(
+                    Stage::Binding,
+                    Error::from_span(
+                        span.clone(),
+                        format!("Undefined function: '{call}'{hint}").as_ref(),
+                    ),
+                ));
+
+                // Arguments are still checked, without an arity to compare them to
+                Ok(Expr::FunCall {
+                    tense: Tense::Present,
+                    name: call.clone(),
+                    args: convert_iter!(expr args | vars funs errors)?,
+                })
+            }
+        },
+        PExpr::Number(num) => Ok(Expr::Number(*num)),
+        PExpr::Float(num) => Ok(Expr::Float(*num)),
+        PExpr::String(string) => Ok(Expr::String(string.clone())),
+        PExpr::Bool(value) => Ok(Expr::Bool(*value)),
+        PExpr::Negated(expr) => Ok(Expr::Negated(Box::new(convert_expr(
+            expr, vars, funs, errors,
+        )?))),
+        PExpr::Comparison {
+            left,
+            right,
+            operator,
+        } => Ok(Expr::Comparison {
+            left: Box::new(convert_expr(left, vars, funs, errors)?),
+            right: Box::new(convert_expr(right, vars, funs, errors)?),
+            operator: operator.clone(),
+        }),
+        PExpr::BinOp { op, left, right } => Ok(Expr::BinOp {
+            op: *op,
+            left: Box::new(convert_expr(left, vars, funs, errors)?),
+            right: Box::new(convert_expr(right, vars, funs, errors)?),
+        }),
+        PExpr::Array { items } => Ok(Expr::Array {
+            items: convert_iter!(expr items | vars funs errors)?,
+        }),
+        PExpr::None => Ok(Expr::None),
+        PExpr::HasValue(expr) => Ok(Expr::HasValue(Box::new(convert_expr(
+            expr, vars, funs, errors,
+        )?))),
+        PExpr::Fallback { value, fallback } => Ok(Expr::Fallback {
+            value: Box::new(convert_expr(value, vars, funs, errors)?),
+            fallback: Box::new(convert_expr(fallback, vars, funs, errors)?),
+        }),
+        PExpr::Index { value, index } => Ok(Expr::Index {
+            value: Box::new(convert_expr(value, vars, funs, errors)?),
+            index: Box::new(convert_expr(index, vars, funs, errors)?),
+        }),
+        PExpr::Field { value, name } => Ok(Expr::Field {
+            value: Box::new(convert_expr(value, vars, funs, errors)?),
+            name: name.clone(),
+        }),
+        PExpr::FunRef(name) => Ok(Expr::FunRef(name.clone())),
+    })
+}
+
+fn closest<T>(scope_map: &ScopeMap<String, T>, name: &str) -> String {
+    scope_map
+        .keys()
+        .map(|key| (key, distance::levenshtein(name, key)))
+        .min_by(|(_, d1), (_, d2)| usize::cmp(d1, d2))
+        .filter(|(key, dist)| *dist * 2 < key.len())
+        .map(|(key, _)| format!(". Maybe you meant: '{}'?", key.replace('.', "")))
+        .unwrap_or_else(|| "".to_owned())
+}
+
+/// Lists the functions declared by the program nearest to `name` first, for calls too far off
+/// from all of them for `closest` to pick one.
+fn declared<T>(funs: &ScopeMap<String, T>, name: &str) -> String {
+    let mut candidates = funs
+        .keys()
+        .filter(|key| !builtins::names().any(|builtin| builtin == key.as_str()))
+        .map(|key| key.replace('.', ""))
+        .map(|key| (distance::levenshtein(name, &key), key))
+        .collect::<Vec<_>>();
+
+    candidates.sort();
+    candidates.dedup();
+
+    match candidates.is_empty() {
+        true => "".to_owned(),
+        false => format!(
+            ". Declared functions: {}",
+            candidates
+                .iter()
+                .take(3)
+                .map(|(_, key)| format!("'{key}'"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Rejects variables named after a number word, which would be read as that number instead.
+fn number_names(names: &[String], span: &Span) -> Result<(), Trace> {
+    match names
+        .iter()
+        .find_map(|name| number_word(name).map(|value| (name, value)))
+    {
+        Some((name, value)) => Err(Trace::new(
+            Stage::Binding,
+            Error::from_span(
+                span.clone(),
+                format!("'{name}' is the number {value}, it cannot name a variable").as_ref(),
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Whether `name` is plain or has a single `.` inside of it, where the tense infix goes.
+///
+/// Always the case of parsed names, but not of trees built by hand.
+fn valid_fun_name(name: &str) -> bool {
+    match name.split_once('.') {
+        Some((left, right)) => !left.is_empty() && !right.is_empty() && !right.contains('.'),
+        None => !name.is_empty(),
+    }
+}
+
+/// How the function declared as `name` is called in `tense`, the infix going where the `.` is.
+fn spelling(name: &str, tense: &Tense) -> String {
+    match (name.split_once('.'), tense) {
+        (Some((left, right)), Tense::Present) => format!("{left}{right}"),
+        (Some((left, right)), Tense::Imminent) => format!("{left}ìy{right}"),
+        (Some((left, right)), Tense::Future) => format!("{left}ay{right}"),
+        (None, _) => name.to_owned(),
+    }
+}
+
+/// Every way of calling the function declared as `name`.
+fn spellings(name: &str) -> Vec<(Tense, String)> {
+    match name.contains('.') {
+        true => [Tense::Present, Tense::Imminent, Tense::Future]
+            .into_iter()
+            .map(|tense| {
+                let spelling = spelling(name, &tense);
+                (tense, spelling)
+            })
+            .collect(),
+        false => vec![(Tense::Present, name.to_owned())],
+    }
+}
+
+/// Indents every line of `text` by one level.
+pub(crate) fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("    {line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Statements of a block, one per line and chained with `ulte`.
+fn block(body: &[AyNode<Statement>]) -> String {
+    indent(
+        &body
+            .iter()
+            .map(|node| node.to_string())
+            .collect::<Vec<_>>()
+            .join(" ulte\n"),
+    )
+}
+
+/// Declarations written with the article matching their number of names.
+fn var_dec(f: &mut std::fmt::Formatter<'_>, dec: &VarDec) -> std::fmt::Result {
+    let article = match dec.names.len() {
+        1 => "'u",
+        2 => "meu",
+        3 => "pxeu",
+        _ => "ayu",
+    };
+
+    write!(
+        f,
+        "{article} {} alu {}",
+        dec.names.join(" sì "),
+        match dec.values.as_slice() {
+            [value] => value.to_string(),
+            values => values.iter().map(operand).collect::<Vec<_>>().join(" sì "),
+        }
+    )
+}
+
+/// Renders statements back into source, without the `.` ending top-level ones.
+/// Assignments have no source form and are rendered as declarations without `ngop`.
+impl std::fmt::Display for Statement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Statement::FunDec(dec) => {
+                write!(f, "ngop lì'ukìng {}", dec.name)?;
+                if !dec.args.is_empty() {
+                    write!(f, " fa {}", dec.args.join(" sì "))?;
+                }
+                if !dec.body.is_empty() {
+                    write!(f, " alu\n{}\ntxew", block(&dec.body))?;
+                }
+                if !dec.helpers.is_empty() {
+                    write!(f, " tsengit\n{}", block(&dec.helpers))?;
+                }
+                Ok(())
+            }
+            Statement::VarDec(dec) => {
+                write!(f, "ngop ")?;
+                var_dec(f, dec)
+            }
+            Statement::Assign(dec) => var_dec(f, dec),
+            Statement::Expr(expr) => write!(f, "{expr}"),
+            // A `txokefyaw` branch has no closing of its own
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => match otherwise.is_empty() {
+                true => write!(f, "txo {cond}, tsakrr\n{}\ntxew", block(then)),
+                false => write!(
+                    f,
+                    "txo {cond}, tsakrr\n{},\ntxokefyaw\n{}",
+                    block(then),
+                    block(otherwise)
+                ),
+            },
+            Statement::Loop { cond, body, label } => {
+                write!(f, "leyn")?;
+                if let Some(label) = label {
+                    write!(f, " tstxo {label}")?;
+                }
+                if let Some(cond) = cond {
+                    write!(f, " vaykrr {cond}")?;
+                }
+                write!(f, " kem si\n{}\nftang", block(body))
+            }
+            Statement::Break(label) | Statement::Continue(label) => {
+                let keyword = match self {
+                    Statement::Break(_) => "hum",
+                    _ => "kä",
+                };
+                match label {
+                    Some(label) => write!(f, "{keyword} tstxo {label}"),
+                    None => write!(f, "{keyword}"),
+                }
+            }
+            Statement::Assert(cond) => write!(f, "nìngay {cond}"),
+        }
+    }
+}
+
+impl Expr {
+    /// Whether the expression can be used as any operand as is, without swallowing what follows.
+    fn is_atom(&self) -> bool {
+        matches!(
+            self,
+            Expr::Number(_)
+                | Expr::Float(_)
+                | Expr::String(_)
+                | Expr::Bool(_)
+                | Expr::Var(_)
+                | Expr::None
+                | Expr::Index { .. }
+                | Expr::Field { .. }
+                | Expr::FunRef(_)
+        )
+    }
+
+    /// Binding power of operations, anything else not being an operation.
+    fn precedence(&self) -> Option<u8> {
+        match self {
+            Expr::BinOp {
+                op: BinaryOperator::Or,
+                ..
+            } => Some(0),
+            Expr::BinOp {
+                op: BinaryOperator::And,
+                ..
+            } => Some(1),
+            Expr::BinOp {
+                op: BinaryOperator::Concat,
+                ..
+            } => Some(2),
+            Expr::BinOp {
+                op: BinaryOperator::Add | BinaryOperator::Subtract,
+                ..
+            } => Some(3),
+            Expr::BinOp { .. } => Some(4),
+            _ => None,
+        }
+    }
+}
+
+/// Wraps `node` in parentheses unless it is an atom.
+fn operand(node: &AyNode<Expr>) -> String {
+    match node.inner.is_atom() {
+        true => node.to_string(),
+        false => format!("({node})"),
+    }
+}
+
+/// Renders expressions back into source, numbers in octal and calls in the tense they were made.
+/// Parentheses are only added where the expression would otherwise be read differently.
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list =
+            |items: &[AyNode<Expr>]| items.iter().map(operand).collect::<Vec<_>>().join(" sì ");
+
+        match self {
+            Expr::FunCall { tense, name, args } => match args.is_empty() {
+                true => write!(f, "{} si", spelling(name, tense)),
+                false => write!(f, "{} fa {}", spelling(name, tense), list(args)),
+            },
+            Expr::Array { items } => match items.is_empty() {
+                true => write!(f, "vezeykoyu"),
+                false => write!(f, "vezeykoyu {}", list(items)),
+            },
+            Expr::Comparison {
+                left,
+                right,
+                operator,
+            } => write!(
+                f,
+                "may' {} sì {} livu {}",
+                operand(left),
+                operand(right),
+                <&str>::from(operator)
+            ),
+            Expr::BinOp { op, left, right } => {
+                // Operations are left associative, a right one of the same precedence is grouped
+                let precedence = self.precedence();
+                let left = match left.inner.precedence() >= precedence {
+                    true => left.to_string(),
+                    false => operand(left),
+                };
+                let right = match right.inner.precedence() > precedence {
+                    true => right.to_string(),
+                    false => operand(right),
+                };
+
+                write!(f, "{left} {} {right}", <&str>::from(op))
+            }
+            Expr::Number(number) => write!(f, "{}", Value::Number(*number).display_octal()),
+            Expr::Float(number) => {
+                let number = Value::Float(*number).display_octal().to_string();
+                match number.contains('.') {
+                    true => write!(f, "{number}"),
+                    false => write!(f, "{number}.0"),
+                }
+            }
+            Expr::String(string) => write!(
+                f,
+                "san {} sìk",
+                string
+                    .replace('\\', "\\\\")
+                    .replace('\n', "\\n")
+                    .replace('\t', "\\t")
+            ),
+            Expr::Bool(true) => write!(f, "srane"),
+            Expr::Bool(false) => write!(f, "kehe"),
+            Expr::Var(name) => write!(f, "{name}"),
+            Expr::FunRef(name) => write!(f, "({name})"),
+            Expr::Negated(node) => write!(f, "ke {}", operand(node)),
+            Expr::None => write!(f, "kewu"),
+            Expr::HasValue(node) => write!(f, "srak {node}"),
+            Expr::Fallback { value, fallback } => write!(f, "{} fu {fallback}", operand(value)),
+            Expr::Index { value, index } => write!(f, "{value}[{index}]"),
+            Expr::Field { value, name } => write!(f, "{value}.{name}"),
+        }
+    }
+}
+
+/// Warns about conditions and loops with an empty body, which are most likely leftovers.
+///
+/// The body of a condition is its `tsakrr` branch, a condition that only has something to do
+/// otherwise being clearer once negated. An empty `txokefyaw` alone is the same as not having one
+/// and is fine.
+pub fn empty_bodies(ast: &[AyNode<Statement>]) -> Vec<Trace> {
+    fn statements(ast: &[AyNode<Statement>], res: &mut Vec<Trace>) {
+        let mut warn = |span: &Span, message: &str| {
+            res.push(
+                Trace::new(Stage::Binding, Error::from_span(span.clone(), message))
+                    .with_severity(Severity::Warning),
+            )
+        };
+
+        for AyNode { span, inner } in ast {
+            match inner {
+                Statement::If {
+                    then, otherwise, ..
+                } if then.is_empty() && otherwise.is_empty() => warn(
+                    span,
+                    "Condition has no effect, both of its branches are empty",
+                ),
+                Statement::If { then, .. } if then.is_empty() => warn(
+                    span,
+                    "Condition has an empty `tsakrr` branch, negate it to only keep the \
+                     `txokefyaw` one",
+                ),
+                Statement::Loop {
+                    cond: None, body, ..
+                } if body.is_empty() => warn(
+                    span,
+                    "Loop without a condition and with an empty body never ends and does nothing",
+                ),
+                Statement::Loop { body, .. } if body.is_empty() => {
+                    warn(span, "Loop has an empty body")
+                }
+                _ => {}
+            }
+        }
+
+        ast.iter().for_each(|node| match &node.inner {
+            Statement::FunDec(dec) => {
+                statements(&dec.helpers, res);
+                statements(&dec.body, res);
+            }
+            Statement::If {
+                then, otherwise, ..
+            } => {
+                statements(then, res);
+                statements(otherwise, res);
+            }
+            Statement::Loop { body, .. } => statements(body, res),
+            _ => {}
+        })
+    }
+
+    let mut res = vec![];
+    statements(ast, &mut res);
+    res
+}
+
+/// Type of `expr` if it is a literal of another type than `Bool`.
+fn literal_type(expr: &Expr) -> Option<&'static str> {
+    match expr {
+        Expr::Number(_) => Some("Number"),
+        Expr::Float(_) => Some("Float"),
+        Expr::String(_) => Some("String"),
+        Expr::Array { .. } => Some("Array"),
+        Expr::None => Some("None"),
+        _ => None,
+    }
+}
+
+/// Warns about conditions that are literals of another type than `Bool`, such as `txo 1`, which
+/// only work because of how values are made truthy. Such loop conditions are rejected by binding.
+pub fn non_boolean_conditions(ast: &[AyNode<Statement>]) -> Vec<Trace> {
+    fn statements(ast: &[AyNode<Statement>], res: &mut Vec<Trace>) {
+        ast.iter().for_each(|node| match &node.inner {
+            Statement::FunDec(dec) => {
+                statements(&dec.helpers, res);
+                statements(&dec.body, res);
+            }
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                check(cond, res);
+                statements(then, res);
+                statements(otherwise, res);
+            }
+            Statement::Loop { body, .. } => statements(body, res),
+            _ => {}
+        })
+    }
+
+    fn check(AyNode { span, inner }: &AyNode<Expr>, res: &mut Vec<Trace>) {
+        if let Some(ty) = literal_type(inner) {
+            res.push(
+                Trace::new(
+                    Stage::Binding,
+                    Error::from_span(
+                        span.clone(),
+                        format!("Condition is a {ty} literal, expected `srane` or `kehe`").as_ref(),
+                    ),
+                )
+                .with_severity(Severity::Warning),
+            )
+        }
+    }
+
+    let mut res = vec![];
+    statements(ast, &mut res);
+    res
+}
+
+/// Warns about variables that are never read in their scope, reads from nested functions included.
+/// Parameters are left alone, as the caller decides what they hold.
+pub fn unused_variables(ast: &[AyNode<Statement>]) -> Vec<Trace> {
+    /// Declaration of the variables in scope and whether they were read, `None` for parameters.
+    type Vars = ScopeMap<String, Option<(Span, bool)>>;
+
+    fn expr(AyNode { inner, .. }: &AyNode<Expr>, vars: &mut Vars) {
+        match inner {
+            Expr::Var(name) => {
+                if let Some(Some((_, used))) = vars.get_mut(name) {
+                    *used = true;
+                }
+            }
+            // Calls may go through a variable
+            Expr::FunCall { name, args, .. } => {
+                if let Some(Some((_, used))) = vars.get_mut(name) {
+                    *used = true;
+                }
+                args.iter().for_each(|arg| expr(arg, vars))
+            }
+            Expr::Array { items } => items.iter().for_each(|item| expr(item, vars)),
+            Expr::Comparison { left, right, .. }
+            | Expr::BinOp { left, right, .. }
+            | Expr::Fallback {
+                value: left,
+                fallback: right,
+            }
+            | Expr::Index {
+                value: left,
+                index: right,
+            } => {
+                expr(left, vars);
+                expr(right, vars);
+            }
+            Expr::Negated(node) | Expr::HasValue(node) | Expr::Field { value: node, .. } => {
+                expr(node, vars)
+            }
+            Expr::Number(_)
+            | Expr::Float(_)
+            | Expr::String(_)
+            | Expr::Bool(_)
+            | Expr::None
+            | Expr::FunRef(_) => {}
+        }
+    }
+
+    fn warn(name: &str, var: Option<&Option<(Span, bool)>>, res: &mut Vec<Trace>) {
+        if let Some(Some((span, false))) = var {
+            res.push(
+                Trace::new(
+                    Stage::Binding,
+                    Error::from_span(span.clone(), &format!("Variable '{name}' is never used")),
+                )
+                .with_severity(Severity::Warning),
+            )
+        }
+    }
+
+    fn scope<'a>(
+        stmts: impl Iterator<Item = &'a AyNode<Statement>>,
+        params: &[String],
+        vars: &mut Vars,
+        res: &mut Vec<Trace>,
+    ) {
+        vars.push_layer();
+        params
+            .iter()
+            .for_each(|param| vars.define(param.clone(), None));
+
+        stmts.for_each(|node| statement(node, vars, res));
+
+        let mut unused = vars
+            .iter_top()
+            .filter_map(|(name, var)| match var {
+                Some((span, false)) => Some((span.start(), name.clone(), var.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        unused.sort_by_key(|(start, ..)| *start);
+        unused
+            .iter()
+            .for_each(|(_, name, var)| warn(name, Some(var), res));
+
+        vars.pop_layer();
+    }
+
+    fn statement(
+        AyNode { span, inner }: &AyNode<Statement>,
+        vars: &mut Vars,
+        res: &mut Vec<Trace>,
+    ) {
+        match inner {
+            Statement::VarDec(VarDec { names, values }) => {
+                // Values are read before the names they are assigned to exist
+                values.iter().for_each(|value| expr(value, vars));
+
+                for name in names {
+                    // Declaring the name again in the same scope hides the previous one for good
+                    if vars.contains_key_at_top(name) {
+                        warn(name, vars.get(name), res);
+                    }
+                    vars.define(name.clone(), Some((span.clone(), false)));
+                }
+            }
+            Statement::Assign(VarDec { values, .. }) => {
+                values.iter().for_each(|value| expr(value, vars))
+            }
+            Statement::FunDec(dec) => {
+                scope(dec.helpers.iter().chain(&dec.body), &dec.args, vars, res)
+            }
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                expr(cond, vars);
+                scope(then.iter(), &[], vars, res);
+                scope(otherwise.iter(), &[], vars, res);
+            }
+            Statement::Loop { cond, body, .. } => {
+                cond.iter().for_each(|cond| expr(cond, vars));
+                scope(body.iter(), &[], vars, res);
+            }
+            Statement::Expr(node) | Statement::Assert(node) => expr(node, vars),
+            Statement::Break(_) | Statement::Continue(_) => {}
+        }
+    }
+
+    let mut res = vec![];
+    scope(ast.iter(), &[], &mut Vars::new(), &mut res);
+    res
+}
+
+/// Warns about differently named functions anywhere in the program sharing a spelling, making
+/// calls to one of them resolve depending on which is in scope.
+pub fn tense_collisions(ast: &[AyNode<Statement>]) -> Vec<Trace> {
+    #[derive(Default)]
+    struct FunDecs<'a>(Vec<(&'a Span, &'a FunDec)>);
+
+    impl<'a> Visitor<'a> for FunDecs<'a> {
+        fn visit_statement(&mut self, node: &'a AyNode<Statement>) {
+            if let Statement::FunDec(dec) = &node.inner {
+                self.0.push((&node.span, dec));
+            }
+            walk_statement(self, node)
+        }
+    }
+
+    let mut decs = FunDecs::default();
+    walk(ast, &mut decs);
+
+    let mut seen = HashMap::<String, (Tense, &str)>::new();
+    let mut res = vec![];
+
+    for (span, dec) in decs.0 {
+        for (tense, spelling) in spellings(&dec.name) {
+            match seen.get(&spelling) {
+                Some((other_tense, other)) if *other != dec.name => res.push(
+                    Trace::new(
+                        Stage::Binding,
+                        Error::from_span(
+                            span.clone(),
+                            format!(
+                                "'{spelling}' is both the {} tense of '{}' and the {} tense of \
+                                 '{other}'",
+                                format!("{tense:?}").to_lowercase(),
+                                dec.name,
+                                format!("{other_tense:?}").to_lowercase(),
+                            )
+                            .as_ref(),
+                        ),
+                    )
+                    .with_severity(Severity::Warning),
+                ),
+                Some(_) => {}
+                None => {
+                    seen.insert(spelling, (tense, &dec.name));
+                }
+            }
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ast::{lib::SourceCode, parsing};
+
+    use super::*;
+
+    fn fun_decs(ast: &[AyNode<Statement>]) -> Vec<Rc<FunDec>> {
+        ast.iter()
+            .filter_map(|node| match &node.inner {
+                Statement::FunDec(dec) => Some(dec.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn display() {
+        let render = |source: &str| {
+            let ast = parsing::parse(SourceCode::Content(source.to_owned())).unwrap();
+            convert(&ast)
+                .unwrap()
+                .iter()
+                .map(|node| format!("{node}.\n"))
+                .collect::<String>()
+        };
+
+        let source = "ngop lì'ukìng t.aron fa a sì b alu\n\
+                        txo a lu b, tsakrr wìntxu fa san a\\tb sìk txew ulte\n\
+                        (a - (b - 1)) * 2 + ke a / 2\n\
+                      txew.\n\
+                      ngop meu x sì y alu (tayaron fa 1 sì 2) sì 0.4.\n\
+                      leyn tstxo ngay vaykrr may' x sì y livu hol kem si\n\
+                        txo srak kewu, tsakrr hum tstxo ngay, txokefyaw kä\n\
+                      ftang.\n\
+                      nìngay tìyaron fa (sung fa 1 sì 2) sì 3 fu 1.\n\
+                      (x || y) && x || ((y)).";
+
+        let expected = "\
+ngop lì'ukìng t.aron fa a sì b alu
+    txo may' a sì b livu teng, tsakrr
+        wìntxu fa san a\\tb sìk
+    txew ulte
+    (a - (b - 1)) * 2 + (ke a) / 2
+txew.
+ngop meu x sì y alu (tayaron fa 1 sì 2) sì 0.4.
+leyn tstxo ngay vaykrr may' x sì y livu hol kem si
+    txo srak kewu, tsakrr
+        hum tstxo ngay,
+    txokefyaw
+        kä
+ftang.
+nìngay tìyaron fa (sung fa 1 sì 2) sì (3 fu 1).
+(x || y) && x || y.
+";
+
+        assert_eq!(render(source), expected);
+        // The rendered source is read back to the same tree
+        assert_eq!(render(expected), expected);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialization() {
+        let ast = parsing::parse(SourceCode::Content(
+            "ngop 'u a alu 1.\n\
+             ngop lì'ukìng f alu a txew.\n\
+             sung fa a sì f si."
+                .to_owned(),
+        ))
+        .unwrap();
+        let bound = convert(&ast).unwrap();
+
+        let json = serde_json::to_value(&bound).unwrap();
+
+        // Uses of the variable only refer to it by name
+        assert_eq!(
+            json[1]["inner"]["FunDec"]["body"][0]["inner"]["Expr"]["inner"]["Var"],
+            "a"
+        );
+        assert_eq!(
+            json[2]["inner"]["Expr"]["inner"]["FunCall"]["args"][0]["inner"]["Var"],
+            "a"
+        );
+        assert_eq!(
+            json[0]["inner"]["VarDec"]["names"],
+            serde_json::json!(["a"])
+        );
+
+        let back: Vec<AyNode<Statement>> = serde_json::from_value(json).unwrap();
+        assert_eq!(back, bound);
+    }
+
+    #[test]
+    fn tense_collision() {
+        let ast = parsing::parse(SourceCode::File(
+            "./examples/features/functions/valid/tense_collision.ay".to_owned(),
+        ))
+        .unwrap();
+
+        let warnings = tense_collisions(&convert(&ast).unwrap());
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].severity(), Severity::Warning);
+        assert!(warnings[0].frames().any(|(_, err)| {
+            err.message()
+            == "'tayaron' is both the present tense of 'tay.aron' and the future tense of 't.aron'"
+        }));
+    }
+
+    #[test]
+    fn context_frames() {
+        let notes = |source: &str| {
+            let ast = parsing::parse(SourceCode::Content(source.to_owned())).unwrap();
+            let trace = convert(&ast).unwrap_err();
+            trace
+                .frames()
+                .map(|(_, err)| err.message().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let frames = notes(
+            "ngop lì'ukìng f fa n alu\n\
+                 txo n lu 1, tsakrr\n\
+                     wìntxu fa b\n\
+                 txew\n\
+             txew.",
+        );
+        assert!(frames[0].contains("'b'"), "{frames:?}");
+        assert_eq!(
+            frames[1..],
+            [
+                "While binding the branches of a condition",
+                "While binding the body of 'f'"
+            ]
+        );
+
+        // Returned rather than collected
+        let frames = notes(
+            "ngop lì'ukìng f alu\n\
+                 leyn vaykrr 1 kem si\n\
+                     hum\n\
+                 ftang\n\
+             txew.",
+        );
+        assert!(frames[0].starts_with("Loop condition"), "{frames:?}");
+        assert_eq!(frames[1..], ["While binding the body of 'f'"]);
+    }
+
+    #[test]
+    fn simultaneous_declarations() {
+        let bind = |source: &str| convert(&parsing::parse(SourceCode::Content(source.to_owned()))?);
+
+        for (source, read) in [
+            ("ngop 'u a alu a.", "a"),
+            ("ngop meu a sì b alu 1 sì a.", "a"),
+            ("ngop meu a sì b alu b + 1 sì 2.", "b"),
+            (
+                "ngop lì'ukìng f alu ngop meu a sì b alu 1 sì (sung fa a sì 1) txew.",
+                "a",
+            ),
+        ] {
+            let trace = bind(source).unwrap_err();
+            let (stage, err) = trace.frames().next().unwrap();
+            assert!(matches!(stage, Stage::Binding));
+            assert_eq!(
+                err.message(),
+                format!(
+                    "Variable '{read}' is read in its own declaration, names are only bound once \
+                     all values are"
+                ),
+                "{source}"
+            );
+        }
+
+        // Values see the outer variable being shadowed
+        let bound = bind("ngop 'u a alu 1.\nngop meu a sì b alu 2 sì a.").unwrap();
+        let Statement::VarDec(VarDec { values, .. }) = &bound[1].inner else {
+            unreachable!()
+        };
+        assert_eq!(values[1].inner, Expr::Var("a".to_owned()));
+    }
+
+    #[test]
+    fn dotted_names() {
+        let source = "ngop lì'ukìng t.aron alu 1 txew.\n\
+                      ngop lì'ukìng taw alu 2 txew.\n\
+                      wìntxu fa (tìyaron si) sì (taw si).";
+        let ast = parsing::parse(SourceCode::Content(source.to_owned())).unwrap();
+        let bound = convert(&ast).unwrap();
+
+        let names = fun_decs(&bound)
+            .iter()
+            .map(|dec| dec.name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["t.aron", "taw"]);
+
+        // Only built by hand, the grammar taking a single `.`
+        for name in ["t.a.ron", ".aron", "t.", ""] {
+            let mut ast = ast.clone();
+            let PStatement::FunDec { name: dec, .. } = &mut ast[0].inner else {
+                unreachable!()
+            };
+            *dec = name.to_owned();
+
+            let trace = convert(&ast).unwrap_err();
+            assert!(trace.frames().any(|(stage, err)| {
+                matches!(stage, Stage::Binding)
+                    && err
+                        .message()
+                        .starts_with(&format!("Invalid function name '{name}'"))
+            }));
+        }
+    }
+
+    #[test]
+    fn number_word_names() {
+        let message = |source: &str| {
+            let ast = parsing::parse(SourceCode::Content(source.to_owned())).unwrap();
+            let trace = convert(&ast).unwrap_err();
+            let (stage, err) = trace.frames().next().unwrap();
+            assert!(matches!(stage, Stage::Binding));
+            err.message().to_owned()
+        };
+
+        assert_eq!(
+            message("ngop 'u a sì mrr alu 1 sì 2."),
+            "'mrr' is the number 5, it cannot name a variable"
+        );
+        assert_eq!(
+            message("ngop lì'ukìng f fa vol alu 1 txew."),
+            "'vol' is the number 8, it cannot name a variable"
+        );
+
+        // Functions are called with `fa` or `si`, which numbers are not followed by
+        let ast = parsing::parse(SourceCode::Content(
+            "ngop lì'ukìng mune fa a alu a * 2 txew.\nmune fa mune.".to_owned(),
+        ))
+        .unwrap();
+        convert(&ast).unwrap();
+    }
+
+    #[test]
+    fn undefined_variable() {
+        let ast =
+            parsing::parse(SourceCode::Content("ngop 'u a alu 1.\nke tìng.".to_owned())).unwrap();
+        let trace = convert(&ast).unwrap_err();
+
+        let (stage, err) = trace.frames().next().unwrap();
+        assert!(matches!(stage, Stage::Binding));
+        assert!(err.message().contains("'tìng'"));
+    }
+
+    #[test]
+    fn undefined_function() {
+        let message = |source: &str| {
+            let ast = parsing::parse(SourceCode::Content(source.to_owned())).unwrap();
+            let trace = convert(&ast).unwrap_err();
+            let (stage, err) = trace.frames().next().unwrap();
+
+            assert!(matches!(stage, Stage::Binding));
+            err.message().to_owned()
+        };
+
+        let program = "ngop lì'ukìng scope alu\n  ngop lì'ukìng t.aron alu 1 txew ulte\n  ";
+
+        assert_eq!(
+            message(&format!("{program}nonexistent si\ntxew.")),
+            "Undefined function: 'nonexistent'. Declared functions: 'scope', 'taron'"
+        );
+        assert_eq!(
+            message(&format!("{program}tarun si\ntxew.")),
+            "Undefined function: 'tarun'. Maybe you meant: 'taron'?"
+        );
+    }
+
+    #[test]
+    fn arity() {
+        let message = |path: &str| {
+            let ast = parsing::parse(SourceCode::File(path.to_owned())).unwrap();
+            let trace = convert(&ast).unwrap_err();
+            let (_, err) = trace.frames().next().unwrap();
+
+            err.message().to_owned()
+        };
+
+        assert_eq!(
+            message("./examples/features/functions/invalid/too_few_arguments.ay"),
+            "'tìyvaron' expects 2 arguments, found 1"
+        );
+        assert_eq!(
+            message("./examples/features/functions/invalid/too_many_arguments.ay"),
+            "'tayvaron' expects 2 arguments, found 3"
+        );
+    }
+
+    #[test]
+    fn recursive_functions() {
+        let bind = |source: &str| convert(&parsing::parse(SourceCode::Content(source.to_owned()))?);
+
+        // Directly recursive
+        assert!(bind(
+            "ngop lì'ukìng tri fa n alu\n\
+                 txo n lu 0, tsakrr 0, txokefyaw sung fa n sì tri fa n - 1\n\
+             txew."
+        )
+        .is_ok());
+
+        // Mutually recursive, called before their declaration, at the top level and in a body
+        let pair = |end: &str| {
+            format!(
+                "ngop lì'ukìng mesrr fa n alu\n\
+                     txo n lu 0, tsakrr 1, txokefyaw kxetse fa n - 1\n\
+                 txew{end}\n\
+                 ngop lì'ukìng kxetse fa n alu\n\
+                     txo n lu 0, tsakrr 0, txokefyaw mesrr fa n - 1\n\
+                 txew"
+            )
+        };
+        assert!(bind(&format!("mesrr fa 7.\n{}.", pair("."))).is_ok());
+        assert!(bind(&format!(
+            "ngop lì'ukìng rey alu\nmesrr fa 7 ulte\n{}\ntxew.",
+            pair(" ulte")
+        ))
+        .is_ok());
+
+        // Forward calls are checked against the declaration they refer to
+        let trace = bind(&format!("kxetse fa 1 sì 2.\n{}.", pair("."))).unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert_eq!(err.message(), "'kxetse' expects 1 arguments, found 2");
+
+        // Declarations of a block are not hoisted out of it
+        let trace = bind(
+            "ngop lì'ukìng rey alu\n\
+                 ngop lì'ukìng tsun alu 1 txew\n\
+             txew.\n\
+             tsun si.",
+        )
+        .unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert!(err.message().starts_with("Undefined function: 'tsun'"));
+    }
+
+    #[test]
+    fn duplicate_function() {
+        let ast = parsing::parse(SourceCode::File(
+            "./examples/features/functions/invalid/duplicate_function.ay".to_owned(),
+        ))
+        .unwrap();
+        let trace = convert(&ast).unwrap_err();
+
+        let frames = trace
+            .frames()
+            .map(|(_, err)| (err.line_col(), err.message().to_owned()))
+            .collect::<Vec<_>>();
+
+        assert!(matches!(
+            frames.as_slice(),
+            [
+                (LineColLocation::Span((3, _), _), duplicate),
+                (LineColLocation::Span((2, _), _), original),
+                (LineColLocation::Span((1, _), _), context),
+            ] if duplicate == "Function 't.aron' is already declared in this scope"
+                && original == "'t.aron' first declared here"
+                && context == "While binding the body of 'scope'"
+        ));
+    }
+
+    #[test]
+    fn unused_variable() {
+        let warnings = |source: &str| {
+            let ast = parsing::parse(SourceCode::Content(source.to_owned())).unwrap();
+
+            unused_variables(&convert(&ast).unwrap())
+                .iter()
+                .flat_map(|trace| trace.frames().map(|(_, err)| err.message().to_owned()))
+                .collect::<Vec<_>>()
+        };
+
+        // Read from a nested function
+        assert!(warnings(
+            "ngop 'u a alu 1.\n\
+             ngop lì'ukìng rey alu ngop lì'ukìng tìng alu a txew ulte tìng si txew."
+        )
+        .is_empty());
+        assert_eq!(
+            warnings(
+                "ngop 'u a sì b alu 1 sì 2.\n\
+                 ngop lì'ukìng rey fa a alu ngop 'u c alu a ulte 0 txew.\n\
+                 b."
+            ),
+            vec![
+                "Variable 'c' is never used".to_owned(),
+                "Variable 'a' is never used".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn parameter_resolutions() {
+        // Reading the argument resolves to the parameter
+        let source = "ngop lì'ukìng rey fa n alu n + 1 txew.";
+        let ast = parsing::parse(SourceCode::Content(source.to_owned())).unwrap();
+        let mut binder = Binder::new().with_resolutions();
+        binder.convert(&ast).unwrap();
+        assert!(binder
+            .resolutions()
+            .unwrap()
+            .iter()
+            .any(|(_, resolution)| matches!(
+                &resolution.declaration,
+                Declaration::Param(dec) if dec.name == "rey"
+            )));
+    }
+
+    #[test]
+    fn all_errors() {
+        let errors = |source: &str| {
+            let ast = parsing::parse(SourceCode::Content(source.to_owned())).unwrap();
+
+            convert_all(&ast)
+                .unwrap_err()
+                .iter()
+                .map(|trace| {
+                    let (_, err) = trace.frames().next().unwrap();
+                    (err.line_col(), err.message().to_owned())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let found = errors(
+            "ngop 'u a alu kxa.\n\
+             wìntxu fa a sì fkxen.\n\
+             ngop lì'ukìng rey fa x sì x alu x txew.\n\
+             txo rey fa a, tsakrr pxun txew.",
+        );
+
+        // `a` and `rey` are declared despite their errors, nothing is reported for their uses
+        assert_eq!(
+            found
+                .iter()
+                .map(|(location, message)| (location, message.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    &LineColLocation::Span((1, 15), (1, 18)),
+                    "Undefined variable: 'kxa'"
+                ),
+                (
+                    &LineColLocation::Span((2, 16), (2, 21)),
+                    "Undefined variable: 'fkxen'"
+                ),
+                (
+                    &LineColLocation::Span((3, 6), (3, 39)),
+                    "Duplicate parameter 'x' in declaration of 'rey'"
+                ),
+                (
+                    &LineColLocation::Span((4, 22), (4, 26)),
+                    "Undefined variable: 'pxun'"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn string_escapes() {
+        let ast = parsing::parse(SourceCode::File(
+            "./examples/features/expressions/valid/string_escapes.ay".to_owned(),
+        ))
+        .unwrap();
+        let bound = convert(&ast).unwrap();
+
+        let strings = bound
+            .iter()
+            .map(|node| match &node.inner {
+                Statement::Expr(AyNode {
+                    inner: Expr::FunCall { args, .. },
+                    ..
+                }) => match &args[0].inner {
+                    Expr::String(string) => string.as_str(),
+                    inner => panic!("Expected a string, found {inner:?}"),
+                },
+                inner => panic!("Expected a call, found {inner:?}"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(strings, vec!["two\nlines", "a\ttab, a \\ and a \""]);
+    }
+
+    #[test]
+    fn negated_literals() {
+        let ast =
+            parsing::parse(SourceCode::Content("ke san kaltxì sìk.\nke 7.".to_owned())).unwrap();
+        let bound = convert(&ast).unwrap();
+
+        let negated = bound
+            .iter()
+            .map(|node| match &node.inner {
+                Statement::Expr(AyNode {
+                    span,
+                    inner: Expr::Negated(inner),
+                }) => (span.as_str(), &inner.inner),
+                inner => panic!("Expected a negation, found {inner:?}"),
+            })
+            .collect::<Vec<_>>();
+
+        // The negation spans the whole expression, not just its operand
+        assert_eq!(
+            negated,
+            vec![
+                ("ke san kaltxì sìk", &Expr::String("kaltxì".to_owned())),
+                ("ke 7", &Expr::Number(7)),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_arrays() {
+        let ast = parsing::parse(SourceCode::Content(
+            "vezeykoyu.\nvezeykoyu 1 sì vezeykoyu 2 sì 3.".to_owned(),
+        ))
+        .unwrap();
+        let bound = convert(&ast).unwrap();
+
+        let arrays = bound
+            .iter()
+            .map(|node| match &node.inner {
+                Statement::Expr(AyNode {
+                    inner: Expr::Array { items },
+                    ..
+                }) => items
+                    .iter()
+                    .map(|item| match &item.inner {
+                        Expr::Array { items } => items.len(),
+                        _ => 0,
+                    })
+                    .collect::<Vec<_>>(),
+                inner => panic!("Expected an array, found {inner:?}"),
+            })
+            .collect::<Vec<_>>();
+
+        // Arrays are greedy, the trailing item belongs to the innermost one
+        assert_eq!(arrays, vec![vec![], vec![0, 2]]);
+    }
+
+    #[test]
+    fn bool_literals() {
+        let bind = |source: &str| convert(&parsing::parse(SourceCode::Content(source.to_owned()))?);
+
+        let bound = bind("srane.\nkehe.").unwrap();
+        assert!(matches!(
+            bound.as_slice(),
+            [
+                AyNode {
+                    inner: Statement::Expr(AyNode {
+                        inner: Expr::Bool(true),
+                        ..
+                    }),
+                    ..
+                },
+                AyNode {
+                    inner: Statement::Expr(AyNode {
+                        inner: Expr::Bool(false),
+                        ..
+                    }),
+                    ..
+                },
+            ]
+        ));
+
+        assert!(bind("nìngay ke kehe.").is_ok());
+        let trace = bind("nìngay ke srane.").unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert_eq!(err.message(), "Assertion can never hold");
+
+        let warnings = non_boolean_conditions(
+            &bind(
+                "txo 1, tsakrr 2 txew.\n\
+                 txo srane, tsakrr 2 txew.\n\
+                 ngop lì'ukìng rey alu txo san a sìk, tsakrr 2 txew txew.",
+            )
+            .unwrap(),
+        )
+        .iter()
+        .map(|warning| {
+            let (_, err) = warning.frames().next().unwrap();
+            (warning.severity(), err.message().to_owned())
+        })
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            warnings,
+            vec![
+                (
+                    Severity::Warning,
+                    "Condition is a Number literal, expected `srane` or `kehe`".to_owned()
+                ),
+                (
+                    Severity::Warning,
+                    "Condition is a String literal, expected `srane` or `kehe`".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn loop_conditions() {
+        let bind = |source: &str| convert(&parsing::parse(SourceCode::Content(source.to_owned()))?);
+
+        assert!(bind("ngop 'u i alu 0.\nleyn vaykrr i lu 0 kem si hum ftang.").is_ok());
+        assert!(bind("leyn vaykrr srane kem si hum ftang.").is_ok());
+        assert!(bind("leyn vaykrr sung fa 1 sì 2 kem si hum ftang.").is_ok());
+        // Without a condition, left with `hum`
+        assert!(bind("leyn kem si hum ftang.").is_ok());
+
+        let trace = bind("leyn vaykrr san ftang sìk kem si hum ftang.").unwrap_err();
+        let (stage, err) = trace.frames().next().unwrap();
+        assert_eq!(stage, Stage::Binding);
+        assert_eq!(
+            err.message(),
+            "Loop condition is a String literal, expected `srane`, `kehe` or a comparison"
+        );
+        assert_eq!(err.line(), "ftang");
+
+        assert!(bind("leyn vaykrr 1 kem si hum ftang.").is_err());
+    }
+
+    #[test]
+    fn structural_equality() {
+        let bind = |source: &str| {
+            convert(&parsing::parse(SourceCode::Content(source.to_owned())).unwrap()).unwrap()
+        };
+
+        let source = "ngop lì'ukìng t.aron fa a alu a + b txew tsengit ngop 'u b alu 2.\n\
+             ngop 'u c alu tayaron fa 1.\n\
+             nìngay c lu 2.";
+        let (bound, shifted) = (bind(source), bind(&format!("  {source}")));
+
+        assert_ne!(bound, shifted);
+        assert!(bound.structural_eq(&shifted));
+
+        // Same call in another tense
+        assert!(!bound.structural_eq(&bind(&source.replace("tayaron", "taron"))));
+        assert!(!bound.structural_eq(&bind(&source.replace("a + b", "a - b"))));
+    }
+
+    #[test]
+    fn file_names() {
+        let path = "./examples/features/functions/invalid/duplicate_function.ay";
+        let trace =
+            convert(&parsing::parse(SourceCode::File(path.to_owned())).unwrap()).unwrap_err();
+        let plain = trace.to_string_colored(false);
+
+        assert!(trace.file().unwrap().ends_with("duplicate_function.ay"));
+        assert!(plain.contains("duplicate_function.ay:3:8 -> 3:35\n"));
+        assert!(plain.contains("duplicate_function.ay:2:8 -> 2:35\n"));
+    }
+
+    #[test]
+    fn index_and_concat() {
+        let ast = parsing::parse(SourceCode::Content(
+            "ngop 'u xs alu vezeykoyu 1 sì 2.\nxs[1].\nsan kal sìk ++ san txì sìk.".to_owned(),
+        ))
+        .unwrap();
+        let bound = convert(&ast).unwrap();
+
+        let Statement::Expr(index) = &bound[1].inner else {
+            panic!("Expected an expression");
+        };
+        assert!(matches!(
+            &index.inner,
+            Expr::Index { value, index }
+                if value.inner == Expr::Var("xs".to_owned()) && index.inner == Expr::Number(1)
+        ));
+
+        let Statement::Expr(concat) = &bound[2].inner else {
+            panic!("Expected an expression");
+        };
+        assert!(matches!(
+            &concat.inner,
+            Expr::BinOp { op: BinaryOperator::Concat, left, right }
+                if left.inner == Expr::String("kal".to_owned())
+                    && right.inner == Expr::String("txì".to_owned())
+        ));
+        assert_eq!(concat.to_string(), "san kal sìk ++ san txì sìk");
+
+        // Operands are resolved like any other
+        let ast = parsing::parse(SourceCode::Content("ys[0] ++ zs.".to_owned())).unwrap();
+        assert_eq!(convert_all(&ast).unwrap_err().len(), 2);
+    }
+
+    #[test]
+    fn else_if_chains() {
+        /// Number of conditions chained through `otherwise`, and what the last one falls back to.
+        fn chain(node: &AyNode<Statement>) -> (usize, &[AyNode<Statement>]) {
+            match &node.inner {
+                Statement::If { otherwise, .. } => match otherwise.as_slice() {
+                    [nested @ AyNode {
+                        inner: Statement::If { .. },
+                        ..
+                    }] => {
+                        let (count, last) = chain(nested);
+                        (count + 1, last)
+                    }
+                    otherwise => (1, otherwise),
+                },
+                _ => (0, &[]),
+            }
+        }
+
+        let ast = parsing::parse(SourceCode::File(
+            "./examples/features/conditions/valid/else_if.ay".to_owned(),
+        ))
+        .unwrap();
+        let bound = convert(&ast).unwrap();
+
+        let (count, last) = chain(&bound[1]);
+        assert_eq!((count, last.len()), (2, 0));
+
+        let Statement::FunDec(sign) = &bound[2].inner else {
+            panic!("Expected a function, found {:?}", bound[2]);
+        };
+        let (count, last) = chain(&sign.body[0]);
+        assert_eq!(count, 3);
+        assert!(matches!(
+            last,
+            [AyNode {
+                inner: Statement::Expr(_),
+                ..
+            }]
+        ));
+
+        // The single `txew` closes the whole chain, leaving `n` to the function
+        let Statement::FunDec(describe) = &bound[3].inner else {
+            panic!("Expected a function, found {:?}", bound[3]);
+        };
+        assert_eq!(describe.body.len(), 2);
+        let (count, last) = chain(&describe.body[0]);
+        assert_eq!((count, last.len()), (3, 0));
+
+        // Nested conditions span their own `txokefyaw txo` clause
+        let Statement::If { otherwise, .. } = &bound[1].inner else {
+            unreachable!()
+        };
+        assert!(otherwise[0]
+            .span
+            .as_str()
+            .starts_with(", txokefyaw txo a lu 2"));
+    }
+
+    #[test]
+    fn empty_body() {
+        let warnings = |path: &str| {
+            let ast = parsing::parse(SourceCode::File(path.to_owned())).unwrap();
+            empty_bodies(&convert(&ast).unwrap())
+                .iter()
+                .flat_map(|warning| {
+                    assert_eq!(warning.severity(), Severity::Warning);
+                    warning
+                        .frames()
+                        .map(|(_, err)| err.message().to_owned())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            warnings("./examples/features/conditions/valid/empty.ay"),
+            vec![
+                "Condition has no effect, both of its branches are empty",
+                "Condition has an empty `tsakrr` branch, negate it to only keep the `txokefyaw` \
+                 one"
+            ]
+        );
+        assert_eq!(
+            warnings("./examples/features/loops/valid/empty.ay"),
+            vec![
+                "Loop has an empty body",
+                "Loop without a condition and with an empty body never ends and does nothing"
+            ]
+        );
+    }
+
+    #[test]
+    fn binder_resolves_declarations() {
+        let ast = parsing::parse(SourceCode::Content(
+            "ngop 'u a sì b alu 5 sì 6.\nngop lì'ukìng t.aron fa x alu x txew.".to_owned(),
+        ))
+        .unwrap();
+
+        let mut binder = Binder::new();
+        let bound = binder.convert(&ast).unwrap();
+
+        let Statement::VarDec(dec) = &bound[0].inner else {
+            panic!("Expected a variable declaration");
+        };
+
+        assert_eq!(binder.resolve_var("b").map(|dec| dec.as_ref()), Some(dec));
+        assert!(binder.resolve_var("c").is_none());
+
+        let (tense, dec) = binder.resolve_fun("tayaron").unwrap();
+        assert_eq!(tense, Tense::Future);
+        assert_eq!(dec.name, "t.aron");
+        assert!(binder.resolve_fun("sung").is_none());
+    }
+
+    #[test]
+    fn resolutions() {
+        let ast = parsing::parse(SourceCode::Content(
+            "ngop 'u a alu 1.\n\
+             ngop lì'ukìng f fa a alu\n    \
+                 ngop 'u b alu a + c ulte\n    \
+                 b\n\
+             txew tsengit ngop 'u c alu a.\n\
+             ngop 'u a alu (f fa a) + 1.\n\
+             a."
+            .to_owned(),
+        ))
+        .unwrap();
+
+        let mut binder = Binder::new();
+        binder.convert(&ast).unwrap();
+        assert!(binder.resolutions().is_none());
+
+        let mut binder = Binder::new().with_resolutions();
+        binder.convert(&ast).unwrap();
+        let table = binder.resolutions().unwrap();
+
+        let declared_at = |line, col| {
+            table
+                .resolve_at(line, col)
+                .map(|resolution| (resolution.span.start().0, &resolution.declaration))
+        };
+
+        // The value sees the `a` it shadows
+        assert!(
+            matches!(declared_at(6, 21), Some((1, Declaration::Var(dec))) if dec.names == ["a"])
+        );
+        assert!(matches!(declared_at(7, 1), Some((6, Declaration::Var(_)))));
+        assert!(matches!(declared_at(6, 16), Some((2, Declaration::Fun(dec))) if dec.name == "f"));
+
+        // Parameters shadow variables, helpers are visible from the body
+        assert!(matches!(
+            declared_at(3, 19),
+            Some((2, Declaration::Param(_)))
+        ));
+        assert!(
+            matches!(declared_at(3, 23), Some((5, Declaration::Var(dec))) if dec.names == ["c"])
+        );
+        assert!(matches!(
+            declared_at(5, 28),
+            Some((2, Declaration::Param(_)))
+        ));
+
+        assert!(declared_at(1, 1).is_none());
+        assert_eq!(table.iter().count(), 7);
+
+        // Not kept past a failed run
+        binder
+            .convert(&parsing::parse(SourceCode::Content("d.".to_owned())).unwrap())
+            .unwrap_err();
+        assert!(binder.resolutions().is_none());
+    }
+
+    #[test]
+    fn binder_reuses_unchanged_functions() {
+        let program = |body: &str| {
+            parsing::parse(SourceCode::Content(format!(
+                "ngop lì'ukìng rey alu 1 txew.\n\
+                 ngop lì'ukìng tìng fa a alu {body} txew.\n\
+                 ngop lì'ukìng kan alu rey si txew."
+            )))
+            .unwrap()
+        };
+
+        let mut binder = Binder::new();
+
+        let before = fun_decs(&binder.convert(&program("a")).unwrap());
+        let after = fun_decs(&binder.convert(&program("san tìng sìk")).unwrap());
+
+        assert!(Rc::ptr_eq(&before[0], &after[0]));
+        assert!(!Rc::ptr_eq(&before[1], &after[1]));
+        assert!(Rc::ptr_eq(&before[2], &after[2]));
+    }
+
+    #[test]
+    fn binder_reuses_moved_functions() {
+        let program = |body: &str| {
+            parsing::parse(SourceCode::Content(format!(
+                "ngop lì'ukìng rey alu\n{body}\ntxew.\n\
+                 ngop lì'ukìng kan alu rey si txew."
+            )))
+            .unwrap()
+        };
+
+        let mut binder = Binder::new();
+
+        // `kan` moves one line down, its text and what it sees staying the same
+        let before = fun_decs(&binder.convert(&program("1")).unwrap());
+        let after = fun_decs(&binder.convert(&program("wìntxu fa 1 ulte\n1")).unwrap());
+
+        assert!(!Rc::ptr_eq(&before[0], &after[0]));
+        assert!(Rc::ptr_eq(&before[1], &after[1]));
+
+        // Unless resolutions are built, as they are found by position
+        let mut binder = Binder::new().with_resolutions();
+
+        let before = fun_decs(&binder.convert(&program("1")).unwrap());
+        let after = fun_decs(&binder.convert(&program("wìntxu fa 1 ulte\n1")).unwrap());
+
+        assert!(!Rc::ptr_eq(&before[1], &after[1]));
+        assert_eq!(after[1].body[0].span.start(), (5, 23));
+    }
+
+    #[test]
+    fn binder_rejects_bad_functions_again() {
+        let ast = parsing::parse(SourceCode::Content(
+            "ngop lì'ukìng rey alu b txew.\nngop lì'ukìng tìng alu 1 txew.".to_owned(),
+        ))
+        .unwrap();
+
+        let mut binder = Binder::new();
+        for _ in 0..2 {
+            let trace = binder.convert(&ast).unwrap_err();
+            let (_, err) = trace.frames().next().unwrap();
+            assert!(err.message().starts_with("Undefined variable: 'b'"));
+        }
+    }
+
+    #[test]
+    /// Test:
+    /// ````
+    /// fn scope {
+    ///    fn t.aron {}
+    ///    taron()   // valid
+    ///    tìyaron() // valid
+    ///    tayaron() // valid
+    /// }
+    /// taron() -- invalid
+    /// ````
+    fn test_match_function() {
+        let mut funs = FunScope::<()>::default();
+
+        wrap_scope!(
+            funs | {
+                funs.define("scope".to_owned(), ());
+
+                wrap_scope!(
+                    funs | {
+                        funs.define("t.aron".to_owned(), ());
+
+                        ["taron", "tìyaron", "tayaron"]
+                            .iter()
+                            .map(|name| (name, funs.resolve(name)))
+                            .for_each(|(name, res)| {
+                                assert!(res.is_some(), "Function not found: '{}'", name)
+                            });
+                    }
+                );
+
+                assert!(funs.resolve("taron").is_none());
+            }
+        );
+    }
+
+    #[test]
+    fn number_literals() {
+        let ast = parsing::parse(SourceCode::Content(
+            "ngop 'u a alu 12 melo.\nngop 'u b alu 7 pxelo.".to_owned(),
+        ))
+        .unwrap();
+        let bound = convert(&ast).unwrap();
+
+        // Octal and multiplied when parsed, carried over as is
+        let values = bound
+            .iter()
+            .map(|node| match &node.inner {
+                Statement::VarDec(VarDec { values, .. }) => {
+                    (values[0].span.as_str(), &values[0].inner)
+                }
+                inner => panic!("Expected a declaration, found {inner:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![
+                ("12 melo", &Expr::Number(20)),
+                ("7 pxelo", &Expr::Number(21))
+            ]
+        );
+    }
+
+    #[test]
+    fn indexed_spellings() {
+        /// Resolution by trying the spellings of every function in scope.
+        fn scan(call: &str, funs: &ScopeMap<String, ()>) -> Option<(Tense, String)> {
+            funs.iter().find_map(|(key, _)| {
+                spellings(key)
+                    .into_iter()
+                    .find(|(_, spelling)| spelling == call)
+                    .map(|(tense, _)| (tense, key.clone()))
+            })
+        }
+
+        let names = (0..200)
+            .map(|i| match i % 3 {
+                0 => format!("f{i}.aron"),
+                1 => format!("fn{i}.ul.ke"),
+                _ => format!("g{i}"),
+            })
+            .collect::<Vec<_>>();
+
+        let mut indexed = FunScope::default();
+        let mut scanned = ScopeMap::new();
+
+        for (layer, names) in names.chunks(50).enumerate() {
+            if layer > 0 {
+                indexed.push_layer();
+                scanned.push_layer();
+            }
+
+            for name in names {
+                indexed.define(name.clone(), ());
+                scanned.define(name.clone(), ());
+            }
+        }
+
+        // Popping the last layer, its functions have to be out of the index as well
+        indexed.pop_layer();
+        scanned.pop_layer();
+
+        for name in &names {
+            for (_, spelling) in spellings(name)
+                .into_iter()
+                .chain([(Tense::Present, format!("{}x", name.replace('.', "")))])
+            {
+                assert_eq!(indexed.resolve(&spelling), scan(&spelling, &scanned));
+            }
+        }
+
+        // Same spelling in two tenses, the present one winning
+        let mut funs = FunScope::default();
+        funs.define("t.aron".to_owned(), ());
+        funs.define("tay.aron".to_owned(), ());
+        funs.define("tìy.aron".to_owned(), ());
+
+        assert_eq!(
+            funs.resolve("tayaron"),
+            Some((Tense::Present, "tay.aron".to_owned()))
+        );
+        assert_eq!(
+            funs.resolve("tìyaron"),
+            Some((Tense::Present, "tìy.aron".to_owned()))
+        );
+        assert_eq!(
+            funs.resolve("taron"),
+            Some((Tense::Present, "t.aron".to_owned()))
         );
     }
 }
@@ -0,0 +1,450 @@
+use crate::{
+    ast::{
+        binding::indent,
+        lib::{Base, SourceCode},
+        parsing::{self, AyParser, Rule, Trivia},
+    },
+    error::trace::Trace,
+};
+
+use pest::{iterators::Pair, Parser};
+
+/// Rewrites the source canonically: one top-level statement per line, blocks indented by four
+/// spaces and single spaces between words and around operators. Formatting the result again leaves
+/// it as is.
+///
+/// Statements are rendered from their parse tree, keeping the forms they are written in such as
+/// `pe` blocks, `txokefyaw txo` chains, `a lu b`, multipliers and the parentheses of groups.
+/// Numbers are read in `base`, the file being checked to parse without being bound.
+///
+/// Comments and module uses between top-level statements are kept on their own lines, at most one
+/// blank line apart, and a comment following a statement on its line stays there. Statements with
+/// comments within them are kept as written rather than losing them.
+pub fn format(source: SourceCode, base: Base) -> Result<String, Trace> {
+    let lossless = parsing::parse_lossless_with_base(source, base)?;
+
+    let mut res = String::new();
+
+    for (
+        index,
+        Trivia {
+            leading,
+            node,
+            trailing,
+            ..
+        },
+    ) in lossless.statements.iter().enumerate()
+    {
+        res.push_str(&trivia(leading, index == 0));
+
+        let text = node.span.as_str();
+        // Blocks may be closed by the `.` itself, which the statement's span leaves out
+        match AyParser::parse(Rule::statement, &format!("{text}."))
+            .ok()
+            .and_then(|mut pairs| pairs.next())
+        {
+            Some(pair) if !has_comment(&pair) => res.push_str(&statement(pair)),
+            _ => res.push_str(text),
+        }
+        res.push('.');
+
+        // Whatever follows the `.` on the statement's line can only be a comment
+        let comment = trailing.trim_start().trim_start_matches('.').trim();
+        if !comment.is_empty() {
+            res.push(' ');
+            res.push_str(comment);
+        }
+        res.push('\n');
+    }
+
+    // Blank lines ending the file are left out as well
+    let end = trivia(&lossless.end, lossless.statements.is_empty());
+    let end = end.trim_end_matches('\n');
+    if !end.is_empty() {
+        res.push_str(end);
+        res.push('\n');
+    }
+
+    Ok(res)
+}
+
+/// Renders a `statement` pair, without the `.` ending top-level ones.
+fn statement(pair: Pair<Rule>) -> String {
+    let Some(inner) = pair.clone().into_inner().next() else {
+        return expr(pair);
+    };
+
+    // `ngop` is part of the statement rather than of the declaration
+    match inner.as_rule() {
+        Rule::fun_dec => format!("ngop {}", fun_dec(inner)),
+        Rule::var_dec => format!("ngop {}", expr(inner)),
+        Rule::if_block => if_block(inner),
+        Rule::match_block => match_block(inner),
+        Rule::loop_block => loop_block(inner),
+        _ => expr(inner),
+    }
+}
+
+/// Statements of a `block_body`, one per line and chained with `ulte`.
+fn block(pair: Pair<Rule>) -> String {
+    pair.into_inner()
+        .map(statement)
+        .collect::<Vec<_>>()
+        .join(" ulte\n")
+}
+
+/// `head` followed by the indented `body` on the next lines, if it has any statement.
+fn branch(head: &str, body: Pair<Rule>) -> String {
+    match block(body) {
+        body if body.is_empty() => head.to_owned(),
+        body => format!("{head}\n{}", indent(&body)),
+    }
+}
+
+fn fun_dec(pair: Pair<Rule>) -> String {
+    let mut res = "lì'ukìng".to_owned();
+
+    for child in pair.into_inner() {
+        match child.as_rule() {
+            Rule::block_body => {
+                res = branch(&format!("{res} alu"), child);
+                res.push_str("\ntxew");
+            }
+            Rule::where_clause => {
+                let helpers = child
+                    .into_inner()
+                    .map(|helper| match helper.as_rule() {
+                        Rule::fun_dec => format!("ngop {}", fun_dec(helper)),
+                        _ => format!("ngop {}", expr(helper)),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ulte\n");
+                res.push_str(&format!(" tsengit\n{}", indent(&helpers)));
+            }
+            _ => {
+                res.push(' ');
+                res.push_str(&expr(child));
+            }
+        }
+    }
+
+    res
+}
+
+/// A `txokefyaw` branch has no closing of its own, chains being closed once.
+fn if_block(pair: Pair<Rule>) -> String {
+    let mut children = pair.into_inner();
+    let (Some(cond), Some(then)) = (children.next(), children.next()) else {
+        unreachable!("Conditions have a condition and a body")
+    };
+
+    let mut res = branch(&format!("txo {}, tsakrr", expr(cond)), then);
+    let mut closed = false;
+
+    for child in children {
+        match child.as_rule() {
+            Rule::else_if => {
+                let mut children = child.into_inner();
+                let (Some(cond), Some(then)) = (children.next(), children.next()) else {
+                    unreachable!("`txokefyaw txo` branches have a condition and a body")
+                };
+                res.push_str(",\n");
+                res.push_str(&branch(
+                    &format!("txokefyaw txo {}, tsakrr", expr(cond)),
+                    then,
+                ));
+            }
+            _ => {
+                res.push_str(",\n");
+                res.push_str(&branch("txokefyaw", child));
+                closed = true;
+            }
+        }
+    }
+
+    if !closed {
+        res.push_str("\ntxew");
+    }
+    res
+}
+
+/// Arms are indented below the `pe` they belong to.
+fn match_block(pair: Pair<Rule>) -> String {
+    let mut children = pair.into_inner();
+    let Some(head) = children.next() else {
+        unreachable!("Matches have a head")
+    };
+
+    let mut res = expr(head);
+    let mut closed = false;
+
+    for child in children {
+        res.push_str(",\n");
+
+        match child.as_rule() {
+            Rule::match_arm => {
+                let mut children = child.into_inner();
+                let (Some(pattern), Some(body)) = (children.next(), children.next()) else {
+                    unreachable!("Arms have a pattern and a body")
+                };
+                res.push_str(&indent(&branch(
+                    &format!("txo {} tsakrr", expr(pattern)),
+                    body,
+                )));
+            }
+            _ => {
+                res.push_str(&indent(&branch("txokefyaw", child)));
+                closed = true;
+            }
+        }
+    }
+
+    if !closed {
+        res.push_str("\ntxew");
+    }
+    res
+}
+
+fn loop_block(pair: Pair<Rule>) -> String {
+    let mut head = "leyn".to_owned();
+
+    for child in pair.into_inner() {
+        match child.as_rule() {
+            Rule::loop_label => head.push_str(&format!(" {}", expr(child))),
+            Rule::block_body => {
+                return format!("{}\nftang", branch(&format!("{head} kem si"), child))
+            }
+            _ => head.push_str(&format!(" vaykrr {}", expr(child))),
+        }
+    }
+
+    unreachable!("Loops have a body")
+}
+
+/// Renders an expression, or any pair without blocks, as written with whitespace collapsed.
+/// Operators get a single space on both sides and groups none inside their parentheses.
+fn expr(pair: Pair<Rule>) -> String {
+    match pair.as_rule() {
+        Rule::string => pair.as_str().to_owned(),
+        Rule::group => format!("({})", inner(pair)),
+        Rule::index => format!("[{}]", inner(pair)),
+        // Its span includes the whitespace separating it from `lu`
+        Rule::is_negation => "ke ".to_owned(),
+        Rule::arithmetic => pair
+            .into_inner()
+            .map(|child| match child.as_rule() {
+                Rule::prefix_negation => "ke ".to_owned(),
+                Rule::or
+                | Rule::and
+                | Rule::concat
+                | Rule::add
+                | Rule::subtract
+                | Rule::multiply
+                | Rule::divide => {
+                    format!(" {} ", child.as_str())
+                }
+                _ => expr(child),
+            })
+            .collect(),
+        _ => {
+            let span = pair.as_span();
+            let input = span.get_input();
+
+            let mut res = String::new();
+            let mut last = span.start();
+            for child in pair.into_inner() {
+                let span = child.as_span();
+                res.push_str(&words(&input[last..span.start()]));
+                res.push_str(&expr(child));
+                last = span.end();
+            }
+            res.push_str(&words(&input[last..span.end()]));
+
+            res.trim().to_owned()
+        }
+    }
+}
+
+/// Rendering of the only child of `pair`.
+fn inner(pair: Pair<Rule>) -> String {
+    pair.into_inner().map(expr).collect()
+}
+
+/// `text` with every run of whitespace replaced with a single space.
+fn words(text: &str) -> String {
+    let mut res = String::new();
+    for c in text.chars() {
+        match c.is_whitespace() {
+            true if res.ends_with(' ') => {}
+            true => res.push(' '),
+            false => res.push(c),
+        }
+    }
+    res
+}
+
+/// Lines of the comments and module uses in `text`, blank lines being collapsed into one and left
+/// out at the start of the file.
+fn trivia(text: &str, start: bool) -> String {
+    let mut lines = text.split('\n').map(str::trim_end).collect::<Vec<_>>();
+    // What precedes the statement on its own line, usually its indentation
+    if lines.last() == Some(&"") {
+        lines.pop();
+    }
+
+    let mut res = String::new();
+    let mut blank = start;
+
+    for line in lines {
+        if line.is_empty() && blank {
+            continue;
+        }
+        blank = line.is_empty();
+
+        res.push_str(line);
+        res.push('\n');
+    }
+
+    res
+}
+
+/// Whether comments are found within the statement, that is outside of the tokens it is made of.
+fn has_comment(pair: &Pair<Rule>) -> bool {
+    let statement = pair.as_str();
+
+    let mut tokens = pair
+        .clone()
+        .into_inner()
+        .flatten()
+        .filter(|pair: &Pair<Rule>| pair.clone().into_inner().next().is_none())
+        .map(|pair| (pair.as_span().start(), pair.as_span().end()))
+        .collect::<Vec<_>>();
+    tokens.sort();
+
+    tokens.push((statement.len(), statement.len()));
+
+    let mut last = 0;
+    tokens.iter().any(|&(start, end)| {
+        let gap = statement.get(last..start).unwrap_or_default();
+        last = last.max(end);
+        gap.contains("''") || gap.contains("{-")
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn canonical() {
+        let source = "\n\n\
+                      '' Doubles its argument\n\
+                      ngop   lì'ukìng rey fa n alu\n\
+                      \t n*2\n\
+                      \x20     txew .   '' Still here\n\
+                      \n\n\n\
+                      {- Block comment -}\n\
+                      ngop 'u a alu (rey fa 1)  +2.\n\
+                      leyn vaykrr may' a sì 0 livu hol kem si wìntxu fa a ulte\n\
+                      '' Inner comment\n\
+                      hum   ftang.\n\n";
+
+        let expected = "\
+'' Doubles its argument
+ngop lì'ukìng rey fa n alu
+    n * 2
+txew. '' Still here
+
+{- Block comment -}
+ngop 'u a alu (rey fa 1) + 2.
+leyn vaykrr may' a sì 0 livu hol kem si wìntxu fa a ulte
+'' Inner comment
+hum   ftang.
+";
+
+        let formatted = format(SourceCode::Content(source.to_owned()), Base::Octal).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(
+            format(SourceCode::Content(formatted.clone()), Base::Octal).unwrap(),
+            formatted
+        );
+    }
+
+    fn formatted(source: &str, base: Base) -> String {
+        let formatted = format(SourceCode::Content(source.to_owned()), base).unwrap();
+        assert_eq!(
+            format(SourceCode::Content(formatted.clone()), base).unwrap(),
+            formatted
+        );
+        formatted
+    }
+
+    #[test]
+    fn match_blocks() {
+        let source = "ngop lì'ukìng name fa n alu pe n, txo 0 tsakrr san kewa sìk,\n\
+                      txo 1 tsakrr san 'aw sìk, txokefyaw san pxay sìk txew.\n\
+                      pe 2,   txo 2 tsakrr 1.";
+
+        let expected = "\
+ngop lì'ukìng name fa n alu
+    pe n,
+        txo 0 tsakrr
+            san kewa sìk,
+        txo 1 tsakrr
+            san 'aw sìk,
+        txokefyaw
+            san pxay sìk
+txew.
+pe 2,
+    txo 2 tsakrr
+        1
+txew.
+";
+
+        assert_eq!(formatted(source, Base::Octal), expected);
+    }
+
+    #[test]
+    fn else_if_chains() {
+        let source = "txo a lu 1, tsakrr 1, txokefyaw txo a lu 2, tsakrr 2.\n\
+                      txo a lu 1, tsakrr 1, txokefyaw txo a lu 2, tsakrr 2, txokefyaw 3.\n\
+                      ngop lì'ukìng f fa n alu txo n lu 0, tsakrr 0,\n\
+                      txokefyaw txo n ke lu 1, tsakrr 1 txew ulte n txew.";
+
+        let expected = "\
+txo a lu 1, tsakrr
+    1,
+txokefyaw txo a lu 2, tsakrr
+    2
+txew.
+txo a lu 1, tsakrr
+    1,
+txokefyaw txo a lu 2, tsakrr
+    2,
+txokefyaw
+    3.
+ngop lì'ukìng f fa n alu
+    txo n lu 0, tsakrr
+        0,
+    txokefyaw txo n ke lu 1, tsakrr
+        1
+    txew ulte
+    n
+txew.
+";
+
+        assert_eq!(formatted(source, Base::Octal), expected);
+    }
+
+    #[test]
+    fn surface_forms() {
+        // Unbound names are fine, the source only having to parse
+        let source = "1   melo.\nmune  melo.\na lu  b.\n(a+b)  *2.\na||b&&c.";
+        let expected = "1 melo.\nmune melo.\na lu b.\n(a + b) * 2.\na || b && c.\n";
+        assert_eq!(formatted(source, Base::Octal), expected);
+
+        let source = "9  +  1.";
+        assert!(format(SourceCode::Content(source.to_owned()), Base::Octal).is_err());
+        assert_eq!(formatted(source, Base::Decimal), "9 + 1.\n");
+    }
+}
@@ -0,0 +1,398 @@
+use crate::{
+    ast::{
+        binding::{Expr, FunDec, Statement, Tense, VarDec},
+        lib::AyNode,
+    },
+    interp::builtins,
+};
+
+use std::{collections::HashMap, rc::Rc};
+
+/// Replaces the only call to a trivial function by its body.
+///
+/// A function is trivial when its body is a single expression only made of literals, its own
+/// parameters and builtin calls, so that moving it to the call site cannot change what its names
+/// refer to. Calls are only inlined in the present tense and when their arguments are literals or
+/// variables, so that evaluating them once per use instead of once per call makes no difference.
+pub fn inline(ast: &[AyNode<Statement>]) -> Vec<AyNode<Statement>> {
+    let mut decs = HashMap::<String, Vec<Rc<FunDec>>>::new();
+    let mut calls = HashMap::<String, usize>::new();
+    collect(ast, &mut decs, &mut calls);
+
+    let inlined = decs
+        .iter()
+        .filter_map(|(name, overloads)| match overloads.as_slice() {
+            [dec] if calls.get(name) == Some(&1) && is_trivial(dec, &decs) => {
+                Some((name.clone(), dec.clone()))
+            }
+            _ => None,
+        })
+        .collect::<HashMap<String, Rc<FunDec>>>();
+
+    statements(ast, &inlined)
+}
+
+fn collect(
+    ast: &[AyNode<Statement>],
+    decs: &mut HashMap<String, Vec<Rc<FunDec>>>,
+    calls: &mut HashMap<String, usize>,
+) {
+    fn expr(AyNode { inner, .. }: &AyNode<Expr>, calls: &mut HashMap<String, usize>) {
+        match inner {
+            Expr::FunCall { name, args, .. } => {
+                *calls.entry(name.clone()).or_default() += 1;
+                args.iter().for_each(|node| expr(node, calls));
+            }
+            Expr::Array { items } => items.iter().for_each(|node| expr(node, calls)),
+            Expr::Comparison { left, right, .. }
+            | Expr::BinOp { left, right, .. }
+            | Expr::Fallback {
+                value: left,
+                fallback: right,
+            }
+            | Expr::Index {
+                value: left,
+                index: right,
+            } => {
+                expr(left, calls);
+                expr(right, calls);
+            }
+            Expr::Negated(node) | Expr::HasValue(node) | Expr::Field { value: node, .. } => {
+                expr(node, calls)
+            }
+            Expr::Number(_)
+            | Expr::Float(_)
+            | Expr::String(_)
+            | Expr::Bool(_)
+            | Expr::Var(_)
+            | Expr::None
+            | Expr::FunRef(_) => {}
+        }
+    }
+
+    ast.iter().for_each(|node| match &node.inner {
+        Statement::FunDec(dec) => {
+            decs.entry(dec.name.clone()).or_default().push(dec.clone());
+            collect(&dec.helpers, decs, calls);
+            collect(&dec.body, decs, calls);
+        }
+        Statement::VarDec(VarDec { values, .. }) | Statement::Assign(VarDec { values, .. }) => {
+            values.iter().for_each(|node| expr(node, calls))
+        }
+        Statement::Expr(node) | Statement::Assert(node) => expr(node, calls),
+        Statement::If {
+            cond,
+            then,
+            otherwise,
+        } => {
+            expr(cond, calls);
+            collect(then, decs, calls);
+            collect(otherwise, decs, calls);
+        }
+        Statement::Loop { cond, body, .. } => {
+            cond.iter().for_each(|node| expr(node, calls));
+            collect(body, decs, calls);
+        }
+        Statement::Break(_) | Statement::Continue(_) => {}
+    })
+}
+
+fn is_trivial(dec: &FunDec, decs: &HashMap<String, Vec<Rc<FunDec>>>) -> bool {
+    fn expr(
+        AyNode { inner, .. }: &AyNode<Expr>,
+        dec: &FunDec,
+        decs: &HashMap<String, Vec<Rc<FunDec>>>,
+    ) -> bool {
+        match inner {
+            // Builtins can be shadowed by user declarations
+            Expr::FunCall { name, args, .. } => {
+                builtins::names().any(|builtin| builtin == name)
+                    && !decs.contains_key(name)
+                    && args.iter().all(|node| expr(node, dec, decs))
+            }
+            Expr::Array { items } => items.iter().all(|node| expr(node, dec, decs)),
+            Expr::Comparison { left, right, .. }
+            | Expr::BinOp { left, right, .. }
+            | Expr::Fallback {
+                value: left,
+                fallback: right,
+            }
+            | Expr::Index {
+                value: left,
+                index: right,
+            } => expr(left, dec, decs) && expr(right, dec, decs),
+            Expr::Negated(node) | Expr::HasValue(node) | Expr::Field { value: node, .. } => {
+                expr(node, dec, decs)
+            }
+            Expr::Var(name) => dec.args.contains(name),
+            Expr::Number(_)
+            | Expr::Float(_)
+            | Expr::String(_)
+            | Expr::Bool(_)
+            | Expr::None
+            | Expr::FunRef(_) => true,
+        }
+    }
+
+    match dec.body.as_slice() {
+        [AyNode {
+            inner: Statement::Expr(body),
+            ..
+        }] => dec.helpers.is_empty() && expr(body, dec, decs),
+        _ => false,
+    }
+}
+
+fn statements(
+    ast: &[AyNode<Statement>],
+    inlined: &HashMap<String, Rc<FunDec>>,
+) -> Vec<AyNode<Statement>> {
+    ast.iter()
+        .map(|AyNode { span, inner }| AyNode {
+            span: span.clone(),
+            inner: match inner {
+                Statement::FunDec(dec) => Statement::FunDec(Rc::new(FunDec {
+                    name: dec.name.clone(),
+                    args: dec.args.clone(),
+                    body: statements(&dec.body, inlined),
+                    helpers: statements(&dec.helpers, inlined),
+                })),
+                Statement::VarDec(VarDec { names, values }) => Statement::VarDec(VarDec {
+                    names: names.clone(),
+                    values: exprs(values, inlined),
+                }),
+                Statement::Assign(VarDec { names, values }) => Statement::Assign(VarDec {
+                    names: names.clone(),
+                    values: exprs(values, inlined),
+                }),
+                Statement::Expr(node) => Statement::Expr(expr(node, inlined)),
+                Statement::Assert(node) => Statement::Assert(expr(node, inlined)),
+                Statement::If {
+                    cond,
+                    then,
+                    otherwise,
+                } => Statement::If {
+                    cond: expr(cond, inlined),
+                    then: statements(then, inlined),
+                    otherwise: statements(otherwise, inlined),
+                },
+                Statement::Loop { cond, body, label } => Statement::Loop {
+                    cond: cond.as_ref().map(|node| expr(node, inlined)),
+                    body: statements(body, inlined),
+                    label: label.clone(),
+                },
+                Statement::Break(_) | Statement::Continue(_) => inner.clone(),
+            },
+        })
+        .collect()
+}
+
+fn exprs(nodes: &[AyNode<Expr>], inlined: &HashMap<String, Rc<FunDec>>) -> Vec<AyNode<Expr>> {
+    nodes.iter().map(|node| expr(node, inlined)).collect()
+}
+
+fn expr(
+    AyNode { span, inner }: &AyNode<Expr>,
+    inlined: &HashMap<String, Rc<FunDec>>,
+) -> AyNode<Expr> {
+    let inner = match inner {
+        Expr::FunCall { tense, name, args } => {
+            let args = exprs(args, inlined);
+
+            match inlined.get(name) {
+                Some(dec)
+                    if *tense == Tense::Present
+                        && dec.args.len() == args.len()
+                        && args.iter().all(|arg| {
+                            matches!(
+                                arg.inner,
+                                Expr::Number(_)
+                                    | Expr::Float(_)
+                                    | Expr::String(_)
+                                    | Expr::Bool(_)
+                                    | Expr::Var(_)
+                                    | Expr::None
+                            )
+                        }) =>
+                {
+                    let Statement::Expr(body) = &dec.body[0].inner else {
+                        unreachable!("Only single expression bodies are inlined")
+                    };
+
+                    let args = dec.args.iter().cloned().zip(args).collect();
+
+                    // The call site is a better location for diagnostics than the body
+                    return AyNode {
+                        span: span.clone(),
+                        inner: substitute(body, &args).inner,
+                    };
+                }
+                _ => Expr::FunCall {
+                    tense: tense.clone(),
+                    name: name.clone(),
+                    args,
+                },
+            }
+        }
+        Expr::Array { items } => Expr::Array {
+            items: exprs(items, inlined),
+        },
+        Expr::Comparison {
+            left,
+            right,
+            operator,
+        } => Expr::Comparison {
+            left: Box::new(expr(left, inlined)),
+            right: Box::new(expr(right, inlined)),
+            operator: operator.clone(),
+        },
+        Expr::BinOp { op, left, right } => Expr::BinOp {
+            op: *op,
+            left: Box::new(expr(left, inlined)),
+            right: Box::new(expr(right, inlined)),
+        },
+        Expr::Negated(node) => Expr::Negated(Box::new(expr(node, inlined))),
+        Expr::HasValue(node) => Expr::HasValue(Box::new(expr(node, inlined))),
+        Expr::Fallback { value, fallback } => Expr::Fallback {
+            value: Box::new(expr(value, inlined)),
+            fallback: Box::new(expr(fallback, inlined)),
+        },
+        Expr::Index { value, index } => Expr::Index {
+            value: Box::new(expr(value, inlined)),
+            index: Box::new(expr(index, inlined)),
+        },
+        Expr::Field { value, name } => Expr::Field {
+            value: Box::new(expr(value, inlined)),
+            name: name.clone(),
+        },
+        Expr::Number(_)
+        | Expr::Float(_)
+        | Expr::String(_)
+        | Expr::Bool(_)
+        | Expr::Var(_)
+        | Expr::None
+        | Expr::FunRef(_) => inner.clone(),
+    };
+
+    AyNode {
+        span: span.clone(),
+        inner,
+    }
+}
+
+/// Replaces the parameters used in `node` by the corresponding arguments.
+fn substitute(node: &AyNode<Expr>, args: &HashMap<String, AyNode<Expr>>) -> AyNode<Expr> {
+    let inner = match &node.inner {
+        Expr::Var(name) => match args.get(name) {
+            Some(arg) => return arg.clone(),
+            None => node.inner.clone(),
+        },
+        Expr::FunCall {
+            tense,
+            name,
+            args: call_args,
+        } => Expr::FunCall {
+            tense: tense.clone(),
+            name: name.clone(),
+            args: call_args.iter().map(|arg| substitute(arg, args)).collect(),
+        },
+        Expr::Array { items } => Expr::Array {
+            items: items.iter().map(|item| substitute(item, args)).collect(),
+        },
+        Expr::Comparison {
+            left,
+            right,
+            operator,
+        } => Expr::Comparison {
+            left: Box::new(substitute(left, args)),
+            right: Box::new(substitute(right, args)),
+            operator: operator.clone(),
+        },
+        Expr::BinOp { op, left, right } => Expr::BinOp {
+            op: *op,
+            left: Box::new(substitute(left, args)),
+            right: Box::new(substitute(right, args)),
+        },
+        Expr::Negated(inner) => Expr::Negated(Box::new(substitute(inner, args))),
+        Expr::HasValue(inner) => Expr::HasValue(Box::new(substitute(inner, args))),
+        Expr::Fallback { value, fallback } => Expr::Fallback {
+            value: Box::new(substitute(value, args)),
+            fallback: Box::new(substitute(fallback, args)),
+        },
+        Expr::Index { value, index } => Expr::Index {
+            value: Box::new(substitute(value, args)),
+            index: Box::new(substitute(index, args)),
+        },
+        Expr::Field { value, name } => Expr::Field {
+            value: Box::new(substitute(value, args)),
+            name: name.clone(),
+        },
+        Expr::Number(_)
+        | Expr::Float(_)
+        | Expr::String(_)
+        | Expr::Bool(_)
+        | Expr::None
+        | Expr::FunRef(_) => node.inner.clone(),
+    };
+
+    AyNode {
+        span: node.span.clone(),
+        inner,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ast::{binding, lib::SourceCode, parsing},
+        interp::Interpreter,
+    };
+
+    use super::*;
+
+    fn run(ast: &[AyNode<Statement>]) -> (String, String) {
+        let mut interpreter = Interpreter::new(vec![]);
+        let res = interpreter.run(ast).unwrap();
+
+        (
+            res.to_string(),
+            String::from_utf8(interpreter.into_output()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn same_behaviour() {
+        let ast = parsing::parse(SourceCode::Content(
+            "ngop lì'ukìng mrr fa a sì b alu sung fa a sì b sì 1 txew.\n\
+             ngop 'u x alu 4.\n\
+             wìntxu fa mrr fa x sì 2.\n\
+             mrr fa x sì x."
+                .to_owned(),
+        ))
+        .unwrap();
+        let bound = binding::convert(&ast).unwrap();
+        let inlined = inline(&bound);
+
+        // Called twice so kept as is
+        assert_eq!(bound, inlined);
+
+        let ast = parsing::parse(SourceCode::Content(
+            "ngop lì'ukìng mrr fa a sì b alu sung fa a sì b sì 1 txew.\n\
+             ngop 'u x alu 4.\n\
+             wìntxu fa mrr fa x sì 2."
+                .to_owned(),
+        ))
+        .unwrap();
+        let bound = binding::convert(&ast).unwrap();
+        let inlined = inline(&bound);
+
+        assert_ne!(bound, inlined);
+        assert!(matches!(
+            &inlined[2].inner,
+            Statement::Expr(AyNode { inner: Expr::FunCall { name, args, .. }, .. })
+                if name == "wìntxu"
+                    && matches!(&args[0].inner, Expr::FunCall { name, .. } if name == "sung")
+        ));
+        assert_eq!(run(&bound), run(&inlined));
+    }
+}
@@ -0,0 +1,319 @@
+use crate::{
+    ast::{
+        binding::{Expr, FunDec, Statement, Tense, VarDec},
+        lib::AyNode,
+    },
+    error::span::Span,
+};
+
+use std::rc::Rc;
+
+/// Names that cannot be written in source code, so that they never clash with the program's.
+const LABEL: &str = "#recursion";
+const RESULT: &str = "#result";
+
+/// Rewrites tail-recursive functions into loops, so that they run in constant stack space.
+///
+/// Only functions whose every self-call is a present tense call in tail position are rewritten:
+/// the call then becomes an update of the parameters followed by a `kä`, and every other tail
+/// expression stores the result and leaves the loop. Self-calls in accumulator position, such as
+/// `sung fa n sì f fa ...`, are left alone as adding in a different order can change where an
+/// overflow happens or how floats get rounded.
+pub fn iterate(ast: &[AyNode<Statement>]) -> Vec<AyNode<Statement>> {
+    ast.iter()
+        .map(|AyNode { span, inner }| AyNode {
+            span: span.clone(),
+            inner: match inner {
+                Statement::FunDec(dec) => {
+                    let dec = FunDec {
+                        name: dec.name.clone(),
+                        args: dec.args.clone(),
+                        body: iterate(&dec.body),
+                        helpers: iterate(&dec.helpers),
+                    };
+
+                    Statement::FunDec(Rc::new(match is_tail_recursive(&dec) {
+                        true => FunDec {
+                            body: rewrite(&dec, span),
+                            ..dec
+                        },
+                        false => dec,
+                    }))
+                }
+                Statement::If {
+                    cond,
+                    then,
+                    otherwise,
+                } => Statement::If {
+                    cond: cond.clone(),
+                    then: iterate(then),
+                    otherwise: iterate(otherwise),
+                },
+                Statement::Loop { cond, body, label } => Statement::Loop {
+                    cond: cond.clone(),
+                    body: iterate(body),
+                    label: label.clone(),
+                },
+                _ => inner.clone(),
+            },
+        })
+        .collect()
+}
+
+fn is_tail_recursive(dec: &FunDec) -> bool {
+    // Every statement in tail position has to be an expression to know what gets returned
+    fn tails<'a>(stmts: &'a [AyNode<Statement>], res: &mut Vec<&'a AyNode<Expr>>) -> bool {
+        match stmts.last().map(|node| &node.inner) {
+            Some(Statement::Expr(expr)) => {
+                res.push(expr);
+                true
+            }
+            Some(Statement::If {
+                then, otherwise, ..
+            }) => tails(then, res) && tails(otherwise, res),
+            _ => false,
+        }
+    }
+
+    let mut tail_exprs = vec![];
+    if !dec.helpers.is_empty() || !tails(&dec.body, &mut tail_exprs) {
+        return false;
+    }
+
+    let tail_calls = tail_exprs
+        .iter()
+        .filter_map(|expr| self_call(dec, expr))
+        .collect::<Vec<&[AyNode<Expr>]>>();
+
+    !tail_calls.is_empty()
+        && tail_calls.iter().all(|args| args.len() == dec.args.len())
+        // No self-call anywhere else, nested in the arguments of a tail call included
+        && self_calls(dec, &dec.body) == tail_calls.len()
+        && tail_calls
+            .iter()
+            .all(|args| args.iter().all(|arg| expr_self_calls(dec, arg) == 0))
+        && !has_escapes(&dec.body)
+        && !shadows(&dec.body, &dec.args)
+}
+
+/// Whether the statements declare one of `names`, updating it would then miss the original one.
+fn shadows(stmts: &[AyNode<Statement>], names: &[String]) -> bool {
+    stmts.iter().any(|node| match &node.inner {
+        Statement::VarDec(dec) => dec.names.iter().any(|name| names.contains(name)),
+        Statement::If {
+            then, otherwise, ..
+        } => shadows(then, names) || shadows(otherwise, names),
+        Statement::Loop { body, .. } => shadows(body, names),
+        _ => false,
+    })
+}
+
+/// Arguments of `expr` if it is a present tense call to `dec`.
+fn self_call<'a>(dec: &FunDec, expr: &'a AyNode<Expr>) -> Option<&'a [AyNode<Expr>]> {
+    match &expr.inner {
+        Expr::FunCall {
+            tense: Tense::Present,
+            name,
+            args,
+        } if *name == dec.name => Some(args),
+        _ => None,
+    }
+}
+
+/// Counts the calls to `dec` in the statements, bailing out with `usize::MAX` on nested
+/// declarations as they could shadow it.
+fn self_calls(dec: &FunDec, stmts: &[AyNode<Statement>]) -> usize {
+    stmts.iter().fold(0, |acc, node| {
+        acc.saturating_add(match &node.inner {
+            Statement::FunDec(_) => usize::MAX,
+            Statement::VarDec(VarDec { values, .. }) | Statement::Assign(VarDec { values, .. }) => {
+                values.iter().map(|value| expr_self_calls(dec, value)).sum()
+            }
+            Statement::Expr(expr) | Statement::Assert(expr) => expr_self_calls(dec, expr),
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => expr_self_calls(dec, cond)
+                .saturating_add(self_calls(dec, then))
+                .saturating_add(self_calls(dec, otherwise)),
+            Statement::Loop { cond, body, .. } => cond
+                .iter()
+                .map(|cond| expr_self_calls(dec, cond))
+                .sum::<usize>()
+                .saturating_add(self_calls(dec, body)),
+            Statement::Break(_) | Statement::Continue(_) => 0,
+        })
+    })
+}
+
+fn expr_self_calls(dec: &FunDec, AyNode { inner, .. }: &AyNode<Expr>) -> usize {
+    match inner {
+        Expr::FunCall { name, args, .. } => {
+            usize::from(*name == dec.name)
+                + args
+                    .iter()
+                    .map(|arg| expr_self_calls(dec, arg))
+                    .sum::<usize>()
+        }
+        Expr::Array { items } => items.iter().map(|item| expr_self_calls(dec, item)).sum(),
+        Expr::Comparison { left, right, .. }
+        | Expr::BinOp { left, right, .. }
+        | Expr::Fallback {
+            value: left,
+            fallback: right,
+        }
+        | Expr::Index {
+            value: left,
+            index: right,
+        } => expr_self_calls(dec, left) + expr_self_calls(dec, right),
+        Expr::Negated(node) | Expr::HasValue(node) | Expr::Field { value: node, .. } => {
+            expr_self_calls(dec, node)
+        }
+        Expr::Number(_)
+        | Expr::Float(_)
+        | Expr::String(_)
+        | Expr::Bool(_)
+        | Expr::Var(_)
+        | Expr::None
+        | Expr::FunRef(_) => 0,
+    }
+}
+
+/// Whether a `hum` or `kä` outside of a nested loop would end up targeting the rewritten one.
+fn has_escapes(stmts: &[AyNode<Statement>]) -> bool {
+    stmts.iter().any(|node| match &node.inner {
+        Statement::Break(_) | Statement::Continue(_) => true,
+        Statement::If {
+            then, otherwise, ..
+        } => has_escapes(then) || has_escapes(otherwise),
+        _ => false,
+    })
+}
+
+/// `ngop 'u #result alu kewu. leyn tstxo #recursion kem si <body> ftang. #result.`
+fn rewrite(dec: &FunDec, span: &Span) -> Vec<AyNode<Statement>> {
+    fn tails(dec: &FunDec, stmts: &[AyNode<Statement>]) -> Vec<AyNode<Statement>> {
+        let Some((last, rest)) = stmts.split_last() else {
+            return vec![];
+        };
+        let node = |inner| AyNode {
+            span: last.span.clone(),
+            inner,
+        };
+
+        let tail = match &last.inner {
+            Statement::Expr(expr) => match self_call(dec, expr) {
+                Some(args) => vec![
+                    node(Statement::Assign(VarDec {
+                        names: dec.args.clone(),
+                        values: args.to_vec(),
+                    })),
+                    node(Statement::Continue(Some(LABEL.to_owned()))),
+                ],
+                None => vec![
+                    node(Statement::Assign(VarDec {
+                        names: vec![RESULT.to_owned()],
+                        values: vec![expr.clone()],
+                    })),
+                    node(Statement::Break(Some(LABEL.to_owned()))),
+                ],
+            },
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => vec![node(Statement::If {
+                cond: cond.clone(),
+                then: tails(dec, then),
+                otherwise: tails(dec, otherwise),
+            })],
+            _ => unreachable!("Only expressions and conditions are in tail position"),
+        };
+
+        rest.iter().cloned().chain(tail).collect()
+    }
+
+    let node = |inner| AyNode {
+        span: span.clone(),
+        inner,
+    };
+
+    vec![
+        node(Statement::VarDec(VarDec {
+            names: vec![RESULT.to_owned()],
+            values: vec![node_expr(span, Expr::None)],
+        })),
+        node(Statement::Loop {
+            cond: None,
+            body: tails(dec, &dec.body),
+            label: Some(LABEL.to_owned()),
+        }),
+        node(Statement::Expr(node_expr(
+            span,
+            Expr::Var(RESULT.to_owned()),
+        ))),
+    ]
+}
+
+fn node_expr(span: &Span, inner: Expr) -> AyNode<Expr> {
+    AyNode {
+        span: span.clone(),
+        inner,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ast::{binding, lib::SourceCode, parsing},
+        interp::{value::Value, Interpreter},
+    };
+
+    use super::*;
+
+    fn run(ast: &[AyNode<Statement>]) -> (Value, String) {
+        let mut interpreter = Interpreter::new(vec![]);
+        let res = interpreter.run(ast).unwrap();
+
+        (res, String::from_utf8(interpreter.into_output()).unwrap())
+    }
+
+    fn contains_loop(stmts: &[AyNode<Statement>]) -> bool {
+        stmts.iter().any(|node| match &node.inner {
+            Statement::FunDec(dec) => contains_loop(&dec.body),
+            Statement::Loop { .. } => true,
+            _ => false,
+        })
+    }
+
+    #[test]
+    fn tail_recursion() {
+        let ast = parsing::parse(SourceCode::File(
+            "./examples/features/functions/valid/recursive_sum.ay".to_owned(),
+        ))
+        .unwrap();
+        let bound = binding::convert(&ast).unwrap();
+        let iterative = iterate(&bound);
+
+        assert!(!contains_loop(&bound));
+        assert!(contains_loop(&iterative));
+        assert_eq!(run(&bound), run(&iterative));
+        assert_eq!(run(&iterative).0, Value::Number(55));
+    }
+
+    #[test]
+    fn accumulator_position() {
+        let ast = parsing::parse(SourceCode::Content(
+            "ngop lì'ukìng sum fa n alu\n\
+               txo n lu 0, tsakrr 0, txokefyaw sung fa n sì sum fa sung fa n sì ke 1\n\
+             txew.\n\
+             sum fa 12."
+                .to_owned(),
+        ))
+        .unwrap();
+        let bound = binding::convert(&ast).unwrap();
+
+        assert_eq!(iterate(&bound), bound);
+    }
+}
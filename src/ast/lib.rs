@@ -1,50 +1,296 @@
-use crate::error::span::Span;
+use crate::error::{
+    span::{self, Span},
+    trace::Trace,
+};
 
-use std::str::FromStr;
+use strum_macros::{Display, EnumIter, EnumString, IntoStaticStr};
 
-use {paste::paste, strum_macros::EnumString};
-
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum SourceCode {
     File(String),
     Content(String),
+    /// Raw bytes, e.g. from the network or an archive, validated as UTF-8 before parsing.
+    Bytes(Vec<u8>),
 }
 
 /// Node containing a `Span` of code and the corresponding AST
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AyNode<Inner: Node> {
     pub span: Span,
     pub inner: Inner,
 }
 
+impl<Inner: Node> AyNode<Inner> {
+    /// Swaps the node's content for `inner`, keeping its span.
+    pub fn replace_inner<Other: Node>(self, inner: Other) -> AyNode<Other> {
+        AyNode {
+            span: self.span,
+            inner,
+        }
+    }
+
+    pub fn set_inner(&mut self, inner: Inner) {
+        self.inner = inner;
+    }
+
+    /// Transforms the node's content, keeping its span.
+    pub fn map<Other: Node>(self, f: impl FnOnce(Inner) -> Other) -> AyNode<Other> {
+        AyNode {
+            inner: f(self.inner),
+            span: self.span,
+        }
+    }
+
+    /// Transforms the node's content, keeping its span, unless `f` fails.
+    pub fn try_map<Other: Node>(
+        self,
+        f: impl FnOnce(Inner) -> Result<Other, Trace>,
+    ) -> Result<AyNode<Other>, Trace> {
+        Ok(AyNode {
+            inner: f(self.inner)?,
+            span: self.span,
+        })
+    }
+
+    /// Node borrowing this one's content, e.g. to [`map`](Self::map) it without giving it up.
+    pub fn as_ref(&self) -> AyNode<&Inner> {
+        AyNode {
+            span: self.span.clone(),
+            inner: &self.inner,
+        }
+    }
+
+    /// Node spanning from `first` through `last`, e.g. the spans of both operands of an operation.
+    /// Spans of pairs are given rather than those of their nodes, which leave out the parentheses
+    /// of groups.
+    pub fn spanning(first: pest::Span, last: pest::Span, inner: Inner) -> Self {
+        AyNode {
+            span: span::join(first, last).into(),
+            inner,
+        }
+    }
+
+    /// Node spanning from `prefix`, e.g. that of an operator, through `operand`.
+    pub fn prefixed(prefix: pest::Span, operand: pest::Span, inner: Inner) -> Self {
+        Self::spanning(prefix, operand, inner)
+    }
+}
+
+/// `{:#?}` shows the whole node while `{:?}` is compacted to `inner@line:col`.
+impl<Inner: Node + std::fmt::Debug> std::fmt::Debug for AyNode<Inner> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            f.debug_struct("AyNode")
+                .field("span", &self.span)
+                .field("inner", &self.inner)
+                .finish()
+        } else {
+            let (line, col) = self.span.start();
+            write!(f, "{:?}@{line}:{col}", self.inner)
+        }
+    }
+}
+
+/// Only the content is rendered, see the `Display` implementation of each node type.
+impl<Inner: Node + std::fmt::Display> std::fmt::Display for AyNode<Inner> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
 pub trait Node {}
 
+impl<Inner: Node> Node for &Inner {}
+
+/// Equality of trees regardless of where their nodes are in the source, for tests to check the
+/// shape of a tree without pinning its offsets.
+pub trait StructuralEq {
+    fn structural_eq(&self, other: &Self) -> bool;
+}
+
+impl<Inner: Node + StructuralEq> StructuralEq for AyNode<Inner> {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self.inner.structural_eq(&other.inner)
+    }
+}
+
+impl<T: StructuralEq> StructuralEq for [T] {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .zip(other)
+                .all(|(left, right)| left.structural_eq(right))
+    }
+}
+
+impl<T: StructuralEq> StructuralEq for Vec<T> {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self.as_slice().structural_eq(other.as_slice())
+    }
+}
+
+impl<T: StructuralEq + ?Sized> StructuralEq for Box<T> {
+    fn structural_eq(&self, other: &Self) -> bool {
+        (**self).structural_eq(other)
+    }
+}
+
+impl<T: StructuralEq + ?Sized> StructuralEq for std::rc::Rc<T> {
+    fn structural_eq(&self, other: &Self) -> bool {
+        (**self).structural_eq(other)
+    }
+}
+
+impl<T: StructuralEq> StructuralEq for Option<T> {
+    fn structural_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(left), Some(right)) => left.structural_eq(right),
+            (left, right) => left.is_none() && right.is_none(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub enum AyType {
     #[default]
     Bool,
     Number,
+    Float,
     String,
+    /// Result of statements that do not produce a value, such as declarations and loops.
+    Unit,
     Array(Box<AyType>),
+    /// Either a value of the inner type or `kewu`.
+    Optional(Box<AyType>),
     Function {
         args: Vec<AyType>,
         result: Box<AyType>,
     },
+    /// Type yet to be found, only met while typing.
+    Var(usize),
 }
 
-#[derive(Debug, EnumString)]
-#[repr(i64)]
-pub enum Multiplier {
-    #[strum(serialize = "melo")]
-    Double = 2,
-    #[strum(serialize = "pxelo")]
-    Triple = 3,
+impl std::fmt::Display for AyType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AyType::Bool => write!(f, "Bool"),
+            AyType::Number => write!(f, "Number"),
+            AyType::Float => write!(f, "Float"),
+            AyType::String => write!(f, "String"),
+            AyType::Unit => write!(f, "Unit"),
+            AyType::Array(item) => write!(f, "Array of {item}"),
+            AyType::Optional(inner) => write!(f, "Optional {inner}"),
+            AyType::Function { args, result } => write!(
+                f,
+                "({}) -> {result}",
+                args.iter()
+                    .map(AyType::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            AyType::Var(_) => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Base numeric literals are read in, the language itself counts in octal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+#[repr(u32)]
+pub enum Base {
+    #[default]
+    Octal = 8,
+    Decimal = 10,
 }
 
-#[derive(Debug, EnumString, PartialEq, Eq, Clone)]
+/// Words for "n times", multiplying the octal number they follow, up to the largest digit.
+pub const MULTIPLIERS: [(&str, i64); 7] = [
+    ("'awlo", 1),
+    ("melo", 2),
+    ("pxelo", 3),
+    ("tsìlo", 4),
+    ("mrrlo", 5),
+    ("puxlo", 6),
+    ("kinglo", 7),
+];
+
+/// Factor of the multiplier `word`, `None` if it is not one.
+pub fn multiplier(word: &str) -> Option<i64> {
+    MULTIPLIERS
+        .iter()
+        .find(|(name, _)| *name == word)
+        .map(|(_, factor)| *factor)
+}
+
+/// Na'vi words for the octal digits.
+pub const DIGIT_WORDS: [&str; 8] = [
+    "kew", "'aw", "mune", "pxey", "tsìng", "mrr", "pukap", "kinä",
+];
+
+/// Value of the Na'vi number `word`, `None` if it is not one.
+///
+/// Multiples of eight are `vol` with the number of eights as a prefix, the units being added as a
+/// suffix before which `vol` loses its `l`, e.g. `mevolaw` for 2 * 8 + 1.
+pub fn number_word(word: &str) -> Option<i64> {
+    const EIGHTS: [&str; 7] = ["", "me", "pxe", "tsì", "mrr", "pu", "ki"];
+    const UNITS: [&str; 8] = ["l", "law", "mun", "pey", "sìng", "mrr", "fu", "hin"];
+
+    DIGIT_WORDS
+        .iter()
+        .position(|digit| *digit == word)
+        .map(|digit| digit as i64)
+        .or_else(|| {
+            (0..EIGHTS.len())
+                .flat_map(|eights| (0..UNITS.len()).map(move |units| (eights, units)))
+                .find(|(eights, units)| word == format!("{}vo{}", EIGHTS[*eights], UNITS[*units]))
+                .map(|(eights, units)| 8 * (eights as i64 + 1) + units as i64)
+        })
+}
+
+/// Also callable as builtin functions named after their keyword.
+#[derive(Debug, EnumString, EnumIter, IntoStaticStr, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ComparisonOperator {
     #[strum(serialize = "teng")]
     Equals,
+    /// Fewer
+    #[strum(serialize = "hol")]
+    Less,
+    /// More
+    #[strum(serialize = "pxay")]
+    Greater,
+}
+
+/// Logical and arithmetic operators, from the loosest to the tightest binding pair.
+#[derive(Debug, EnumString, IntoStaticStr, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BinaryOperator {
+    /// Only evaluates its right operand when the left one is false.
+    #[strum(serialize = "||")]
+    Or,
+    /// Only evaluates its right operand when the left one is true.
+    #[strum(serialize = "&&")]
+    And,
+    /// Joins two strings or two arrays.
+    #[strum(serialize = "++")]
+    Concat,
+    #[strum(serialize = "+")]
+    Add,
+    #[strum(serialize = "-")]
+    Subtract,
+    #[strum(serialize = "*")]
+    Multiply,
+    #[strum(serialize = "/")]
+    Divide,
+}
+
+impl BinaryOperator {
+    /// Whether both operands are booleans, the right one not always being evaluated.
+    pub fn is_logical(&self) -> bool {
+        matches!(self, BinaryOperator::Or | BinaryOperator::And)
+    }
 }
 
 macro_rules! convert_iter {
@@ -93,3 +339,102 @@ macro_rules! wrap_scope {
 }
 
 pub(crate) use wrap_scope;
+
+#[cfg(test)]
+mod test {
+    use crate::ast::parsing::{parse, Expr, Statement};
+
+    use super::*;
+
+    #[test]
+    fn joined_spans() {
+        let input = "1 sì\n  ke 2";
+        let span = |start, end| pest::Span::new(input, start, end).unwrap();
+        let (one, negated, two) = (span(0, 1), span(8, 12), span(11, 12));
+
+        // The text between both spans is kept
+        let node = AyNode::spanning(one, two, Expr::None);
+        assert_eq!(node.span.as_str(), input);
+        assert_eq!((node.span.start(), node.span.end()), ((1, 1), (2, 7)));
+
+        // Both ways, the span being the smallest one covering both nodes
+        assert_eq!(AyNode::spanning(two, one, Expr::None), node);
+        assert_eq!(
+            AyNode::prefixed(negated, two, Expr::None).span,
+            Span::from(negated)
+        );
+    }
+
+    #[test]
+    fn replace_inner() {
+        let ast = parse(SourceCode::Content("ngop 'u a alu 7.".to_owned())).unwrap();
+        let node = ast[0].clone();
+        let span = node.span.clone();
+
+        let mut replaced = node.replace_inner(Statement::Expr(AyNode {
+            span: span.clone(),
+            inner: Expr::Number(7),
+        }));
+        assert_eq!(replaced.span, span);
+
+        replaced.set_inner(Statement::Break(None));
+        assert_eq!(replaced.span, span);
+        assert_eq!(replaced.inner, Statement::Break(None));
+    }
+
+    #[test]
+    fn map() {
+        let ast = parse(SourceCode::Content("ke 7.".to_owned())).unwrap();
+        let Statement::Expr(node) = &ast[0].inner else {
+            panic!("Expected an expression");
+        };
+
+        let mapped = node.clone().map(|inner| match inner {
+            Expr::Negated(node) => node.inner,
+            inner => inner,
+        });
+        assert_eq!(mapped.span, node.span);
+        assert_eq!(mapped.inner, Expr::Number(7));
+
+        // Borrowed, the original node being left as is
+        let statement = ast[0].as_ref().map(|inner| {
+            Statement::Assert(match inner {
+                Statement::Expr(node) => node.clone(),
+                _ => unreachable!(),
+            })
+        });
+        assert_eq!(statement.span, ast[0].span);
+        assert!(matches!(ast[0].inner, Statement::Expr(_)));
+    }
+
+    #[test]
+    fn try_map() {
+        use crate::error::{
+            trace::{Stage, TraceError},
+            trace_error::Error,
+        };
+
+        let ast = parse(SourceCode::Content("7.\nke 7.".to_owned())).unwrap();
+        let number = |node: AyNode<&Statement>| {
+            let span = node.span.clone();
+
+            node.try_map(|inner| match inner {
+                Statement::Expr(AyNode {
+                    inner: Expr::Number(number),
+                    ..
+                }) => Ok(Expr::Number(number * 2)),
+                _ => Err(Trace::new(
+                    Stage::Parsing,
+                    Error::from_span(span, "Expected a number"),
+                )),
+            })
+        };
+
+        let doubled = number(ast[0].as_ref()).unwrap();
+        assert_eq!(doubled.span, ast[0].span);
+        assert_eq!(doubled.inner, Expr::Number(14));
+
+        let trace = number(ast[1].as_ref()).unwrap_err();
+        assert!(trace.to_string_colored(false).contains("Expected a number"));
+    }
+}
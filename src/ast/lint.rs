@@ -0,0 +1,497 @@
+use crate::{
+    ast::{
+        lib::{wrap_scope, AyNode, Base, ComparisonOperator},
+        parsing::{Expr, Statement},
+    },
+    error::{
+        trace::{Severity, Stage, Trace, TraceError},
+        trace_error::Error,
+    },
+};
+
+use {
+    quickscope::ScopeMap,
+    strum::IntoEnumIterator,
+    strum_macros::{EnumIter, EnumString},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumString, EnumIter)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Lint {
+    /// `0 melo`
+    ZeroMultiplier,
+    /// `ke ke a`
+    DoubleNegation,
+    /// `sung fa a sì 0`
+    IdentityOperation,
+    /// `may' a sì a livu teng`
+    SelfComparison,
+    /// `ngop 'u a alu 1. ngop lì'ukìng f fa a alu a txew.`
+    ShadowedParameter,
+    /// `ngop lì'ukìng f fa a alu ngop 'u a alu 1 ulte a txew.`
+    ShadowingVariable,
+    /// `010` read as octal, opt-in
+    LeadingZero,
+}
+
+impl Lint {
+    /// Lints that run unless another selection is given.
+    pub fn defaults() -> impl Iterator<Item = Lint> {
+        Lint::iter().filter(|lint| *lint != Lint::LeadingZero)
+    }
+
+    fn check(&self, node: &AyNode<Expr>, base: Base) -> Option<Trace> {
+        match self {
+            Lint::ZeroMultiplier => zero_multiplier(node),
+            Lint::DoubleNegation => double_negation(node),
+            Lint::IdentityOperation => identity_operation(node),
+            Lint::SelfComparison => self_comparison(node),
+            // Need the surrounding scopes, see `shadowed_parameters` and `shadowing_variables`
+            Lint::ShadowedParameter | Lint::ShadowingVariable => None,
+            Lint::LeadingZero => leading_zero(node, base),
+        }
+    }
+}
+
+/// Looks for likely mistakes in a program parsed with numbers in `base`, returning them as
+/// warnings.
+pub fn lint(ast: &[AyNode<Statement>], lints: &[Lint], base: Base) -> Vec<Trace> {
+    let mut warnings = exprs(ast)
+        .into_iter()
+        .flat_map(|node| lints.iter().filter_map(|lint| lint.check(node, base)))
+        .collect::<Vec<Trace>>();
+
+    if lints.contains(&Lint::ShadowedParameter) {
+        warnings.extend(shadowed_parameters(ast));
+    }
+    if lints.contains(&Lint::ShadowingVariable) {
+        warnings.extend(shadowing_variables(ast));
+    }
+
+    warnings
+}
+
+/// Multiplying zero is always zero, the multiplier was probably meant for another literal.
+///
+/// Numbers span their literal alone, the multiplier being the word following its digits.
+fn zero_multiplier(AyNode { span, inner }: &AyNode<Expr>) -> Option<Trace> {
+    let mut words = span.as_str().split_whitespace().skip(1);
+
+    match (inner, words.next()) {
+        (Expr::Number(0), Some(multiplier)) => Some(warning(Error::from_span(
+            span.clone(),
+            format!("Multiplying 0 by `{multiplier}` always yields 0").as_ref(),
+        ))),
+        _ => None,
+    }
+}
+
+/// A leading zero hints at a decimal number written out of habit, which is read as octal anyway.
+/// Decimal numbers are read as written, zeros or not.
+fn leading_zero(AyNode { span, inner }: &AyNode<Expr>, base: Base) -> Option<Trace> {
+    if base == Base::Decimal {
+        return None;
+    }
+
+    let literal = span.as_str().split_whitespace().next()?;
+    let (sign, digits) = match literal.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", literal),
+    };
+
+    match inner {
+        Expr::Number(value) if digits.len() > 1 && digits.starts_with('0') => {
+            Some(warning(Error::from_span(
+                span.clone(),
+                format!(
+                    "`{literal}` is read as octal, its value is {value} and not {sign}{}",
+                    digits.trim_start_matches('0')
+                )
+                .as_ref(),
+            )))
+        }
+        _ => None,
+    }
+}
+
+/// Negating twice gives back the original value.
+fn double_negation(AyNode { span, inner }: &AyNode<Expr>) -> Option<Trace> {
+    match inner {
+        Expr::Negated(node) => match &node.inner {
+            Expr::Negated(node) => Some(warning(Error::from_span(
+                span.clone(),
+                format!(
+                    "Double negation cancels out, use `{}` directly",
+                    node.span.as_str()
+                )
+                .as_ref(),
+            ))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Adding zero does nothing.
+fn identity_operation(AyNode { span, inner }: &AyNode<Expr>) -> Option<Trace> {
+    match inner {
+        Expr::FunCall { name, args } if name == "sung" => {
+            let (zeros, rest): (Vec<_>, Vec<_>) = args
+                .iter()
+                .partition(|arg| matches!(arg.inner, Expr::Number(0)));
+
+            (!zeros.is_empty()).then(|| {
+                let suggestion = match rest.as_slice() {
+                    [] => "0".to_owned(),
+                    [arg] => arg.span.as_str().to_owned(),
+                    rest => format!(
+                        "sung fa {}",
+                        rest.iter()
+                            .map(|arg| arg.span.as_str())
+                            .collect::<Vec<&str>>()
+                            .join(" sì ")
+                    ),
+                };
+
+                warning(Error::from_span(
+                    span.clone(),
+                    format!("Adding 0 has no effect, use `{suggestion}`").as_ref(),
+                ))
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Comparing an expression to itself always yields the same result.
+fn self_comparison(AyNode { span, inner }: &AyNode<Expr>) -> Option<Trace> {
+    match inner {
+        Expr::Comparison {
+            left,
+            right,
+            operator: ComparisonOperator::Equals,
+        } if left.span.as_str() == right.span.as_str() => Some(warning(Error::from_span(
+            span.clone(),
+            format!("`{}` is always equal to itself", left.span.as_str()).as_ref(),
+        ))),
+        _ => None,
+    }
+}
+
+/// A parameter named like an outer variable makes that variable unreachable from the body.
+fn shadowed_parameters(ast: &[AyNode<Statement>]) -> Vec<Trace> {
+    fn statement(
+        AyNode { span, inner }: &AyNode<Statement>,
+        vars: &mut ScopeMap<String, ()>,
+        res: &mut Vec<Trace>,
+    ) {
+        match inner {
+            Statement::VarDec { names, .. } => {
+                names.iter().for_each(|name| vars.define(name.clone(), ()))
+            }
+            Statement::FunDec {
+                name,
+                args,
+                body,
+                helpers,
+            } => {
+                res.extend(
+                    args.iter()
+                        .filter(|arg| vars.contains_key(*arg))
+                        .map(|arg| {
+                            warning(Error::from_span(
+                                span.clone(),
+                                format!("Parameter '{arg}' of '{name}' shadows an outer variable")
+                                    .as_ref(),
+                            ))
+                        }),
+                );
+
+                wrap_scope!(
+                    vars | {
+                        args.iter().for_each(|arg| vars.define(arg.clone(), ()));
+                        helpers
+                            .iter()
+                            .chain(body.iter())
+                            .for_each(|node| statement(node, vars, res));
+                    }
+                );
+            }
+            Statement::If {
+                then, otherwise, ..
+            } => {
+                wrap_scope!(vars | { then.iter().for_each(|node| statement(node, vars, res)) });
+                wrap_scope!(
+                    vars | { otherwise.iter().for_each(|node| statement(node, vars, res)) }
+                );
+            }
+            Statement::Loop { body, .. } => {
+                wrap_scope!(vars | { body.iter().for_each(|node| statement(node, vars, res)) })
+            }
+            Statement::Expr(_)
+            | Statement::Assert(_)
+            | Statement::Break(_)
+            | Statement::Continue(_) => {}
+        }
+    }
+
+    let mut vars = ScopeMap::new();
+    let mut res = vec![];
+    ast.iter()
+        .for_each(|node| statement(node, &mut vars, &mut res));
+    res
+}
+
+/// A variable declared in a function under the name of one of its parameters hides the argument
+/// from the rest of the block. Parameters of enclosing functions count as well.
+fn shadowing_variables(ast: &[AyNode<Statement>]) -> Vec<Trace> {
+    /// Parameters in scope along with the name of their function, innermost last.
+    fn statements<'a>(
+        ast: &'a [AyNode<Statement>],
+        params: &[(&'a str, &'a str)],
+        res: &mut Vec<Trace>,
+    ) {
+        ast.iter().for_each(|AyNode { span, inner }| match inner {
+            Statement::VarDec { names, .. } => names
+                .iter()
+                .filter_map(|name| params.iter().rev().find(|(param, _)| param == name))
+                .for_each(|(param, fun)| {
+                    res.push(warning(Error::from_span(
+                        span.clone(),
+                        format!("Variable '{param}' shadows a parameter of '{fun}'").as_ref(),
+                    )))
+                }),
+            Statement::FunDec {
+                name,
+                args,
+                body,
+                helpers,
+            } => {
+                let params = params
+                    .iter()
+                    .copied()
+                    .chain(args.iter().map(|arg| (arg.as_str(), name.as_str())))
+                    .collect::<Vec<_>>();
+
+                statements(helpers, &params, res);
+                statements(body, &params, res);
+            }
+            Statement::If {
+                then, otherwise, ..
+            } => {
+                statements(then, params, res);
+                statements(otherwise, params, res);
+            }
+            Statement::Loop { body, .. } => statements(body, params, res),
+            Statement::Expr(_)
+            | Statement::Assert(_)
+            | Statement::Break(_)
+            | Statement::Continue(_) => {}
+        })
+    }
+
+    let mut res = vec![];
+    statements(ast, &[], &mut res);
+    res
+}
+
+fn warning(err: Error) -> Trace {
+    Trace::new(Stage::Parsing, err).with_severity(Severity::Warning)
+}
+
+/// Collects every expression of the program, parents before their children.
+fn exprs(ast: &[AyNode<Statement>]) -> Vec<&AyNode<Expr>> {
+    fn statement<'a>(node: &'a AyNode<Statement>, res: &mut Vec<&'a AyNode<Expr>>) {
+        match &node.inner {
+            Statement::FunDec { body, helpers, .. } => body
+                .iter()
+                .chain(helpers.iter())
+                .for_each(|node| statement(node, res)),
+            Statement::VarDec { values, .. } => values.iter().for_each(|node| expr(node, res)),
+            Statement::Expr(node) | Statement::Assert(node) => expr(node, res),
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                expr(cond, res);
+                then.iter()
+                    .chain(otherwise.iter())
+                    .for_each(|node| statement(node, res));
+            }
+            Statement::Loop { cond, body, .. } => {
+                cond.iter().for_each(|node| expr(node, res));
+                body.iter().for_each(|node| statement(node, res));
+            }
+            Statement::Break(_) | Statement::Continue(_) => {}
+        }
+    }
+
+    fn expr<'a>(node: &'a AyNode<Expr>, res: &mut Vec<&'a AyNode<Expr>>) {
+        res.push(node);
+
+        match &node.inner {
+            Expr::FunCall { args: items, .. } | Expr::Array { items } => {
+                items.iter().for_each(|node| expr(node, res))
+            }
+            Expr::Comparison { left, right, .. }
+            | Expr::BinOp { left, right, .. }
+            | Expr::Fallback {
+                value: left,
+                fallback: right,
+            }
+            | Expr::Index {
+                value: left,
+                index: right,
+            } => {
+                expr(left, res);
+                expr(right, res);
+            }
+            Expr::Negated(node) | Expr::HasValue(node) | Expr::Field { value: node, .. } => {
+                expr(node, res)
+            }
+            Expr::Number(_)
+            | Expr::Float(_)
+            | Expr::String(_)
+            | Expr::Bool(_)
+            | Expr::Ident(_)
+            | Expr::None
+            | Expr::FunRef(_) => {}
+        }
+    }
+
+    let mut res = vec![];
+    ast.iter().for_each(|node| statement(node, &mut res));
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ast::{
+        lib::SourceCode,
+        parsing::{parse, parse_with_base},
+    };
+
+    use super::*;
+
+    fn all() -> Vec<Lint> {
+        Lint::iter().collect()
+    }
+
+    #[test]
+    fn redundant_operations() {
+        let ast = parse(SourceCode::File(
+            "./examples/features/expressions/valid/redundant.ay".to_owned(),
+        ))
+        .unwrap();
+
+        assert_eq!(lint(&ast, &all(), Base::Octal).len(), 4);
+        assert_eq!(lint(&ast, &[Lint::DoubleNegation], Base::Octal).len(), 1);
+        assert_eq!(lint(&ast, &[Lint::IdentityOperation], Base::Octal).len(), 2);
+        assert_eq!(lint(&ast, &[Lint::SelfComparison], Base::Octal).len(), 1);
+        assert!(lint(&ast, &[], Base::Octal).is_empty());
+    }
+
+    #[test]
+    fn shadowed_parameter() {
+        let ast = parse(SourceCode::File(
+            "./examples/features/functions/valid/shadowing.ay".to_owned(),
+        ))
+        .unwrap();
+
+        let warnings = lint(&ast, &[Lint::ShadowedParameter], Base::Octal);
+
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0]
+            .frames()
+            .any(|(_, err)| err.message().contains("'a' of 'rey'")));
+    }
+
+    #[test]
+    fn shadowing_variable() {
+        let warnings = |source: &str| {
+            let ast = parse(SourceCode::Content(source.to_owned())).unwrap();
+
+            lint(&ast, &[Lint::ShadowingVariable], Base::Octal)
+                .iter()
+                .flat_map(|trace| trace.frames().map(|(_, err)| err.message().to_owned()))
+                .collect::<Vec<_>>()
+        };
+
+        assert!(warnings("ngop lì'ukìng rey fa n alu n + 1 txew.").is_empty());
+        assert_eq!(
+            warnings(
+                "ngop lì'ukìng rey fa n sì m alu\n\
+                     ngop 'u n alu m ulte\n\
+                     ngop lì'ukìng tìng fa o alu\n\
+                         txo o lu 0, tsakrr ngop 'u m alu o txew ulte\n\
+                         m\n\
+                     txew ulte\n\
+                     n\n\
+                 txew."
+            ),
+            vec![
+                "Variable 'n' shadows a parameter of 'rey'".to_owned(),
+                "Variable 'm' shadows a parameter of 'rey'".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn leading_zero() {
+        let lint_file = |path: &str| {
+            let ast = parse(SourceCode::File(path.to_owned())).unwrap();
+            (
+                lint(&ast, &[Lint::LeadingZero], Base::Octal),
+                lint(&ast, &Lint::defaults().collect::<Vec<_>>(), Base::Octal),
+            )
+        };
+
+        let (warnings, defaults) =
+            lint_file("./examples/features/expressions/valid/leading_zero.ay");
+
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0]
+            .frames()
+            .any(|(_, err)| err.message() == "`010` is read as octal, its value is 8 and not 10"));
+        assert!(defaults.is_empty());
+
+        let (warnings, _) = lint_file("./examples/features/expressions/valid/number.ay");
+
+        assert!(warnings.is_empty());
+
+        // `010` is ten in decimal, as it reads
+        let ast = parse_with_base(
+            SourceCode::File("./examples/features/expressions/valid/leading_zero.ay".to_owned()),
+            Base::Decimal,
+        )
+        .unwrap();
+        assert!(lint(&ast, &[Lint::LeadingZero], Base::Decimal).is_empty());
+    }
+
+    #[test]
+    fn zero_multiplier() {
+        let ast = parse(SourceCode::File(
+            "./examples/features/expressions/valid/zero_multiplier.ay".to_owned(),
+        ))
+        .unwrap();
+
+        let warnings = lint(&ast, &all(), Base::Octal);
+
+        // Negating a zero leaves the literal as is, `ke 0` having no multiplier
+        let messages = warnings
+            .iter()
+            .flat_map(|warning| warning.frames().map(|(_, err)| err.message().to_owned()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "Multiplying 0 by `melo` always yields 0",
+                "Multiplying 0 by `pxelo` always yields 0"
+            ]
+        );
+        assert!(warnings
+            .iter()
+            .all(|warning| warning.severity() == Severity::Warning));
+    }
+}
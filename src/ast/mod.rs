@@ -1,4 +1,8 @@
 pub mod binding;
+pub mod format;
+pub mod inlining;
+pub mod iteration;
 pub mod lib;
+pub mod lint;
 pub mod parsing;
 pub mod typing;
@@ -1,31 +1,47 @@
 use crate::{
     ast::lib::*,
     error::{
-        span::Span,
-        trace::{Stage, Trace},
+        span::{self, Span},
+        trace::{Stage, Trace, TraceError},
         trace_error::Error,
     },
+    highlight::{color, paint},
 };
 
-use std::{path::Path, str::FromStr};
+use std::{num::IntErrorKind, path::Path, str::FromStr, sync::LazyLock};
 
 use pest::{
-    error::{Error as PestError, ErrorVariant},
+    error::{Error as PestError, ErrorVariant, InputLocation},
     iterators::{Pair, Pairs},
-    Parser,
+    pratt_parser::{Assoc, Op, PrattParser},
+    Parser, Position,
 };
 
 #[derive(Parser)]
 #[grammar = "../pest/grammar.pest"]
 pub struct AyParser;
 
+/// Operators of `arithmetic`, from the loosest to the tightest binding.
+static PRATT_PARSER: LazyLock<PrattParser<Rule>> = LazyLock::new(|| {
+    PrattParser::new()
+        .op(Op::infix(Rule::or, Assoc::Left))
+        .op(Op::infix(Rule::and, Assoc::Left))
+        .op(Op::infix(Rule::concat, Assoc::Left))
+        .op(Op::infix(Rule::add, Assoc::Left) | Op::infix(Rule::subtract, Assoc::Left))
+        .op(Op::infix(Rule::multiply, Assoc::Left) | Op::infix(Rule::divide, Assoc::Left))
+        .op(Op::prefix(Rule::prefix_negation))
+});
+
 /// A statement is anything that cannot be expected to return a value.
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Statement {
     FunDec {
         name: String,
         args: Vec<String>,
         body: Vec<AyNode<Statement>>,
+        /// Declarations of the `tsengit` clause.
+        helpers: Vec<AyNode<Statement>>,
     },
     VarDec {
         names: Vec<String>,
@@ -40,12 +56,84 @@ pub enum Statement {
     Loop {
         cond: Option<AyNode<Expr>>,
         body: Vec<AyNode<Statement>>,
+        label: Option<String>,
     },
+    /// Leaves the innermost loop, or the one with the given label.
+    Break(Option<String>),
+    /// Goes on with the next iteration of the innermost loop, or of the one with the given label.
+    Continue(Option<String>),
+    Assert(AyNode<Expr>),
 }
 impl Node for Statement {}
 
+/// Variants without children are left to `PartialEq`.
+impl StructuralEq for Statement {
+    fn structural_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Statement::FunDec {
+                    name,
+                    args,
+                    body,
+                    helpers,
+                },
+                Statement::FunDec {
+                    name: other_name,
+                    args: other_args,
+                    body: other_body,
+                    helpers: other_helpers,
+                },
+            ) => {
+                name == other_name
+                    && args == other_args
+                    && body.structural_eq(other_body)
+                    && helpers.structural_eq(other_helpers)
+            }
+            (
+                Statement::VarDec { names, values },
+                Statement::VarDec {
+                    names: other_names,
+                    values: other_values,
+                },
+            ) => names == other_names && values.structural_eq(other_values),
+            (Statement::Expr(expr), Statement::Expr(other))
+            | (Statement::Assert(expr), Statement::Assert(other)) => expr.structural_eq(other),
+            (
+                Statement::If {
+                    cond,
+                    then,
+                    otherwise,
+                },
+                Statement::If {
+                    cond: other_cond,
+                    then: other_then,
+                    otherwise: other_otherwise,
+                },
+            ) => {
+                cond.structural_eq(other_cond)
+                    && then.structural_eq(other_then)
+                    && otherwise.structural_eq(other_otherwise)
+            }
+            (
+                Statement::Loop { cond, body, label },
+                Statement::Loop {
+                    cond: other_cond,
+                    body: other_body,
+                    label: other_label,
+                },
+            ) => {
+                cond.structural_eq(other_cond)
+                    && body.structural_eq(other_body)
+                    && label == other_label
+            }
+            _ => self == other,
+        }
+    }
+}
+
 /// An expression is anything that is or returns a value.
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
     FunCall {
         name: String,
@@ -59,13 +147,114 @@ pub enum Expr {
         right: Box<AyNode<Expr>>,
         operator: ComparisonOperator,
     },
+    /// `a + b`
+    BinOp {
+        op: BinaryOperator,
+        left: Box<AyNode<Expr>>,
+        right: Box<AyNode<Expr>>,
+    },
     Number(i64),
+    Float(f64),
     String(String),
+    /// `srane` or `kehe`
+    Bool(bool),
     Ident(String),
     Negated(Box<AyNode<Expr>>),
+    /// `kewu`
+    None,
+    /// `srak a`
+    HasValue(Box<AyNode<Expr>>),
+    /// `a fu b`
+    Fallback {
+        value: Box<AyNode<Expr>>,
+        fallback: Box<AyNode<Expr>>,
+    },
+    /// `a[i]`
+    Index {
+        value: Box<AyNode<Expr>>,
+        index: Box<AyNode<Expr>>,
+    },
+    /// `a.b`
+    Field {
+        value: Box<AyNode<Expr>>,
+        name: String,
+    },
+    /// `(+)`, named after the builtin it stands for
+    FunRef(String),
 }
 impl Node for Expr {}
 
+/// Variants without children are left to `PartialEq`.
+impl StructuralEq for Expr {
+    fn structural_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Expr::FunCall { name, args },
+                Expr::FunCall {
+                    name: other_name,
+                    args: other_args,
+                },
+            ) => name == other_name && args.structural_eq(other_args),
+            (Expr::Array { items }, Expr::Array { items: other_items }) => {
+                items.structural_eq(other_items)
+            }
+            (
+                Expr::Comparison {
+                    left,
+                    right,
+                    operator,
+                },
+                Expr::Comparison {
+                    left: other_left,
+                    right: other_right,
+                    operator: other_operator,
+                },
+            ) => {
+                operator == other_operator
+                    && left.structural_eq(other_left)
+                    && right.structural_eq(other_right)
+            }
+            (
+                Expr::BinOp { op, left, right },
+                Expr::BinOp {
+                    op: other_op,
+                    left: other_left,
+                    right: other_right,
+                },
+            ) => {
+                op == other_op && left.structural_eq(other_left) && right.structural_eq(other_right)
+            }
+            (Expr::Negated(expr), Expr::Negated(other))
+            | (Expr::HasValue(expr), Expr::HasValue(other)) => expr.structural_eq(other),
+            (
+                Expr::Fallback { value, fallback },
+                Expr::Fallback {
+                    value: other_value,
+                    fallback: other_fallback,
+                },
+            )
+            | (
+                Expr::Index {
+                    value,
+                    index: fallback,
+                },
+                Expr::Index {
+                    value: other_value,
+                    index: other_fallback,
+                },
+            ) => value.structural_eq(other_value) && fallback.structural_eq(other_fallback),
+            (
+                Expr::Field { value, name },
+                Expr::Field {
+                    value: other_value,
+                    name: other_name,
+                },
+            ) => name == other_name && value.structural_eq(other_value),
+            _ => self == other,
+        }
+    }
+}
+
 /// Pushes new error onto stacktrace or returns pred(pair).
 fn handle<F, T: Node>(parent: &Pair<Rule>, pair: Pair<Rule>, pred: &F) -> Result<AyNode<T>, Trace>
 where
@@ -100,46 +289,177 @@ where
         .collect::<Result<Vec<AyNode<T>>, Trace>>()
 }
 
+/// Next of the `children` of `parent`, `expected` naming it in the error raised if it is missing.
+///
+/// The grammar always produces the children the builder expects, unless they drift out of sync.
+fn next_child<'i>(
+    parent: &Pair<'i, Rule>,
+    children: &mut Pairs<'i, Rule>,
+    expected: &str,
+) -> Result<Pair<'i, Rule>, Trace> {
+    children.next().ok_or_else(|| {
+        builder_error(
+            parent,
+            format!("Missing `{expected}` in `{:?}`", parent.as_rule()),
+        )
+    })
+}
+
+/// Error for a `child` of `parent` the builder does not know what to do with.
+fn unexpected_child(parent: &Pair<Rule>, child: Rule) -> Trace {
+    builder_error(
+        parent,
+        format!("Unexpected `{child:?}` in `{:?}`", parent.as_rule()),
+    )
+}
+
+/// Error of the builder itself rather than of the source, spanning `pair`.
+fn builder_error(pair: &Pair<Rule>, message: String) -> Trace {
+    Trace::new::<Error>(
+        Stage::AstBuilding,
+        PestError::new_from_span(ErrorVariant::CustomError { message }, pair.as_span()).into(),
+    )
+}
+
 macro_rules! fields {
     ($pair:ident |> $children:ident $(: $($field:ident),*)?) => {
         let mut $children = $pair.clone().into_inner();
 
         $(
             $(
-                let $field = $children
-                    .next()
-                    .ok_or_else(|| Trace::new::<Error>(
-                        Stage::Parsing,
-                        PestError::new_from_span(
-                            ErrorVariant::ParsingError {
-                                positives: vec![$pair.as_rule()],
-                                negatives: vec![]
-                            },
-                            $pair.as_span()
-                        ).into()
-                    ))?;
+                let $field = next_child(&$pair, &mut $children, stringify!($field))?;
             )*
         )?
     };
 }
 
-fn build_ast_from_expr(pair: Pair<Rule>) -> Result<AyNode<Expr>, Trace> {
+/// Interprets the `\n`, `\t`, `\\` and `\"` escape sequences of a string literal.
+fn unescape(pair: &Pair<Rule>) -> Result<String, Trace> {
+    let raw = pair.as_str();
+    let mut res = String::with_capacity(raw.len());
+    let mut chars = raw.char_indices();
+
+    while let Some((pos, c)) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+
+        res.push(match chars.next() {
+            Some((_, 'n')) => '\n',
+            Some((_, 't')) => '\t',
+            Some((_, '\\')) => '\\',
+            Some((_, '"')) => '"',
+            escape => {
+                let start = pair.as_span().start() + pos;
+                let message = match escape {
+                    Some((_, c)) => format!("Unknown escape sequence `\\{c}`"),
+                    None => "Unfinished escape sequence".to_owned(),
+                };
+
+                return Err(Trace::new::<Error>(
+                    Stage::Parsing,
+                    PestError::new_from_span(
+                        ErrorVariant::CustomError { message },
+                        pest::Span::new(pair.get_input(), start, start + 1).unwrap(),
+                    )
+                    .into(),
+                ));
+            }
+        });
+    }
+
+    Ok(res)
+}
+
+/// Combines both sides of a radix point, `None` if either has a digit outside of `base`.
+fn parse_float(integer: &str, fraction: &str, base: Base) -> Option<f64> {
+    let radix = base as u32;
+    let integer = i64::from_str_radix(integer, radix).ok()? as f64;
+
+    fraction
+        .chars()
+        .rev()
+        .try_fold(0., |acc, digit| {
+            Some((acc + digit.to_digit(radix)? as f64) / radix as f64)
+        })
+        .map(|fraction| integer + fraction)
+}
+
+/// `expr` negated by the `ke` at `prefix`, `operand` being the span of its pair. Negative zero is
+/// just zero, which keeps the span of its literal for lints to read its multiplier from.
+fn negate(prefix: pest::Span, operand: pest::Span, expr: AyNode<Expr>) -> AyNode<Expr> {
+    match expr.inner {
+        Expr::Number(0) | Expr::Float(0.) => expr,
+        _ => AyNode::prefixed(prefix, operand, Expr::Negated(Box::new(expr))),
+    }
+}
+
+/// Builds the tree of an `arithmetic` pair, each node spanning from its first to its last operand.
+///
+/// Operands keep the span of their pair, that of their node leaving out the parentheses of groups.
+fn build_ast_from_arithmetic(pair: Pair<Rule>, base: Base) -> Result<AyNode<Expr>, Trace> {
+    type Operand<'i> = Result<(pest::Span<'i>, AyNode<Expr>), Trace>;
+
+    PRATT_PARSER
+        .map_primary(|primary| -> Operand {
+            let span = primary.as_span();
+            Ok((
+                span,
+                handle(&pair, primary, &|pair| build_ast_from_expr(pair, base))?,
+            ))
+        })
+        .map_prefix(|op, operand| -> Operand {
+            let (span, operand) = operand?;
+            let op = op.as_span();
+            Ok((span::join(op, span), negate(op, span, operand)))
+        })
+        .map_infix(|left, op, right| -> Operand {
+            let ((left_span, left), (right_span, right)) = (left?, right?);
+            let op = BinaryOperator::from_str(op.as_str()).map_err(|_| {
+                Trace::new_from_pair(&op, format!("Unimplemented operator: `{}`", op.as_str()))
+            })?;
+
+            let node = AyNode::spanning(
+                left_span,
+                right_span,
+                Expr::BinOp {
+                    op,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                },
+            );
+            Ok((span::join(left_span, right_span), node))
+        })
+        .parse(pair.clone().into_inner())
+        .map(|(_, node)| node)
+}
+
+fn build_ast_from_expr(pair: Pair<Rule>, base: Base) -> Result<AyNode<Expr>, Trace> {
     match pair.as_rule() {
-        Rule::expr => build_ast_from_expr(pair.into_inner().next().unwrap()),
-        Rule::negation => Ok(AyNode {
-            span: pair.as_span().into(),
-            inner: Expr::Negated(Box::new(handle(
-                &pair.clone(),
-                pair.into_inner().next().unwrap(),
-                &build_ast_from_expr,
-            )?)),
-        }),
+        Rule::expr | Rule::match_pattern | Rule::group => {
+            fields!(pair |> children: expr);
+            build_ast_from_expr(expr, base)
+        }
+        Rule::negation => {
+            fields!(pair |> children: expr);
+
+            // `ke` along with the whitespace following it
+            let prefix = pair.as_span().start_pos().span(&expr.as_span().start_pos());
+            let operand = expr.as_span();
+            let expr = handle(&pair, expr, &|pair| build_ast_from_expr(pair, base))?;
+
+            Ok(negate(prefix, operand, expr))
+        }
+        Rule::arithmetic => build_ast_from_arithmetic(pair, base),
         Rule::fun_call => {
             let span = pair.as_span();
             fields!(pair |> children: name);
 
             let name = name.as_span().as_str().to_owned();
-            let args = handle_iter(&pair, &mut children, &build_ast_from_expr)?;
+            let args = handle_iter(&pair, &mut children, &|pair| {
+                build_ast_from_expr(pair, base)
+            })?;
 
             Ok(AyNode {
                 span: span.into(),
@@ -148,9 +468,14 @@ fn build_ast_from_expr(pair: Pair<Rule>) -> Result<AyNode<Expr>, Trace> {
         }
         Rule::array => {
             let span = pair.as_span();
-            fields!(pair |> children: items);
 
-            let items = handle_iter(&pair, &mut items.into_inner(), &build_ast_from_expr)?;
+            // `vezeykoyu` alone is empty
+            let items = match pair.clone().into_inner().next() {
+                Some(items) => handle_iter(&pair, &mut items.into_inner(), &|pair| {
+                    build_ast_from_expr(pair, base)
+                })?,
+                None => vec![],
+            };
 
             Ok(AyNode {
                 span: span.into(),
@@ -158,11 +483,10 @@ fn build_ast_from_expr(pair: Pair<Rule>) -> Result<AyNode<Expr>, Trace> {
             })
         }
         Rule::comparison => {
-            let span = pair.as_span();
             fields!(pair |> children: left, right, comparison);
 
-            let left = handle(&pair, left, &build_ast_from_expr)?;
-            let right = handle(&pair, right, &build_ast_from_expr)?;
+            let left = handle(&pair, left, &|pair| build_ast_from_expr(pair, base))?;
+            let right = handle(&pair, right, &|pair| build_ast_from_expr(pair, base))?;
             let operator = ComparisonOperator::from_str(comparison.as_str()).map_err(|_| {
                 Trace::new_from_pair(
                     &pair,
@@ -171,7 +495,7 @@ fn build_ast_from_expr(pair: Pair<Rule>) -> Result<AyNode<Expr>, Trace> {
             })?;
 
             Ok(AyNode {
-                span: span.into(),
+                span: pair.as_span().into(),
                 inner: Expr::Comparison {
                     left: Box::new(left),
                     right: Box::new(right),
@@ -179,42 +503,132 @@ fn build_ast_from_expr(pair: Pair<Rule>) -> Result<AyNode<Expr>, Trace> {
                 },
             })
         }
+        Rule::is_comparison => {
+            fields!(pair |> children: left);
+
+            let left = handle(&pair, left, &|pair| build_ast_from_expr(pair, base))?;
+            let (negated, right) = match (children.next(), children.next()) {
+                (Some(_), Some(right)) => (true, right),
+                (Some(right), None) => (false, right),
+                _ => {
+                    return Err(builder_error(
+                        &pair,
+                        "Missing `right` in `is_comparison`".to_owned(),
+                    ))
+                }
+            };
+            let right = handle(&pair, right, &|pair| build_ast_from_expr(pair, base))?;
+
+            let comparison = AyNode {
+                span: pair.as_span().into(),
+                inner: Expr::Comparison {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    operator: ComparisonOperator::Equals,
+                },
+            };
+
+            Ok(if negated {
+                AyNode {
+                    span: pair.as_span().into(),
+                    inner: Expr::Negated(Box::new(comparison)),
+                }
+            } else {
+                comparison
+            })
+        }
         Rule::number => {
             let span = pair.as_span();
             let mut elems = span.as_str().split_whitespace();
             let number = elems.next().unwrap();
+            // Applied once the magnitude has been read and multiplied
+            let (sign, digits) = match number.strip_prefix('-') {
+                Some(digits) => (-1, digits),
+                None => (1, number),
+            };
 
-            // Bit unnecessary but better be safe than sorry
-            let mult = if let Some(mult) = elems.next() {
-                Multiplier::from_str(mult).map_err(|_| {
-                    Trace::new::<Error>(
-                        Stage::Parsing,
-                        PestError::new_from_span(
-                            ErrorVariant::CustomError {
-                                message: format!("Unimplemented multiplier: `{mult}`"),
-                            },
-                            span,
-                        )
-                        .into(),
+            let mult_word = elems.next();
+
+            if let (Some(mult), Base::Decimal) = (mult_word, base) {
+                return Err(Trace::new_from_pair(
+                    &pair,
+                    format!("Multiplier `{mult}` is only available for octal numbers"),
+                ));
+            }
+
+            let mult = match mult_word {
+                Some(mult) => multiplier(mult).ok_or_else(|| {
+                    Trace::new_from_pair(
+                        &pair,
+                        format!(
+                            "Unknown multiplier `{mult}`, expected one of {}",
+                            MULTIPLIERS
+                                .iter()
+                                .map(|(name, _)| format!("`{name}`"))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
                     )
-                })? as i64
-            } else {
-                1
+                })?,
+                None => 1,
             };
 
-            let result = i64::from_str_radix(number, 8).map_err(|_| {
-                Trace::new::<Error>(
-                    Stage::Parsing,
-                    PestError::new_from_span(
-                        ErrorVariant::ParsingError {
-                            positives: vec![Rule::number],
-                            negatives: vec![],
-                        },
-                        span,
-                    )
-                    .into(),
+            if let Some((integer, fraction)) = digits.split_once('.') {
+                let result = parse_float(integer, fraction, base).ok_or_else(|| {
+                    Trace::new_from_pair(&pair, format!("Invalid {base} number: `{number}`"))
+                })? * mult as f64;
+
+                return Ok(AyNode {
+                    span: span.into(),
+                    // Negative zero is just zero
+                    inner: Expr::Float(match result {
+                        0. => 0.,
+                        _ => sign as f64 * result,
+                    }),
+                });
+            }
+
+            let too_large = || {
+                let (direction, limit) = match sign {
+                    -1 => ("down", i64::MIN),
+                    _ => ("up", i64::MAX),
+                };
+                // Stated in the base the literal was read in
+                let limit = match base {
+                    Base::Octal => format!(
+                        "{}{:o} ({limit} in decimal)",
+                        if limit < 0 { "-" } else { "" },
+                        limit.unsigned_abs()
+                    ),
+                    Base::Decimal => limit.to_string(),
+                };
+
+                Trace::new_from_pair(
+                    &pair,
+                    format!(
+                        "`{}` is too large, numbers go {direction} to {limit}",
+                        span.as_str()
+                    ),
                 )
-            })? * mult;
+            };
+            // Words stand for the same number in any base
+            let magnitude = match number_word(digits) {
+                Some(value) => i128::from(value),
+                None => {
+                    i128::from_str_radix(digits, base as u32).map_err(|err| match err.kind() {
+                        IntErrorKind::PosOverflow => too_large(),
+                        _ => Trace::new_from_pair(
+                            &pair,
+                            format!("Invalid {base} number: `{number}`"),
+                        ),
+                    })?
+                }
+            };
+            // Checked along with the sign, the smallest number being one further from zero
+            let result = magnitude
+                .checked_mul(i128::from(mult * sign))
+                .and_then(|result| i64::try_from(result).ok())
+                .ok_or_else(too_large)?;
 
             Ok(AyNode {
                 span: span.into(),
@@ -223,12 +637,89 @@ fn build_ast_from_expr(pair: Pair<Rule>) -> Result<AyNode<Expr>, Trace> {
         }
         Rule::string => Ok(AyNode {
             span: pair.as_span().into(),
-            inner: Expr::String(pair.as_span().as_str().to_owned()),
+            inner: Expr::String(unescape(&pair)?),
         }),
         Rule::ident | Rule::fun_ident => Ok(AyNode {
             span: pair.as_span().into(),
             inner: Expr::Ident(pair.as_span().as_str().to_owned()),
         }),
+        Rule::postfix => {
+            fields!(pair |> children: operand);
+
+            let operand = handle(&pair, operand, &|pair| build_ast_from_expr(pair, base))?;
+
+            // Each link spans from the start of the chain up to its own end
+            children.try_fold(operand, |value, link| {
+                let span: Span = pair
+                    .as_span()
+                    .start_pos()
+                    .span(&link.as_span().end_pos())
+                    .into();
+                let value = Box::new(value);
+
+                let inner = match link.as_rule() {
+                    Rule::index => {
+                        fields!(link |> children: index);
+                        Expr::Index {
+                            value,
+                            index: Box::new(handle(&link, index, &|pair| {
+                                build_ast_from_expr(pair, base)
+                            })?),
+                        }
+                    }
+                    Rule::field => {
+                        fields!(link |> children: name);
+                        Expr::Field {
+                            value,
+                            name: name.as_str().to_owned(),
+                        }
+                    }
+                    rule => return Err(unexpected_child(&pair, rule)),
+                };
+
+                Ok(AyNode { span, inner })
+            })
+        }
+        Rule::none => Ok(AyNode {
+            span: pair.as_span().into(),
+            inner: Expr::None,
+        }),
+        Rule::bool => Ok(AyNode {
+            span: pair.as_span().into(),
+            inner: Expr::Bool(pair.as_str() == "srane"),
+        }),
+        Rule::operator_value => {
+            fields!(pair |> children: operator);
+
+            Ok(AyNode {
+                span: pair.as_span().into(),
+                inner: Expr::FunRef(operator.as_str().to_owned()),
+            })
+        }
+        Rule::has_value => {
+            fields!(pair |> children: expr);
+
+            let expr = handle(&pair, expr, &|pair| build_ast_from_expr(pair, base))?;
+
+            Ok(AyNode {
+                span: pair.as_span().into(),
+                inner: Expr::HasValue(Box::new(expr)),
+            })
+        }
+        Rule::fallback => {
+            fields!(pair |> children: value, fallback);
+
+            let value = handle(&pair, value, &|pair| build_ast_from_expr(pair, base))?;
+            let fallback = handle(&pair, fallback, &|pair| build_ast_from_expr(pair, base))?;
+
+            Ok(AyNode {
+                span: pair.as_span().into(),
+                inner: Expr::Fallback {
+                    value: Box::new(value),
+                    fallback: Box::new(fallback),
+                },
+            })
+        }
         rule => Err(Trace::new::<Error>(
             Stage::AstBuilding,
             PestError::new_from_span(
@@ -242,11 +733,20 @@ fn build_ast_from_expr(pair: Pair<Rule>) -> Result<AyNode<Expr>, Trace> {
     }
 }
 
-fn build_ast_from_statement(pair: Pair<Rule>) -> Result<AyNode<Statement>, Trace> {
+/// Name given by a `loop_label` pair.
+fn loop_label(pair: Pair<Rule>) -> String {
+    pair.into_inner()
+        .map(|ident| ident.as_str().to_owned())
+        .collect()
+}
+
+fn build_ast_from_statement(pair: Pair<Rule>, base: Base) -> Result<AyNode<Statement>, Trace> {
     match pair.as_rule() {
         Rule::expr => Ok(AyNode {
             span: pair.as_span().into(),
-            inner: Statement::Expr(handle(&pair.clone(), pair, &build_ast_from_expr)?),
+            inner: Statement::Expr(handle(&pair.clone(), pair, &|pair| {
+                build_ast_from_expr(pair, base)
+            })?),
         }),
         Rule::fun_dec => {
             let span = pair.as_span();
@@ -255,26 +755,38 @@ fn build_ast_from_statement(pair: Pair<Rule>) -> Result<AyNode<Statement>, Trace
 
             let name = name.as_span().as_str().to_owned();
 
-            let args = children.next();
-            let body = children.next();
-
-            let (args, body) = match (args, body) {
-                (Some(args), Some(body)) => (
-                    args.into_inner()
-                        .map(|arg| arg.as_span().as_str().to_owned())
-                        .collect::<Vec<String>>(),
-                    handle_iter(&pair, &mut body.into_inner(), &build_ast_from_statement)?,
-                ),
-                (Some(body), None) => (
-                    vec![],
-                    handle_iter(&pair, &mut body.into_inner(), &build_ast_from_statement)?,
-                ),
-                _ => (vec![], vec![]),
-            };
+            let (mut args, mut body, mut helpers) = (vec![], vec![], vec![]);
+
+            for child in children {
+                match child.as_rule() {
+                    Rule::fun_dec_args => {
+                        args = child
+                            .into_inner()
+                            .map(|arg| arg.as_span().as_str().to_owned())
+                            .collect::<Vec<String>>()
+                    }
+                    Rule::block_body => {
+                        body = handle_iter(&pair, &mut child.into_inner(), &|pair| {
+                            build_ast_from_statement(pair, base)
+                        })?
+                    }
+                    Rule::where_clause => {
+                        helpers = handle_iter(&pair, &mut child.into_inner(), &|pair| {
+                            build_ast_from_statement(pair, base)
+                        })?
+                    }
+                    rule => return Err(unexpected_child(&pair, rule)),
+                }
+            }
 
             Ok(AyNode {
                 span: span.into(),
-                inner: Statement::FunDec { name, args, body },
+                inner: Statement::FunDec {
+                    name,
+                    args,
+                    body,
+                    helpers,
+                },
             })
         }
         Rule::var_dec => {
@@ -292,16 +804,34 @@ fn build_ast_from_statement(pair: Pair<Rule>) -> Result<AyNode<Statement>, Trace
             });
 
             if idents.len() != values.len() {
-                return Err(Trace::new::<Error>(
+                let count = |count: usize, noun: &str| match count {
+                    1 => format!("1 {noun}"),
+                    count => format!("{count} {noun}s"),
+                };
+
+                // Only the names or values left without a counterpart
+                let extra = match idents.len() > values.len() {
+                    true => &idents[values.len()..],
+                    false => &values[idents.len()..],
+                };
+                let extra = Span::from(
+                    extra[0]
+                        .as_span()
+                        .start_pos()
+                        .span(&extra[extra.len() - 1].as_span().end_pos()),
+                );
+
+                return Err(Trace::new(
                     Stage::Parsing,
-                    PestError::new_from_span(
-                        ErrorVariant::ParsingError {
-                            positives: vec![Rule::var_dec],
-                            negatives: vec![],
-                        },
-                        span,
-                    )
-                    .into(),
+                    Error::from_span(
+                        extra,
+                        format!(
+                            "{} but {}",
+                            count(idents.len(), "name"),
+                            count(values.len(), "value")
+                        )
+                        .as_ref(),
+                    ),
                 ));
             }
 
@@ -315,74 +845,196 @@ fn build_ast_from_statement(pair: Pair<Rule>) -> Result<AyNode<Statement>, Trace
 
                     values: values
                         .iter()
-                        .map(|value| build_ast_from_expr(value.clone()))
+                        .zip(idents.iter())
+                        .enumerate()
+                        .map(|(index, (value, ident))| {
+                            build_ast_from_expr(value.clone(), base).map_err(|mut trace| {
+                                trace.push(
+                                    Stage::Parsing,
+                                    Error::from_span(
+                                        span.into(),
+                                        format!(
+                                            "Invalid value #{} of {}, assigned to `{}`",
+                                            index + 1,
+                                            values.len(),
+                                            ident.as_str()
+                                        )
+                                        .as_ref(),
+                                    ),
+                                );
+                                trace
+                            })
+                        })
                         .collect::<Result<Vec<AyNode<Expr>>, Trace>>()?,
                 },
             })
         }
+        // `txokefyaw txo` chains are desugared into conditions nested in the `otherwise` branch,
+        // spanning their own clause
         Rule::if_block => {
-            let span = pair.as_span();
             fields!(pair |> children: cond, then);
 
-            let cond = build_ast_from_expr(cond)?;
+            let cond = build_ast_from_expr(cond, base)?;
 
-            let then = handle_iter(&pair, &mut then.into_inner(), &build_ast_from_statement)?;
+            let then = handle_iter(&pair, &mut then.into_inner(), &|pair| {
+                build_ast_from_statement(pair, base)
+            })?;
 
             // The else case is not mandatory
-            if let Some(otherwise) = children.next() {
-                let otherwise = handle_iter(
-                    &pair,
-                    &mut otherwise.into_inner(),
-                    &build_ast_from_statement,
-                )?;
+            let (mut else_ifs, mut otherwise) = (vec![], vec![]);
+            for child in children {
+                match child.as_rule() {
+                    Rule::else_if => else_ifs.push(child),
+                    Rule::block_body => {
+                        otherwise = handle_iter(&pair, &mut child.into_inner(), &|pair| {
+                            build_ast_from_statement(pair, base)
+                        })?
+                    }
+                    rule => return Err(unexpected_child(&pair, rule)),
+                }
+            }
 
-                Ok(AyNode {
-                    span: span.into(),
-                    inner: Statement::If {
-                        cond,
-                        then,
-                        otherwise,
-                    },
-                })
-            } else {
-                Ok(AyNode {
-                    span: pair.as_span().into(),
-                    inner: Statement::If {
-                        cond,
-                        then,
-                        otherwise: vec![],
-                    },
-                })
+            let otherwise =
+                else_ifs
+                    .into_iter()
+                    .rev()
+                    .try_fold(otherwise, |otherwise, else_if| {
+                        fields!(else_if |> else_if_children: cond, then);
+
+                        let cond = handle(&else_if, cond, &|pair| build_ast_from_expr(pair, base))?;
+                        let then = handle_iter(&else_if, &mut then.into_inner(), &|pair| {
+                            build_ast_from_statement(pair, base)
+                        })?;
+
+                        Ok::<_, Trace>(vec![AyNode {
+                            span: else_if.as_span().into(),
+                            inner: Statement::If {
+                                cond,
+                                then,
+                                otherwise,
+                            },
+                        }])
+                    })?;
+
+            Ok(AyNode {
+                span: pair.as_span().into(),
+                inner: Statement::If {
+                    cond,
+                    then,
+                    otherwise,
+                },
+            })
+        }
+        // Desugared into nested conditions, the first one spanning the whole construct
+        Rule::match_block => {
+            fields!(pair |> children: head);
+            fields!(head |> head_children: scrutinee);
+
+            let scrutinee = handle(&head, scrutinee, &|pair| build_ast_from_expr(pair, base))?;
+
+            let (mut arms, mut otherwise) = (vec![], vec![]);
+            for child in children {
+                match child.as_rule() {
+                    Rule::match_arm => arms.push(child),
+                    Rule::block_body => {
+                        otherwise = handle_iter(&pair, &mut child.into_inner(), &|pair| {
+                            build_ast_from_statement(pair, base)
+                        })?
+                    }
+                    rule => return Err(unexpected_child(&pair, rule)),
+                }
             }
+
+            let mut chain = arms
+                .into_iter()
+                .rev()
+                .try_fold(otherwise, |otherwise, arm| {
+                    fields!(arm |> arm_children: pattern, body);
+
+                    let pattern = handle(&arm, pattern, &|pair| build_ast_from_expr(pair, base))?;
+                    let then = handle_iter(&arm, &mut body.into_inner(), &|pair| {
+                        build_ast_from_statement(pair, base)
+                    })?;
+
+                    Ok::<_, Trace>(vec![AyNode {
+                        span: arm.as_span().into(),
+                        inner: Statement::If {
+                            cond: AyNode {
+                                span: pattern.span.clone(),
+                                inner: Expr::Comparison {
+                                    left: Box::new(scrutinee.clone()),
+                                    right: Box::new(pattern),
+                                    operator: ComparisonOperator::Equals,
+                                },
+                            },
+                            then,
+                            otherwise,
+                        },
+                    }])
+                })?;
+
+            let outermost = chain.pop().ok_or_else(|| {
+                builder_error(&pair, "Missing `match_arm` in `match_block`".to_owned())
+            })?;
+            Ok(AyNode {
+                span: pair.as_span().into(),
+                inner: outermost.inner,
+            })
         }
         Rule::loop_block => {
             let span = pair.as_span();
-            fields!(pair |> children);
 
-            let mut child = children.next().unwrap();
+            let (mut cond, mut body, mut label) = (None, vec![], None);
 
-            let (cond, body) = if children.peek().is_none() {
-                (
-                    None,
-                    handle_iter(&pair, &mut child.into_inner(), &build_ast_from_statement)?,
-                )
-            } else {
-                (
-                    Some(handle(&pair, child, &build_ast_from_expr)?),
-                    handle_iter(
-                        &pair,
-                        &mut children.next().unwrap().into_inner(),
-                        &build_ast_from_statement,
-                    )?,
-                )
-            };
+            for child in pair.clone().into_inner() {
+                match child.as_rule() {
+                    Rule::loop_label => label = Some(loop_label(child)),
+                    Rule::expr => {
+                        cond = Some(handle(&pair, child, &|pair| {
+                            build_ast_from_expr(pair, base)
+                        })?)
+                    }
+                    Rule::block_body => {
+                        body = handle_iter(&pair, &mut child.into_inner(), &|pair| {
+                            build_ast_from_statement(pair, base)
+                        })?
+                    }
+                    rule => return Err(unexpected_child(&pair, rule)),
+                }
+            }
+
+            Ok(AyNode {
+                span: span.into(),
+                inner: Statement::Loop { cond, body, label },
+            })
+        }
+        Rule::loop_break | Rule::loop_continue => {
+            let span = pair.as_span();
+            let label = pair.clone().into_inner().next().map(loop_label);
+
+            Ok(AyNode {
+                span: span.into(),
+                inner: match pair.as_rule() {
+                    Rule::loop_break => Statement::Break(label),
+                    _ => Statement::Continue(label),
+                },
+            })
+        }
+        Rule::assert => {
+            let span = pair.as_span();
+            fields!(pair |> children: cond);
 
             Ok(AyNode {
                 span: span.into(),
-                inner: Statement::Loop { cond, body },
+                inner: Statement::Assert(handle(&pair, cond, &|pair| {
+                    build_ast_from_expr(pair, base)
+                })?),
             })
         }
-        Rule::statement => Ok(build_ast_from_statement(pair.into_inner().next().unwrap())?),
+        Rule::statement => {
+            fields!(pair |> children: statement);
+            build_ast_from_statement(statement, base)
+        }
         rule => Err(Trace::new::<Error>(
             Stage::AstBuilding,
             PestError::new_from_span(
@@ -396,28 +1048,273 @@ fn build_ast_from_statement(pair: Pair<Rule>) -> Result<AyNode<Statement>, Trace
     }
 }
 
-pub fn parse(source: SourceCode) -> Result<Vec<AyNode<Statement>>, Trace> {
-    let mut ast: Vec<AyNode<Statement>> = vec![];
+/// Position of the outermost `{-` left open, line comments and strings being skipped.
+fn unclosed_comment(content: &str) -> Option<usize> {
+    let mut opened = vec![];
+    let mut rest = content.char_indices();
 
-    let (mut path, content) = match source {
-        SourceCode::File(path) => {
-            let unparsed_file = std::fs::read_to_string(path.as_str())
-                .unwrap_or_else(|_| panic!("Cannot read file at `{path}`"));
-            (Some(path), unparsed_file)
+    while let Some((pos, c)) = rest.next() {
+        match (c, content[pos..].chars().nth(1)) {
+            ('{', Some('-')) => {
+                rest.next();
+                opened.push(pos);
+            }
+            ('-', Some('}')) => {
+                rest.next();
+                opened.pop();
+            }
+            // Line comments end with the line or the next `''`
+            ('\'', Some('\'')) if opened.is_empty() => {
+                rest.next();
+                while let Some((pos, c)) = rest.next() {
+                    if c == '\n' {
+                        break;
+                    }
+                    if content[pos..].starts_with("''") {
+                        rest.next();
+                        break;
+                    }
+                }
+            }
+            // Strings run up to the first `sìk` following whitespace
+            ('s', _)
+                if opened.is_empty()
+                    && content[pos..].starts_with("san")
+                    && content[pos + 3..].starts_with(char::is_whitespace)
+                    && !content[..pos].ends_with(|c: char| c.is_alphabetic() || c == '\'') =>
+            {
+                let end = content[pos + 3..]
+                    .char_indices()
+                    .find(|&(at, c)| {
+                        c.is_whitespace() && content[pos + 3 + at..].trim_start().starts_with("sìk")
+                    })
+                    .map_or(content.len(), |(at, _)| pos + 3 + at);
+                while rest.clone().next().is_some_and(|(at, _)| at < end) {
+                    rest.next();
+                }
+            }
+            _ => {}
         }
-        SourceCode::Content(content) => (None, content),
+    }
+
+    opened.first().copied()
+}
+
+/// Converts a pest error, pointing out inputs that end before the grammar expected them to.
+fn syntax_error(err: PestError<Rule>, content: &str) -> Trace {
+    let pos = match err.location {
+        InputLocation::Pos(pos) | InputLocation::Span((pos, _)) => pos,
     };
 
-    let pairs = AyParser::parse(Rule::program, content.as_ref()).map_err(Error::from)?;
+    // Everything past it is part of the comment, wherever parsing stopped
+    if let Some(start) = unclosed_comment(content).filter(|start| *start <= pos) {
+        return Trace::new::<Error>(
+            Stage::Parsing,
+            PestError::new_from_span(
+                ErrorVariant::CustomError {
+                    message: "Unterminated block comment, it has to be closed with `-}`".to_owned(),
+                },
+                pest::Span::new(content, start, start + 2).unwrap(),
+            )
+            .into(),
+        );
+    }
 
-    for pair in pairs.clone() {
-        recursive_print(Some(&pair), 0);
+    match err.location {
+        InputLocation::Pos(pos) if content[pos..].trim().is_empty() => Trace::new::<Error>(
+            Stage::Parsing,
+            PestError::new_from_pos(
+                ErrorVariant::CustomError {
+                    message: format!("Unexpected end of input, {}", err.variant.message()),
+                },
+                Position::new(content, pos).unwrap(),
+            )
+            .into(),
+        ),
+        _ => Trace::new::<Error>(Stage::Parsing, err.into()),
     }
+}
 
-    for pair in pairs {
-        match pair.as_rule() {
-            Rule::mod_use => {
-                if let Some(ref mut path) = path {
+/// Returns the resolved path of the source, if any, along with its content.
+///
+/// A leading UTF-8 byte order mark is stripped, editors do not display it so positions have to be
+/// computed without it.
+fn read_source(source: SourceCode) -> Result<(Option<String>, String), Trace> {
+    let (path, content) = match source {
+        SourceCode::File(path) => {
+            let (path, bytes) = std::fs::canonicalize(&path)
+                .and_then(|resolved| Ok((resolved.display().to_string(), std::fs::read(resolved)?)))
+                .map_err(|err| {
+                    Trace::new(
+                        Stage::Reading,
+                        Error::new(&format!("Cannot read file at `{path}`: {err}")),
+                    )
+                })?;
+
+            (Some(path), decode(bytes)?)
+        }
+        SourceCode::Content(content) => (None, content),
+        SourceCode::Bytes(bytes) => (None, decode(bytes)?),
+    };
+
+    match content.strip_prefix('\u{feff}') {
+        Some(content) => Ok((path, content.to_owned())),
+        None => Ok((path, content)),
+    }
+}
+
+/// Validates raw source bytes as UTF-8.
+fn decode(bytes: Vec<u8>) -> Result<String, Trace> {
+    String::from_utf8(bytes).map_err(|err| {
+        Trace::new(
+            Stage::Parsing,
+            Error::new(&format!(
+                "Invalid UTF-8 at byte {}",
+                err.utf8_error().valid_up_to()
+            )),
+        )
+    })
+}
+
+/// Parses the source, splicing in the statements of the modules it uses with `sar` before its own.
+///
+/// Modules are looked up relative to the directory of the file using them, sources that are not
+/// read from a file cannot use any. A module coming back to one of the files using it is an error.
+pub fn parse(source: SourceCode) -> Result<Vec<AyNode<Statement>>, Trace> {
+    parse_with_base(source, Base::default())
+}
+
+/// Same as [`parse`], reading numeric literals in the given base.
+pub fn parse_with_base(source: SourceCode, base: Base) -> Result<Vec<AyNode<Statement>>, Trace> {
+    let (path, content) = read_source(source)?;
+
+    parse_read(path.as_deref(), &content, base)
+}
+
+/// Parses what [`read_source`] read, errors being located in `path`.
+fn parse_read(
+    path: Option<&str>,
+    content: &str,
+    base: Base,
+) -> Result<Vec<AyNode<Statement>>, Trace> {
+    let mut using = path.into_iter().map(str::to_owned).collect();
+
+    parse_program(content, path, base, &mut using).map_err(|trace| match path {
+        Some(path) => trace.in_file(path),
+        None => trace,
+    })
+}
+
+type Parsed = Result<Vec<AyNode<Statement>>, Trace>;
+
+/// Keeps the result of the last parse around, for sources that are parsed again without having
+/// changed, e.g. on every keystroke of an editor.
+///
+/// Only the whole source is compared, any change to it being parsed from scratch.
+#[derive(Debug, Default)]
+pub struct ParseCache {
+    base: Base,
+    /// Hash of the path and content of the last source, along with its parse.
+    last: Option<(u64, Parsed)>,
+    hits: usize,
+}
+
+impl ParseCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_base(mut self, base: Base) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Number of parses served from the cache so far.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Same as [`parse_with_base`], unless the source is the same as that of the last call.
+    ///
+    /// Files are read every time, only their parse is skipped, so changes to the modules they use
+    /// go unnoticed. The path is part of the key as it ends up in the spans of the AST.
+    pub fn parse_cached(&mut self, source: &SourceCode) -> Parsed {
+        use std::hash::{DefaultHasher, Hash, Hasher};
+
+        let (path, content) = read_source(source.clone())?;
+
+        let mut hasher = DefaultHasher::new();
+        (&path, &content).hash(&mut hasher);
+        let hash = hasher.finish();
+
+        match &self.last {
+            Some((last, res)) if *last == hash => {
+                self.hits += 1;
+                res.clone()
+            }
+            _ => {
+                let res = parse_read(path.as_deref(), &content, self.base);
+                self.last = Some((hash, res.clone()));
+                res
+            }
+        }
+    }
+}
+
+/// Parses a lone expression rather than a whole program, e.g. `sung fa 1 sì 2`.
+///
+/// Statements other than expressions are rejected as a whole, instead of at their first keyword.
+pub fn parse_expr(source: &str, base: Base) -> Result<AyNode<Expr>, Trace> {
+    let mut pairs = AyParser::parse(Rule::standalone_expr, source)
+        .map_err(|err| statement_error(source).unwrap_or_else(|| syntax_error(err, source)))?;
+
+    build_ast_from_expr(pairs.next().expect("Missing expression"), base)
+}
+
+/// Error for a `source` made of a single statement that is not an expression, if it is one.
+fn statement_error(source: &str) -> Option<Trace> {
+    let statement = AyParser::parse(Rule::statement, source).ok()?.next()?;
+    let rest = source[statement.as_span().end()..].trim();
+
+    let kind = match statement.clone().into_inner().next()?.as_rule() {
+        Rule::fun_dec => "a function declaration",
+        Rule::var_dec => "a variable declaration",
+        Rule::if_block => "a condition",
+        Rule::match_block => "a match",
+        Rule::loop_block => "a loop",
+        Rule::loop_break | Rule::loop_continue => "a loop control",
+        Rule::assert => "an assertion",
+        _ => return None,
+    };
+
+    (rest.is_empty() || rest == ".").then(|| {
+        Trace::new(
+            Stage::Parsing,
+            Error::from_span(
+                statement.as_span().into(),
+                format!("Expected an expression, found {kind}").as_ref(),
+            ),
+        )
+    })
+}
+
+/// `path` is the file `content` comes from, used to locate the modules it uses, and `using` the
+/// files whose modules are being read, outermost first.
+fn parse_program(
+    content: &str,
+    path: Option<&str>,
+    base: Base,
+    using: &mut Vec<String>,
+) -> Result<Vec<AyNode<Statement>>, Trace> {
+    let mut ast: Vec<AyNode<Statement>> = vec![];
+
+    let pairs =
+        AyParser::parse(Rule::program, content).map_err(|err| syntax_error(err, content))?;
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::mod_use => {
+                if let Some(path) = path {
                     // Bit of a nightmare but it seems to work
                     let parent = Path::new(path).parent().unwrap().to_str().unwrap();
 
@@ -440,7 +1337,7 @@ pub fn parse(source: SourceCode) -> Result<Vec<AyNode<Statement>>, Trace> {
                     );
 
                     eprintln!("Using {path}");
-                    ast.extend(parse(SourceCode::File(path.clone()))?);
+                    ast.extend(parse_module(&pair, path, base, using)?);
                 } else {
                     return Err(Trace::new::<Error>(
                         Stage::AstBuilding,
@@ -454,38 +1351,1502 @@ pub fn parse(source: SourceCode) -> Result<Vec<AyNode<Statement>>, Trace> {
                     ));
                 }
             }
-            Rule::statement => ast.push(build_ast_from_statement(pair)?),
+            Rule::statement => {
+                let mut node = build_ast_from_statement(pair, base)?;
+                if let Some(path) = path {
+                    set_file(&mut node, path);
+                }
+                ast.push(node)
+            }
             Rule::EOI => {}
-            unknown_rule => Err(PestError::new_from_span(
+            unknown_rule => Err(Error::from(PestError::new_from_span(
                 ErrorVariant::CustomError {
                     message: format!("Unknown rule: {:?}", unknown_rule),
                 },
                 pair.as_span(),
-            ))
-            .map_err(Error::from)?,
+            )))?,
         }
     }
 
     Ok(ast)
 }
 
-pub fn recursive_print(cur: Option<&Pair<Rule>>, depth: usize) {
-    if let Some(node) = cur {
-        let rule = node.as_rule();
+/// Parses the module at `path`, used by `pair` in the last of the files in `using`.
+fn parse_module(
+    pair: &Pair<Rule>,
+    path: String,
+    base: Base,
+    using: &mut Vec<String>,
+) -> Result<Vec<AyNode<Statement>>, Trace> {
+    let (resolved, content) = read_source(SourceCode::File(path.clone()))?;
+    let path = resolved.unwrap_or(path);
+
+    if let Some(start) = using.iter().position(|used| *used == path) {
+        let cycle = using[start..]
+            .iter()
+            .chain([&path])
+            .map(|used| format!("`{used}`"))
+            .collect::<Vec<_>>()
+            .join(" -> ");
+
+        return Err(Trace::new(
+            Stage::Parsing,
+            Error::from_span(
+                pair.as_span().into(),
+                format!("Cyclic module use: {cycle}").as_ref(),
+            ),
+        ));
+    }
+
+    using.push(path.clone());
+    let res = parse_program(&content, Some(&path), base, using);
+    using.pop();
+
+    res.map_err(|trace| trace.in_file(&path))
+}
+
+/// Marks every span of `node` as found in the file at `path`, for later stages to tell it.
+fn set_file(node: &mut AyNode<Statement>, path: &str) {
+    fn set_expr_file(node: &mut AyNode<Expr>, path: &str) {
+        node.span.set_file(path);
+
+        match &mut node.inner {
+            Expr::FunCall { args: items, .. } | Expr::Array { items } => {
+                items.iter_mut().for_each(|item| set_expr_file(item, path))
+            }
+            Expr::Comparison { left, right, .. }
+            | Expr::BinOp { left, right, .. }
+            | Expr::Fallback {
+                value: left,
+                fallback: right,
+            }
+            | Expr::Index {
+                value: left,
+                index: right,
+            } => {
+                set_expr_file(left, path);
+                set_expr_file(right, path);
+            }
+            Expr::Negated(expr) | Expr::HasValue(expr) | Expr::Field { value: expr, .. } => {
+                set_expr_file(expr, path)
+            }
+            Expr::Number(_)
+            | Expr::Float(_)
+            | Expr::String(_)
+            | Expr::Bool(_)
+            | Expr::Ident(_)
+            | Expr::None
+            | Expr::FunRef(_) => {}
+        }
+    }
+
+    let set_files =
+        |nodes: &mut Vec<AyNode<Statement>>| nodes.iter_mut().for_each(|node| set_file(node, path));
+
+    node.span.set_file(path);
+
+    match &mut node.inner {
+        Statement::FunDec { body, helpers, .. } => {
+            set_files(body);
+            set_files(helpers);
+        }
+        Statement::VarDec { values, .. } => values
+            .iter_mut()
+            .for_each(|value| set_expr_file(value, path)),
+        Statement::Expr(expr) | Statement::Assert(expr) => set_expr_file(expr, path),
+        Statement::If {
+            cond,
+            then,
+            otherwise,
+        } => {
+            set_expr_file(cond, path);
+            set_files(then);
+            set_files(otherwise);
+        }
+        Statement::Loop { cond, body, .. } => {
+            if let Some(cond) = cond {
+                set_expr_file(cond, path);
+            }
+            set_files(body);
+        }
+        Statement::Break(_) | Statement::Continue(_) => {}
+    }
+}
+
+/// Statement along with the source text surrounding it.
+///
+/// Comments and blank lines preceding a statement are its leading trivia, while the rest of its
+/// last line (terminating `.` or `ulte`, trailing comment, newline) is its trailing trivia.
+/// Statements of the blocks of a statement get their own trivia, the rest of its text being kept
+/// around them.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Trivia<T: Node> {
+    pub leading: String,
+    pub node: AyNode<T>,
+    /// Statements of the node's blocks, in source order.
+    pub nested: Vec<Trivia<Statement>>,
+    /// Text of the node around its nested statements, one more piece than there are of them.
+    /// Nodes without nested statements are rendered from their span instead.
+    pub around: Vec<String>,
+    pub trailing: String,
+}
+
+/// AST able to reconstruct its source byte-for-byte through its `Display` implementation.
+#[derive(PartialEq, Debug, Clone)]
+pub struct LosslessAst {
+    pub statements: Vec<Trivia<Statement>>,
+    /// Whatever follows the trailing trivia of the last statement.
+    pub end: String,
+}
+
+/// Parses the source while keeping track of everything in-between statements.
+///
+/// Module inclusions are not expanded and are kept verbatim as trivia, since the point is to
+/// rewrite the file itself.
+pub fn parse_lossless(source: SourceCode) -> Result<LosslessAst, Trace> {
+    parse_lossless_with_base(source, Base::default())
+}
+
+/// Same as [`parse_lossless`], reading numeric literals in the given base.
+pub fn parse_lossless_with_base(source: SourceCode, base: Base) -> Result<LosslessAst, Trace> {
+    let (path, content) = read_source(source)?;
+
+    lossless(&content, base).map_err(|trace| match path {
+        Some(path) => trace.in_file(&path),
+        None => trace,
+    })
+}
+
+/// Splits `content` into statements along with the trivia around them.
+fn lossless(content: &str, base: Base) -> Result<LosslessAst, Trace> {
+    let statements = AyParser::parse(Rule::program, content)
+        .map_err(|err| syntax_error(err, content))?
+        .filter(|pair| pair.as_rule() == Rule::statement)
+        .collect::<Vec<Pair<Rule>>>();
+
+    let mut res = LosslessAst {
+        statements: vec![],
+        end: String::new(),
+    };
+    let mut last = 0;
+
+    for (index, pair) in statements.iter().enumerate() {
+        let (start, end) = (pair.as_span().start(), pair.as_span().end());
+        let next = statements
+            .get(index + 1)
+            .map_or(content.len(), |next| next.as_span().start());
+        let line_end = line_end(content, end, next);
+
+        res.statements.push(with_trivia(
+            pair,
+            &content[last..start],
+            &content[end..line_end],
+            base,
+        )?);
+
+        last = line_end;
+    }
+
+    res.end = content[last..].to_owned();
+
+    Ok(res)
+}
+
+/// Position following the line `from` is on, up to `limit`.
+fn line_end(text: &str, from: usize, limit: usize) -> usize {
+    text[from..]
+        .find('\n')
+        .map_or(text.len(), |offset| from + offset + 1)
+        .min(limit)
+}
+
+/// `pair` along with its trivia, the statements of its blocks getting theirs.
+///
+/// The first statement of a block leaves the rest of the line opening it, e.g. `alu`, out of its
+/// leading trivia, while a block's last statement has none trailing.
+fn with_trivia(
+    pair: &Pair<Rule>,
+    leading: &str,
+    trailing: &str,
+    base: Base,
+) -> Result<Trivia<Statement>, Trace> {
+    /// Blocks of the statement itself, leaving out those of nested statements.
+    fn blocks<'a>(pair: Pair<'a, Rule>, res: &mut Vec<Pair<'a, Rule>>) {
+        pair.into_inner().for_each(|child| match child.as_rule() {
+            Rule::block_body => res.push(child),
+            Rule::statement => {}
+            _ => blocks(child, res),
+        })
+    }
+
+    let span = pair.as_span();
+    let (text, offset) = (span.as_str(), span.start());
+
+    let mut bodies = vec![];
+    blocks(pair.clone(), &mut bodies);
+
+    let (mut nested, mut around) = (vec![], vec![]);
+    let mut last = 0;
+
+    for body in bodies {
+        let statements = body.clone().into_inner().collect::<Vec<_>>();
+
+        for (index, statement) in statements.iter().enumerate() {
+            let start = statement.as_span().start() - offset;
+            let end = statement.as_span().end() - offset;
+            let leading = match index {
+                0 => text[last..start]
+                    .find('\n')
+                    .map_or(start, |newline| last + newline + 1),
+                _ => last,
+            };
+            let next = statements
+                .get(index + 1)
+                .map_or(body.as_span().end(), |next| next.as_span().start());
+            let line_end = line_end(text, end, next - offset);
+
+            around.push(text[last..leading].to_owned());
+            nested.push(with_trivia(
+                statement,
+                &text[leading..start],
+                &text[end..line_end],
+                base,
+            )?);
+
+            last = line_end;
+        }
+    }
+
+    if !nested.is_empty() {
+        around.push(text[last..].to_owned());
+    }
+
+    // The statement's own span also covers the `ngop` of declarations
+    Ok(Trivia {
+        leading: leading.to_owned(),
+        node: AyNode {
+            span: span.into(),
+            inner: build_ast_from_statement(pair.clone(), base)?.inner,
+        },
+        nested,
+        around,
+        trailing: trailing.to_owned(),
+    })
+}
+
+impl std::fmt::Display for Trivia<Statement> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.leading)?;
 
-        println!(
-            "{}\x1b[1;33m{:?}\x1b[0m:'{}'",
-            format_args!("\x1b[31m{}\x1b[0m", "|   ".repeat(depth)),
-            rule,
+        match self.nested.is_empty() {
+            true => write!(f, "{}", self.node.span.as_str())?,
+            false => {
+                for (text, nested) in self.around.iter().zip(&self.nested) {
+                    write!(f, "{text}{nested}")?;
+                }
+                write!(f, "{}", self.around.last().map_or("", String::as_str))?;
+            }
+        }
+
+        write!(f, "{}", self.trailing)
+    }
+}
+
+impl std::fmt::Display for LosslessAst {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.statements
+            .iter()
+            .try_for_each(|statement| write!(f, "{statement}"))?;
+
+        write!(f, "{}", self.end)
+    }
+}
+
+/// Prints the parse tree of the source, without building the AST.
+pub fn print_tree(source: SourceCode, max_depth: usize) -> Result<(), Trace> {
+    let (_, content) = read_source(source)?;
+
+    let pairs = AyParser::parse(Rule::program, content.as_ref())
+        .map_err(|err| syntax_error(err, &content))?;
+
+    print!("{}", render_tree(pairs, max_depth));
+
+    Ok(())
+}
+
+/// Renders the parse tree, replacing whatever lies deeper than `max_depth` with a marker.
+///
+/// Walks the tree with an explicit stack so that deeply nested sources cannot overflow it.
+fn render_tree(pairs: Pairs<Rule>, max_depth: usize) -> String {
+    let mut res = String::new();
+    let mut stack = pairs.rev().map(|pair| (pair, 0)).collect::<Vec<_>>();
+
+    while let Some((node, depth)) = stack.pop() {
+        let indent = paint("31", &"|   ".repeat(depth), color());
+
+        if depth > max_depth {
+            res.push_str(&format!("{indent}... (truncated at depth {max_depth})\n"));
+            continue;
+        }
+
+        res.push_str(&format!(
+            "{indent}{}:'{}'\n",
+            paint("1;33", &format!("{:?}", node.as_rule()), color()),
             node.as_span()
                 .as_str()
                 .lines()
                 .map(|line| line.trim())
                 .collect::<String>()
+        ));
+
+        let children = node.into_inner().rev().collect::<Vec<_>>();
+
+        if depth == max_depth && !children.is_empty() {
+            // A single marker for all of the children
+            stack.extend(children.into_iter().take(1).map(|pair| (pair, depth + 1)));
+        } else {
+            stack.extend(children.into_iter().map(|pair| (pair, depth + 1)));
+        }
+    }
+
+    res
+}
+
+/// Renders the AST as an indented tree, one node per line.
+pub fn pretty_print(ast: &[AyNode<Statement>]) -> String {
+    fn line(depth: usize, label: String, span: &Span) -> String {
+        let (line, col) = span.start();
+        format!("{}{label} @{line}:{col}\n", "|   ".repeat(depth))
+    }
+
+    fn statement(AyNode { span, inner }: &AyNode<Statement>, depth: usize) -> String {
+        let block = |label: &str, body: &[AyNode<Statement>]| {
+            format!(
+                "{}{label}\n{}",
+                "|   ".repeat(depth + 1),
+                body.iter()
+                    .map(|node| statement(node, depth + 2))
+                    .collect::<String>()
+            )
+        };
+
+        match inner {
+            Statement::FunDec {
+                name,
+                args,
+                body,
+                helpers,
+            } => {
+                line(depth, format!("FunDec {name}({})", args.join(", ")), span)
+                    + &body
+                        .iter()
+                        .map(|node| statement(node, depth + 1))
+                        .collect::<String>()
+                    + &if helpers.is_empty() {
+                        String::new()
+                    } else {
+                        block("Where", helpers)
+                    }
+            }
+            Statement::VarDec { names, values } => {
+                line(depth, format!("VarDec {}", names.join(", ")), span)
+                    + &values
+                        .iter()
+                        .map(|node| expr(node, depth + 1))
+                        .collect::<String>()
+            }
+            Statement::Expr(node) => line(depth, "Expr".to_owned(), span) + &expr(node, depth + 1),
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                line(depth, "If".to_owned(), span)
+                    + &expr(cond, depth + 1)
+                    + &block("Then", then)
+                    + &block("Otherwise", otherwise)
+            }
+            Statement::Loop { cond, body, label } => {
+                let label = label
+                    .as_ref()
+                    .map(|label| format!(" {label}"))
+                    .unwrap_or_default();
+
+                line(depth, format!("Loop{label}"), span)
+                    + &cond
+                        .as_ref()
+                        .map(|cond| expr(cond, depth + 1))
+                        .unwrap_or_default()
+                    + &block("Body", body)
+            }
+            Statement::Break(label) => line(
+                depth,
+                format!("Break {}", label.as_deref().unwrap_or_default()),
+                span,
+            ),
+            Statement::Continue(label) => line(
+                depth,
+                format!("Continue {}", label.as_deref().unwrap_or_default()),
+                span,
+            ),
+            Statement::Assert(cond) => {
+                line(depth, "Assert".to_owned(), span) + &expr(cond, depth + 1)
+            }
+        }
+    }
+
+    fn expr(AyNode { span, inner }: &AyNode<Expr>, depth: usize) -> String {
+        let children = |nodes: &[&AyNode<Expr>]| {
+            nodes
+                .iter()
+                .map(|node| expr(node, depth + 1))
+                .collect::<String>()
+        };
+
+        match inner {
+            Expr::FunCall { name, args } => {
+                line(depth, format!("FunCall {name}"), span)
+                    + &children(&args.iter().collect::<Vec<_>>())
+            }
+            Expr::Array { items } => {
+                line(depth, "Array".to_owned(), span) + &children(&items.iter().collect::<Vec<_>>())
+            }
+            Expr::Comparison {
+                left,
+                right,
+                operator,
+            } => {
+                line(depth, format!("Comparison {operator:?}"), span)
+                    + &children(&[left.as_ref(), right.as_ref()])
+            }
+            Expr::BinOp { op, left, right } => {
+                line(depth, format!("BinOp {op:?}"), span)
+                    + &children(&[left.as_ref(), right.as_ref()])
+            }
+            Expr::Number(number) => line(depth, format!("Number {number}"), span),
+            Expr::Float(number) => line(depth, format!("Float {number}"), span),
+            Expr::String(string) => line(depth, format!("String {string:?}"), span),
+            Expr::Bool(value) => line(depth, format!("Bool {value}"), span),
+            Expr::Ident(name) => line(depth, format!("Ident {name}"), span),
+            Expr::Negated(node) => {
+                line(depth, "Negated".to_owned(), span) + &children(&[node.as_ref()])
+            }
+            Expr::None => line(depth, "None".to_owned(), span),
+            Expr::HasValue(node) => {
+                line(depth, "HasValue".to_owned(), span) + &children(&[node.as_ref()])
+            }
+            Expr::Fallback { value, fallback } => {
+                line(depth, "Fallback".to_owned(), span)
+                    + &children(&[value.as_ref(), fallback.as_ref()])
+            }
+            Expr::Index { value, index } => {
+                line(depth, "Index".to_owned(), span) + &children(&[value.as_ref(), index.as_ref()])
+            }
+            Expr::Field { value, name } => {
+                line(depth, format!("Field {name}"), span) + &children(&[value.as_ref()])
+            }
+            Expr::FunRef(name) => line(depth, format!("FunRef {name}"), span),
+        }
+    }
+
+    ast.iter().map(|node| statement(node, 0)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use pest::error::LineColLocation;
+
+    use super::*;
+
+    #[test]
+    fn premature_end_of_input() {
+        let trace = parse(SourceCode::File(
+            "./examples/features/statements/invalid/truncated_if.ay".to_owned(),
+        ))
+        .unwrap_err();
+
+        assert!(trace
+            .frames()
+            .any(|(stage, err)| matches!(stage, Stage::Parsing)
+                && err
+                    .message()
+                    .starts_with("Unexpected end of input, expected")));
+    }
+
+    #[test]
+    fn fun_dec() {
+        let ast = parse(SourceCode::File(
+            "./examples/features/functions/valid/two_args.ay".to_owned(),
+        ))
+        .unwrap();
+
+        let Statement::FunDec {
+            name, args, body, ..
+        } = &ast[0].inner
+        else {
+            panic!("Expected a function declaration, found {:?}", ast[0].inner);
+        };
+
+        // The name is kept dotted for binding to derive its tensed spellings
+        assert_eq!(name, "t.varon");
+        assert_eq!(args, &["a", "b"]);
+        assert!(matches!(
+            body.as_slice(),
+            [AyNode {
+                inner: Statement::Expr(AyNode {
+                    inner: Expr::FunCall { .. },
+                    ..
+                }),
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn loops() {
+        let conds = [
+            "./examples/features/loops/valid/conditional.ay",
+            "./examples/features/loops/valid/unconditional.ay",
+        ]
+        .map(|path| {
+            let ast = parse(SourceCode::File(path.to_owned())).unwrap();
+
+            match &ast.last().unwrap().inner {
+                Statement::Loop { cond, body, .. } => {
+                    assert_eq!(body.len(), 2);
+                    cond.is_some()
+                }
+                inner => panic!("Expected a loop, found {inner:?}"),
+            }
+        });
+
+        assert_eq!(conds, [true, false]);
+
+        // Errors in the body are reported within the loop
+        let trace = parse(SourceCode::Content(
+            "leyn kem si\n  ngop 'u a alu 9\nftang.".to_owned(),
+        ))
+        .unwrap_err();
+
+        assert!(trace
+            .frames()
+            .any(|(_, err)| matches!(err.line_col(), LineColLocation::Span((1, 1), (3, 6)))));
+    }
+
+    #[test]
+    fn unterminated_comment() {
+        let trace = parse(SourceCode::File(
+            "./examples/features/comments/invalid/unterminated.ay".to_owned(),
+        ))
+        .unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+
+        assert_eq!(err.line_col(), LineColLocation::Span((2, 1), (2, 3)));
+        assert!(err.message().starts_with("Unterminated block comment"));
+
+        // Openers in line comments do not count
+        assert_eq!(unclosed_comment("'' {-\n1. {- '' -} -}"), None);
+        assert_eq!(unclosed_comment("1. {- {- -}"), Some(3));
+        // Nor do those in strings
+        assert_eq!(unclosed_comment("sung fa san {- sìk. {- -}"), None);
+        assert_eq!(unclosed_comment("'usan {- sìk"), Some(6));
+    }
+
+    #[test]
+    fn grouping() {
+        let expr = |source: &str| match parse(SourceCode::Content(format!("{source}.")))
+            .unwrap()
+            .remove(0)
+            .inner
+        {
+            Statement::Expr(expr) => expr.inner,
+            inner => panic!("Expected an expression, found {inner:?}"),
+        };
+
+        assert_eq!(expr("((((3))))"), Expr::Number(3));
+
+        let Expr::FunCall { args, .. } = expr("sung fa (sung fa 1 sì 1) sì 2") else {
+            panic!("Expected a call");
+        };
+        assert_eq!(args.len(), 2);
+        assert!(matches!(args[0].inner, Expr::FunCall { .. }));
+        assert_eq!(args[0].span.as_str(), "sung fa 1 sì 1");
+
+        let Expr::Comparison { left, .. } = expr("(ke 1) lu 2") else {
+            panic!("Expected a comparison");
+        };
+        assert!(matches!(left.inner, Expr::Negated(_)));
+    }
+
+    #[test]
+    fn standalone_expressions() {
+        let expr = |source: &str| parse_expr(source, Base::Octal).map(|node| node.inner);
+        let message = |source: &str| {
+            let trace = parse_expr(source, Base::Octal).unwrap_err();
+            let (stage, err) = trace.frames().next().unwrap();
+
+            assert_eq!(stage, Stage::Parsing);
+            err.message().to_owned()
+        };
+
+        assert_eq!(expr("17").unwrap(), Expr::Number(15));
+        assert_eq!(expr("17.").unwrap(), Expr::Number(15));
+        assert!(matches!(
+            expr("sung fa 1 sì a").unwrap(),
+            Expr::FunCall { name, args } if name == "sung" && args.len() == 2
+        ));
+
+        assert_eq!(
+            message("txo a, tsakrr 1 txew"),
+            "Expected an expression, found a condition"
+        );
+        assert_eq!(
+            message("ngop 'u a alu 1."),
+            "Expected an expression, found a variable declaration"
+        );
+        assert_eq!(
+            message("leyn kem si hum ftang"),
+            "Expected an expression, found a loop"
+        );
+        assert_eq!(
+            message("nìngay a"),
+            "Expected an expression, found an assertion"
+        );
+
+        // Trailing input that is not a statement either
+        assert!(message("1 2").starts_with("expected EOI"));
+        assert!(message("ngop 'u a alu 1. 2").starts_with("expected EOI"));
+    }
+
+    #[test]
+    fn arithmetic_spans() {
+        let expr = parse_expr("ke (a + b) * 2", Base::Octal).unwrap();
+        let Expr::BinOp { left, .. } = &expr.inner else {
+            panic!("Expected an operation");
+        };
+
+        assert_eq!(expr.span.as_str(), "ke (a + b) * 2");
+        // From the operator through the group's closing parenthesis
+        assert_eq!(left.span.as_str(), "ke (a + b)");
+        let Expr::Negated(group) = &left.inner else {
+            panic!("Expected a negation");
+        };
+        assert_eq!(group.span.as_str(), "a + b");
+
+        // Keeping whatever lies between the operands
+        let source = "1   +\n    2";
+        assert_eq!(
+            parse_expr(source, Base::Octal).unwrap().span.as_str(),
+            source
+        );
+    }
+
+    #[test]
+    fn arithmetic_precedence() {
+        let expr = |source: &str| parse_expr(source, Base::Octal).unwrap().inner;
+        let operands = |expr: &Expr| match expr {
+            Expr::BinOp { op, left, right } => (*op, left.inner.clone(), right.inner.clone()),
+            expr => panic!("Expected an operation, found {expr:?}"),
+        };
+
+        let (op, left, right) = operands(&expr("2 + 3 * 4"));
+        assert_eq!((op, left), (BinaryOperator::Add, Expr::Number(2)));
+        assert_eq!(
+            operands(&right),
+            (BinaryOperator::Multiply, Expr::Number(3), Expr::Number(4))
+        );
+
+        // Left associative
+        let (op, left, right) = operands(&expr("7 - 2 - 1"));
+        assert_eq!((op, right), (BinaryOperator::Subtract, Expr::Number(1)));
+        assert_eq!(
+            operands(&left),
+            (BinaryOperator::Subtract, Expr::Number(7), Expr::Number(2))
+        );
+
+        // Negation binds tighter than multiplication
+        let (op, left, _) = operands(&expr("ke 2 * 3"));
+        assert_eq!(op, BinaryOperator::Multiply);
+        assert!(matches!(left, Expr::Negated(_)));
+
+        let (op, _, right) = operands(&expr("(1 + 2)*3"));
+        assert_eq!(op, BinaryOperator::Multiply);
+        assert_eq!(right, Expr::Number(3));
+
+        assert!(matches!(
+            expr("1 + 1 lu 2"),
+            Expr::Comparison { left, .. } if matches!(left.inner, Expr::BinOp { .. })
+        ));
+
+        // Concatenation binds looser than arithmetic
+        let (op, left, right) = operands(&expr("a ++ b + c ++ d"));
+        assert_eq!(
+            (op, right),
+            (BinaryOperator::Concat, Expr::Ident("d".to_owned()))
+        );
+        let (_, _, right) = operands(&left);
+        assert!(matches!(
+            right,
+            Expr::BinOp {
+                op: BinaryOperator::Add,
+                ..
+            }
+        ));
+
+        // Then come `&&` and `||`, the loosest
+        let (op, left, right) = operands(&expr("a || b && c ++ d"));
+        assert_eq!(
+            (op, left),
+            (BinaryOperator::Or, Expr::Ident("a".to_owned()))
+        );
+        let (op, _, right) = operands(&right);
+        assert_eq!(op, BinaryOperator::And);
+        assert!(matches!(
+            right,
+            Expr::BinOp {
+                op: BinaryOperator::Concat,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn operator_values() {
+        let expr = |source: &str| parse_expr(source, Base::Octal).unwrap().inner;
+
+        assert_eq!(expr("(+)"), Expr::FunRef("+".to_owned()));
+        assert_eq!(expr("(teng)"), Expr::FunRef("teng".to_owned()));
+        assert!(matches!(
+            expr("rey fa (*) sì 1"),
+            Expr::FunCall { args, .. } if args[0].inner == Expr::FunRef("*".to_owned())
+        ));
+
+        // Only operators, other names being grouped identifiers
+        assert_eq!(expr("(rey)"), Expr::Ident("rey".to_owned()));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let ast = parse(SourceCode::File(
+            "./examples/features/expressions/valid/postfix.ay".to_owned(),
+        ))
+        .unwrap();
+
+        let json = serde_json::to_string(&ast).unwrap();
+        let back: Vec<AyNode<Statement>> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(back, ast);
+    }
+
+    #[test]
+    fn canonical_zero() {
+        for source in ["0", "000", "ke 0", "ke ke 0", "0 melo", "ke 0 pxelo"] {
+            let ast = parse(SourceCode::Content(format!("{source}."))).unwrap();
+
+            let Statement::Expr(expr) = &ast[0].inner else {
+                panic!("Expected an expression statement");
+            };
+
+            assert_eq!(expr.inner, Expr::Number(0), "`{source}`");
+        }
+
+        let ast = parse(SourceCode::Content("ke 0.0.".to_owned())).unwrap();
+        let Statement::Expr(expr) = &ast[0].inner else {
+            panic!("Expected an expression statement");
+        };
+        assert!(matches!(expr.inner, Expr::Float(number) if number.is_sign_positive()));
+
+        let ast = parse(SourceCode::Content("007.".to_owned())).unwrap();
+        let Statement::Expr(expr) = &ast[0].inner else {
+            panic!("Expected an expression statement");
+        };
+        assert_eq!(expr.inner, Expr::Number(7));
+    }
+
+    #[test]
+    fn var_dec_bad_value() {
+        let trace = parse(SourceCode::File(
+            "./examples/features/statements/invalid/var_dec_value.ay".to_owned(),
+        ))
+        .unwrap_err();
+
+        assert!(trace
+            .frames()
+            .any(|(_, err)| err.message() == "Invalid value #2 of 3, assigned to `b`"));
+    }
+
+    #[test]
+    fn var_dec_count_mismatch() {
+        let error = |source: &str| {
+            let trace = parse(SourceCode::Content(source.to_owned())).unwrap_err();
+            let (stage, err) = trace.frames().next().unwrap();
+            assert!(matches!(stage, Stage::Parsing));
+            (err.message().to_owned(), err.line().to_owned())
+        };
+
+        assert_eq!(
+            error("ngop 'u a sì b sì c alu 1 sì 2."),
+            ("3 names but 2 values".to_owned(), "c".to_owned())
+        );
+        assert_eq!(
+            error("ngop 'u a alu 1 sì 2 sì 3."),
+            ("1 name but 3 values".to_owned(), "2 sì 3".to_owned())
         );
+    }
+
+    #[test]
+    fn number_base() {
+        let number = |source: &str, base| -> Result<Expr, Trace> {
+            let ast = parse_with_base(SourceCode::Content(format!("{source}.")), base)?;
+
+            match &ast[0].inner {
+                Statement::Expr(expr) => Ok(expr.inner.clone()),
+                _ => panic!("Expected an expression statement"),
+            }
+        };
 
-        for pair in node.clone().into_inner() {
-            recursive_print(Some(&pair), depth + 1);
+        assert_eq!(number("10", Base::Octal).unwrap(), Expr::Number(8));
+        assert_eq!(number("10", Base::Decimal).unwrap(), Expr::Number(10));
+        assert_eq!(number("19", Base::Decimal).unwrap(), Expr::Number(19));
+        assert_eq!(number("0.5", Base::Decimal).unwrap(), Expr::Float(0.5));
+        assert_eq!(number("1 melo", Base::Octal).unwrap(), Expr::Number(2));
+        assert!(number("1 melo", Base::Decimal).is_err());
+    }
+
+    #[test]
+    fn multipliers() {
+        let number = |source: &str| parse_expr(source, Base::Octal).map(|node| node.inner);
+
+        for (word, factor) in MULTIPLIERS {
+            assert_eq!(
+                number(&format!("3 {word}")).unwrap(),
+                Expr::Number(3 * factor)
+            );
         }
+        assert_eq!(number("7 kinglo").unwrap(), Expr::Number(49));
+
+        // Fractional digits weigh negative powers of eight, before being multiplied
+        assert_eq!(number("0.4").unwrap(), Expr::Float(0.5));
+        assert_eq!(number("0.4 melo").unwrap(), Expr::Float(1.));
+        assert_eq!(number("1.2 pxelo").unwrap(), Expr::Float(3.75));
+
+        let trace = number("1 vonlo").unwrap_err();
+        let (stage, err) = trace.frames().next().unwrap();
+        assert!(matches!(stage, Stage::Parsing));
+        assert!(err.message().starts_with("Unknown multiplier `vonlo`"));
+        assert_eq!(err.line(), "1 vonlo");
+    }
+
+    #[test]
+    fn builder_out_of_sync() {
+        // Pairs the grammar would never produce, standing for one that drifted from the builder
+        let stub = |input, rule, inner: &dyn Fn(_) -> _| {
+            pest::state(input, |state| state.rule(rule, inner))
+                .unwrap()
+                .next()
+                .unwrap()
+        };
+        let message = |trace: Trace| {
+            let (stage, err) = trace.frames().next().unwrap();
+            assert!(matches!(stage, Stage::AstBuilding));
+            err.message().to_owned()
+        };
+
+        let negation = stub("ke", Rule::negation, &|state| state.match_string("ke"));
+        assert_eq!(
+            message(build_ast_from_expr(negation, Base::Octal).unwrap_err()),
+            "Missing `expr` in `negation`"
+        );
+
+        let is_comparison = stub("1 lu", Rule::is_comparison, &|state| {
+            state
+                .rule(Rule::number, |state| state.match_string("1"))
+                .and_then(|state| state.match_string(" lu"))
+        });
+        assert_eq!(
+            message(build_ast_from_expr(is_comparison, Base::Octal).unwrap_err()),
+            "Missing `right` in `is_comparison`"
+        );
+
+        let loop_block = stub("leyn 1", Rule::loop_block, &|state| {
+            state
+                .match_string("leyn ")
+                .and_then(|state| state.rule(Rule::number, |state| state.match_string("1")))
+        });
+        assert_eq!(
+            message(build_ast_from_statement(loop_block, Base::Octal).unwrap_err()),
+            "Unexpected `number` in `loop_block`"
+        );
+    }
+
+    #[test]
+    fn number_words() {
+        let number = |source: &str| parse_expr(source, Base::Octal).map(|node| node.inner);
+
+        for (word, digits) in [
+            ("kew", "0"),
+            ("mune", "2"),
+            ("kinä", "7"),
+            ("vol", "10"),
+            ("volaw", "11"),
+            ("mevol", "20"),
+            ("mevosìng", "24"),
+            ("kivohin", "77"),
+        ] {
+            assert_eq!(number(word).unwrap(), number(digits).unwrap(), "{word}");
+        }
+        assert_eq!(number("mevolaw").unwrap(), Expr::Number(17));
+        assert_eq!(number("pxey melo").unwrap(), Expr::Number(6));
+        assert_eq!(number("-vol").unwrap(), Expr::Number(-8));
+        assert_eq!(
+            parse_expr("mune", Base::Decimal).unwrap().inner,
+            Expr::Number(2)
+        );
+
+        // Only whole words, the others being identifiers
+        assert_eq!(number("munea").unwrap(), Expr::Ident("munea".to_owned()));
+        assert_eq!(number("volume").unwrap(), Expr::Ident("volume".to_owned()));
+
+        for mixed in ["1mune", "vol1", "1 mune", "mune 1", "me10"] {
+            let trace = number(mixed).unwrap_err();
+            assert!(
+                matches!(trace.frames().next(), Some((Stage::Parsing, _))),
+                "{mixed}"
+            );
+        }
+    }
+
+    #[test]
+    fn number_overflow() {
+        let message = |source: &str| {
+            let trace = parse_expr(source, Base::Octal).unwrap_err();
+            let (stage, err) = trace.frames().next().unwrap();
+            assert!(matches!(stage, Stage::Parsing));
+            assert_eq!(err.line(), source);
+            err.message().to_owned()
+        };
+
+        // i64::MAX / 3, then i64::MAX
+        assert_eq!(
+            parse_expr("252525252525252525252 pxelo", Base::Octal)
+                .unwrap()
+                .inner,
+            Expr::Number(i64::MAX / 3 * 3)
+        );
+        assert_eq!(
+            parse_expr("777777777777777777777", Base::Octal)
+                .unwrap()
+                .inner,
+            Expr::Number(i64::MAX)
+        );
+
+        assert!(message("777777777777777777777 pxelo").contains("is too large"));
+        assert!(message("-300000000000000000000 pxelo").contains("is too large"));
+        assert_eq!(
+            parse_expr("-1000000000000000000000", Base::Octal)
+                .unwrap()
+                .inner,
+            Expr::Number(i64::MIN)
+        );
+        assert_eq!(
+            message("-1000000000000000000001"),
+            "`-1000000000000000000001` is too large, numbers go down to \
+             -1000000000000000000000 (-9223372036854775808 in decimal)"
+        );
+        assert_eq!(
+            message("1000000000000000000000"),
+            "`1000000000000000000000` is too large, numbers go up to 777777777777777777777 \
+             (9223372036854775807 in decimal)"
+        );
+        let trace = parse_expr("9223372036854775808", Base::Decimal).unwrap_err();
+        assert!(trace.frames().any(|(_, err)| err
+            .message()
+            .ends_with("numbers go up to 9223372036854775807")));
+        assert_eq!(message("18"), "Invalid octal number: `18`");
+    }
+
+    #[test]
+    fn bool_literals() {
+        let expr = |source: &str| parse_expr(source, Base::Octal).unwrap().inner;
+
+        assert_eq!(expr("srane"), Expr::Bool(true));
+        assert_eq!(expr("kehe"), Expr::Bool(false));
+        assert_eq!(expr("kehea"), Expr::Ident("kehea".to_owned()));
+        assert!(matches!(expr("ke srane"), Expr::Negated(node) if node.inner == Expr::Bool(true)));
+        assert!(matches!(
+            expr("a lu kehe"),
+            Expr::Comparison { right, .. } if right.inner == Expr::Bool(false)
+        ));
+    }
+
+    #[test]
+    fn negative_literals() {
+        let expr = |source: &str| parse_expr(source, Base::Octal).unwrap().inner;
+
+        assert_eq!(expr("-17"), Expr::Number(-15));
+        assert_eq!(expr("-0"), Expr::Number(0));
+        assert_eq!(expr("-0.4"), Expr::Float(-0.5));
+        assert!(matches!(expr("-0.0"), Expr::Float(number) if number.is_sign_positive()));
+        assert_eq!(
+            parse_expr("-19", Base::Decimal).unwrap().inner,
+            Expr::Number(-19)
+        );
+
+        // The multiplier applies to the magnitude
+        assert_eq!(expr("-3 melo"), Expr::Number(-6));
+        assert_eq!(expr("-7 kinglo"), Expr::Number(-49));
+        assert_eq!(expr("-0.4 melo"), Expr::Float(-1.));
+
+        // An explicit negation is kept around the negative literal
+        let Expr::Negated(node) = expr("ke -17") else {
+            panic!("Expected a negation");
+        };
+        assert_eq!(node.inner, Expr::Number(-15));
+
+        let Expr::BinOp { op, right, .. } = expr("7 - -2") else {
+            panic!("Expected an operation");
+        };
+        assert_eq!(
+            (op, right.inner),
+            (BinaryOperator::Subtract, Expr::Number(-2))
+        );
+    }
+
+    #[test]
+    fn is_comparison() {
+        let ast = parse(SourceCode::File(
+            "./examples/features/expressions/valid/is.ay".to_owned(),
+        ))
+        .unwrap();
+
+        let exprs = ast
+            .iter()
+            .filter_map(|node| match &node.inner {
+                Statement::Expr(expr) => Some(&expr.inner),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert!(matches!(
+            exprs[0],
+            Expr::Comparison {
+                operator: ComparisonOperator::Equals,
+                ..
+            }
+        ));
+        assert!(matches!(
+            exprs[1],
+            Expr::Negated(node) if matches!(node.inner, Expr::Comparison { .. })
+        ));
+        assert!(matches!(
+            exprs[2],
+            Expr::FunCall { args, .. } if matches!(args[0].inner, Expr::Comparison { .. })
+        ));
+    }
+
+    #[test]
+    fn truncated_tree() {
+        let source = format!("{}1.", "ke ".repeat(200));
+        let pairs = AyParser::parse(Rule::program, &source).unwrap();
+
+        let tree = render_tree(pairs, 16);
+
+        assert_eq!(tree.matches("... (truncated at depth 16)").count(), 1);
+        assert!(tree.lines().count() < 20);
+    }
+
+    #[test]
+    fn rendered_tree() {
+        let pairs = AyParser::parse(Rule::program, "ngop 'u a alu 1.").unwrap();
+
+        // Colors are global, hence stripped rather than turned off
+        let mut tree = render_tree(pairs, 64);
+        while let Some(start) = tree.find('\x1b') {
+            let end = start + tree[start..].find('m').unwrap();
+            tree.replace_range(start..=end, "");
+        }
+
+        assert_eq!(
+            tree,
+            "statement:'ngop 'u a alu 1'\n\
+             |   var_dec:''u a alu 1'\n\
+             |   |   ident:'a'\n\
+             |   |   expr:'1'\n\
+             |   |   |   number:'1'\n\
+             EOI:''\n"
+        );
+    }
+
+    #[test]
+    fn match_desugaring() {
+        let source = "pe n, txo 0 tsakrr 1, txo 1 tsakrr 2, txokefyaw 3";
+        let ast = parse(SourceCode::Content(format!("ngop 'u n alu 1.\n{source}."))).unwrap();
+
+        let is_arm = |node: &AyNode<Statement>, pattern: i64, result: i64| match &node.inner {
+            Statement::If {
+                cond:
+                    AyNode {
+                        inner: Expr::Comparison { left, right, .. },
+                        ..
+                    },
+                then,
+                ..
+            } => {
+                left.inner == Expr::Ident("n".to_owned())
+                    && right.inner == Expr::Number(pattern)
+                    && matches!(&then[..], [AyNode { inner: Statement::Expr(expr), .. }]
+                        if expr.inner == Expr::Number(result))
+            }
+            _ => false,
+        };
+
+        let outer = &ast[1];
+        assert_eq!(outer.span.as_str(), source);
+        assert!(is_arm(outer, 0, 1));
+
+        let Statement::If { otherwise, .. } = &outer.inner else {
+            unreachable!()
+        };
+        assert!(is_arm(&otherwise[0], 1, 2));
+
+        let Statement::If { otherwise, .. } = &otherwise[0].inner else {
+            unreachable!()
+        };
+        assert!(
+            matches!(&otherwise[..], [AyNode { inner: Statement::Expr(expr), .. }]
+            if expr.inner == Expr::Number(3))
+        );
+    }
+
+    #[test]
+    fn postfix_chain() {
+        let ast = parse(SourceCode::File(
+            "./examples/features/expressions/valid/postfix.ay".to_owned(),
+        ))
+        .unwrap();
+
+        let exprs = ast
+            .iter()
+            .filter_map(|node| match &node.inner {
+                Statement::Expr(expr) => Some(expr),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // ((a.b)[0]).c
+        let Expr::Field { value, name } = &exprs[0].inner else {
+            panic!(
+                "Expected the last link outermost, found {:?}",
+                exprs[0].inner
+            );
+        };
+        assert_eq!(name, "c");
+        assert_eq!(exprs[0].span.as_str(), "a.b[0].c");
+
+        let Expr::Index { value, index } = &value.inner else {
+            panic!("Expected an index, found {:?}", value.inner);
+        };
+        assert_eq!(index.inner, Expr::Number(0));
+        assert_eq!(value.span.as_str(), "a.b");
+
+        assert!(matches!(
+            &value.inner,
+            Expr::Field { value, name } if name == "b" && value.inner == Expr::Ident("a".to_owned())
+        ));
+
+        // Negation applies to the whole chain
+        assert!(matches!(
+            &exprs[1].inner,
+            Expr::Negated(node) if matches!(node.inner, Expr::Index { .. })
+        ));
+    }
+
+    #[test]
+    fn comparison_span() {
+        let source = "may' ke 1 sì 2 melo livu teng";
+        let ast = parse(SourceCode::Content(format!("{source}."))).unwrap();
+
+        let Statement::Expr(expr) = &ast[0].inner else {
+            panic!("Expected an expression statement");
+        };
+
+        assert!(matches!(expr.inner, Expr::Comparison { .. }));
+        assert_eq!(expr.span.as_str(), source);
+        assert_eq!(
+            expr.span.line_col(),
+            &LineColLocation::Span((1, 1), (1, source.chars().count() + 1))
+        );
+    }
+
+    #[test]
+    fn logical_precedence() {
+        let source = "a || b && c || d";
+        let ast = parse(SourceCode::Content(format!("{source}."))).unwrap();
+
+        let Statement::Expr(expr) = &ast[0].inner else {
+            panic!("Expected an expression statement");
+        };
+        let Expr::BinOp {
+            op: BinaryOperator::Or,
+            left,
+            right,
+        } = &expr.inner
+        else {
+            panic!("Expected `||` to bind the loosest, found {:?}", expr.inner);
+        };
+
+        assert_eq!(expr.span.as_str(), source);
+        assert_eq!(left.span.as_str(), "a || b && c");
+        assert!(matches!(
+            &left.inner,
+            Expr::BinOp {
+                op: BinaryOperator::Or,
+                right,
+                ..
+            } if matches!(right.inner, Expr::BinOp { op: BinaryOperator::And, .. })
+        ));
+        assert_eq!(right.inner, Expr::Ident("d".to_owned()));
+    }
+
+    #[test]
+    fn source_file() {
+        let trace = parse(SourceCode::File("./examples/missing.ay".to_owned())).unwrap_err();
+
+        assert!(matches!(trace.frames().next(), Some((Stage::Reading, _))));
+
+        let path = "./examples/features/statements/invalid/var_dec_value.ay";
+        let trace = parse(SourceCode::File(path.to_owned())).unwrap_err();
+        let resolved = std::fs::canonicalize(path).unwrap();
+
+        assert_eq!(trace.file(), resolved.to_str());
+        assert!(trace
+            .to_string()
+            .contains(&format!("{}:1:", resolved.display())));
+    }
+
+    #[test]
+    fn source_bytes() {
+        let bytes = "\u{feff}ngop 'u ìlä alu 1.".as_bytes().to_vec();
+
+        assert_eq!(
+            parse(SourceCode::Bytes(bytes)).unwrap(),
+            parse(SourceCode::Content("ngop 'u ìlä alu 1.".to_owned())).unwrap()
+        );
+
+        let mut bytes = b"ngop 'u a alu ".to_vec();
+        bytes.extend([0xc3, 0x28]);
+
+        let trace = parse(SourceCode::Bytes(bytes)).unwrap_err();
+
+        assert!(trace
+            .frames()
+            .any(|(stage, err)| stage == Stage::Parsing
+                && err.message() == "Invalid UTF-8 at byte 14"));
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let path = "./examples/features/expressions/valid/bom.ay";
+        let content = std::fs::read_to_string(path).unwrap();
+
+        assert!(content.starts_with('\u{feff}'));
+
+        let from_file = parse(SourceCode::File(path.to_owned())).unwrap();
+        let mut from_content =
+            parse(SourceCode::Content(content.replacen('\u{feff}', "", 1))).unwrap();
+        let file = from_file[0].span.file().unwrap();
+        from_content
+            .iter_mut()
+            .for_each(|node| set_file(node, file));
+
+        assert_eq!(from_file, from_content);
+
+        let trace = parse(SourceCode::Content("\u{feff}ngop 'u 1.".to_owned())).unwrap_err();
+        assert!(trace
+            .frames()
+            .all(|(_, err)| matches!(err.line_col(), LineColLocation::Pos((1, 9)))));
+    }
+
+    #[test]
+    fn module_uses() {
+        let folder = "./examples/features/modules";
+
+        // The function of the module comes before the assertion using it, each in its own file
+        let ast = parse(SourceCode::File(format!("{folder}/valid/uses.ay"))).unwrap();
+        assert!(matches!(&ast[0].inner, Statement::FunDec { name, .. } if name == "rey"));
+        assert!(matches!(ast[1].inner, Statement::Assert(_)));
+        assert!(ast[0].span.file().unwrap().ends_with("rey.ay"));
+        assert!(ast[1].span.file().unwrap().ends_with("uses.ay"));
+
+        let trace = parse(SourceCode::File(format!("{folder}/invalid/ping.ay"))).unwrap_err();
+        let (stage, err) = trace.frames().next().unwrap();
+        assert!(matches!(stage, Stage::Parsing));
+        assert_eq!(err.line(), "sar ping");
+        assert!(trace.file().unwrap().ends_with("pong.ay"));
+
+        let cycle = err
+            .message()
+            .strip_prefix("Cyclic module use: ")
+            .unwrap()
+            .split(" -> ")
+            .map(|path| path.rsplit('/').next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(cycle, ["ping.ay`", "pong.ay`", "ping.ay`"]);
+
+        // Without a file to be relative to
+        assert!(parse(SourceCode::Content("sar rey.".to_owned())).is_err());
+    }
+
+    #[test]
+    fn parse_cache() {
+        let mut cache = ParseCache::new();
+        let source = SourceCode::Content("ngop 'u a alu 1.".to_owned());
+
+        let first = cache.parse_cached(&source).unwrap();
+        assert_eq!(cache.hits(), 0);
+        assert_eq!(cache.parse_cached(&source).unwrap(), first);
+        assert_eq!(cache.hits(), 1);
+
+        // Same content as bytes
+        let bytes = SourceCode::Bytes(b"ngop 'u a alu 1.".to_vec());
+        assert_eq!(cache.parse_cached(&bytes).unwrap(), first);
+        assert_eq!(cache.hits(), 2);
+
+        // Errors are kept as well
+        let invalid = SourceCode::Content("ngop 'u 1.".to_owned());
+        let trace = cache
+            .parse_cached(&invalid)
+            .unwrap_err()
+            .to_string_colored(false);
+        assert_eq!(
+            cache
+                .parse_cached(&invalid)
+                .unwrap_err()
+                .to_string_colored(false),
+            trace
+        );
+        assert_eq!(cache.hits(), 3);
+
+        // Only the last source is kept
+        cache.parse_cached(&source).unwrap();
+        assert_eq!(cache.hits(), 3);
+
+        // Same content from a file, located in it
+        let path = "./examples/features/expressions/valid/bom.ay";
+        let file = SourceCode::File(path.to_owned());
+        let from_file = cache.parse_cached(&file).unwrap();
+        assert_eq!(cache.parse_cached(&file).unwrap(), from_file);
+        assert_eq!(cache.hits(), 4);
+        assert_eq!(from_file, parse(file).unwrap());
+    }
+
+    #[test]
+    fn octal_float() {
+        let ast = parse(SourceCode::Content("1.4. 0.14.".to_owned())).unwrap();
+
+        assert!(matches!(
+            ast.as_slice(),
+            [
+                AyNode {
+                    inner: Statement::Expr(AyNode {
+                        inner: Expr::Float(first),
+                        ..
+                    }),
+                    ..
+                },
+                AyNode {
+                    inner: Statement::Expr(AyNode {
+                        inner: Expr::Float(second),
+                        ..
+                    }),
+                    ..
+                },
+            ] if *first == 1.5 && *second == 0.1875
+        ));
+    }
+
+    #[test]
+    fn lossless_round_trip() {
+        let source = "''header comment''\n\
+                      ngop 'u a alu 2.  ''trailing''\n\
+                      \n\n\
+                      ngop   lì'ukìng foo fa b alu\n    ke b\ntxew. a.\n\
+                      ''dangling comment\n";
+
+        let ast = parse_lossless(SourceCode::Content(source.to_owned())).unwrap();
+
+        assert_eq!(ast.to_string(), source);
+
+        assert_eq!(ast.statements.len(), 3);
+        assert_eq!(ast.statements[0].leading, "''header comment''\n");
+        assert_eq!(ast.statements[0].trailing, ".  ''trailing''\n");
+        assert_eq!(ast.statements[1].leading, "\n\n");
+        assert_eq!(ast.statements[1].trailing, ". ");
+        assert_eq!(ast.statements[2].trailing, ".\n");
+        assert_eq!(ast.end, "''dangling comment\n");
+    }
+
+    #[test]
+    fn lossless_nested_trivia() {
+        let source = "ngop lì'ukìng foo fa b alu\n\
+                      \x20   '' Doubled\n\
+                      \x20   ngop 'u c alu b * 2 ulte  '' kept\n\
+                      \x20   ke c\n\
+                      txew.\n\
+                      leyn kem si\n\
+                      \x20   txo foo fa 1 lu 2, tsakrr hum txew\n\
+                      ftang.\n";
+        let parse = |source: &str| parse_lossless(SourceCode::Content(source.to_owned())).unwrap();
+
+        let mut ast = parse(source);
+        assert_eq!(ast.to_string(), source);
+
+        let body = &ast.statements[0].nested;
+        assert_eq!(body.len(), 2);
+        assert_eq!(body[0].leading, "    '' Doubled\n    ");
+        assert_eq!(body[0].trailing, "ulte  '' kept\n");
+        assert_eq!(
+            (body[1].leading.as_str(), body[1].trailing.as_str()),
+            ("    ", "")
+        );
+        assert_eq!(
+            ast.statements[0].around,
+            ["ngop lì'ukìng foo fa b alu\n", "", "\ntxew"]
+        );
+
+        // Only the modified statements are rendered differently, nested ones included
+        ast.statements[0].nested[0].leading = "    ".to_owned();
+        ast.statements[0].nested[1].node = parse("ke c * 3.").statements[0].node.clone();
+        ast.statements[1].nested[0].nested[0].node = parse("kä.").statements[0].node.clone();
+
+        assert_eq!(
+            ast.to_string(),
+            "ngop lì'ukìng foo fa b alu\n\
+             \x20   ngop 'u c alu b * 2 ulte  '' kept\n\
+             \x20   ke c * 3\n\
+             txew.\n\
+             leyn kem si\n\
+             \x20   txo foo fa 1 lu 2, tsakrr kä txew\n\
+             ftang.\n"
+        );
+    }
+
+    #[test]
+    fn structural_equality() {
+        let parse = |source: &str| parse(SourceCode::Content(source.to_owned())).unwrap();
+
+        let source = "ngop lì'ukìng f fa a alu\n\
+                 txo a lu 1, tsakrr vezeykoyu a sì 2, txokefyaw ke a[0] fu 3\n\
+             txew.\n\
+             leyn tstxo ngay vaykrr srak f fa 1 kem si hum ftang.";
+        let (ast, shifted) = (parse(source), parse(&format!("\n   \t{source}")));
+
+        assert_ne!(ast, shifted);
+        assert!(ast.structural_eq(&shifted));
+
+        assert!(!ast.structural_eq(&parse(&source.replace("sì 2", "sì 3"))));
+        assert!(!ast.structural_eq(&parse(&source.replace("tstxo ngay ", ""))));
+        assert!(!ast[..1].structural_eq(&ast[1..]));
     }
 }
@@ -1,187 +1,1095 @@
 use crate::{
     ast::{
-        binding::{Expr as BExpr, Statement as BStatement},
-        lib::{convert_iter, AyNode, AyType, ComparisonOperator, Multiplier, Node},
+        binding::{Expr as BExpr, FunDec as BFunDec, Statement as BStatement, Tense},
+        lib::{AyNode, AyType, BinaryOperator, ComparisonOperator, Node},
     },
     error::{
         span::Span,
         trace::{Stage, Trace, TraceError},
         trace_error::Error,
     },
+    interp::builtins,
 };
 
-use std::rc::Rc;
+use std::{rc::Rc, str::FromStr};
 
-use paste::paste;
+use quickscope::ScopeMap;
 
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub enum Tense {
-    Present,
-    Imminent,
-    Future,
-}
-
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct FunDec {
-    name: String,
-    args: Vec<Rc<VarDec>>,
-    body: Vec<AyNode<Statement>>,
+    pub name: String,
+    pub args: Vec<String>,
+    /// `AyType::Function` taking the types of the parameters.
+    pub fun_type: AyType,
+    pub body: Vec<AyNode<Statement>>,
+    pub helpers: Vec<AyNode<Statement>>,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct VarDec {
-    names: Vec<String>,
-    values: Vec<TypedExpr>,
+    pub names: Vec<String>,
+    pub values: Vec<AyNode<TypedExpr>>,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct TypedExpr {
-    expr_type: AyType,
-    inner: Expr,
+    pub expr_type: AyType,
+    pub inner: Expr,
 }
 impl Node for TypedExpr {}
 
 /// A statement is anything that cannot be expected to return a value.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum Statement {
     FunDec(Rc<FunDec>),
-    VarDec(Rc<VarDec>),
-    Expr(TypedExpr),
+    VarDec(VarDec),
+    Assign(VarDec),
+    Expr(AyNode<TypedExpr>),
     If {
-        cond: TypedExpr,
+        cond: AyNode<TypedExpr>,
         then: Vec<AyNode<Statement>>,
         otherwise: Vec<AyNode<Statement>>,
     },
     Loop {
-        cond: Option<TypedExpr>,
+        cond: Option<AyNode<TypedExpr>>,
         body: Vec<AyNode<Statement>>,
+        label: Option<String>,
     },
+    Break(Option<String>),
+    Continue(Option<String>),
+    Assert(AyNode<TypedExpr>),
 }
 impl Node for Statement {}
 
 /// An expression is anything that is or returns a value.
-#[derive(PartialEq, Eq, Debug, Clone)]
+/// Fields are missing as no value has any.
+#[derive(PartialEq, Debug, Clone)]
 pub enum Expr {
     FunCall {
         tense: Tense,
-        dec: Rc<FunDec>,
         name: String,
-        args: Vec<TypedExpr>,
+        args: Vec<AyNode<TypedExpr>>,
     },
     Array {
-        items: Vec<TypedExpr>,
+        items: Vec<AyNode<TypedExpr>>,
     },
     Comparison {
-        left: Box<TypedExpr>,
-        right: Box<TypedExpr>,
+        left: Box<AyNode<TypedExpr>>,
+        right: Box<AyNode<TypedExpr>>,
         operator: ComparisonOperator,
     },
+    BinOp {
+        op: BinaryOperator,
+        left: Box<AyNode<TypedExpr>>,
+        right: Box<AyNode<TypedExpr>>,
+    },
     Number(i64),
+    Float(f64),
     String(String),
-    Var(Rc<VarDec>),
-    Negated(Box<TypedExpr>),
+    Bool(bool),
+    Var(String),
+    Negated(Box<AyNode<TypedExpr>>),
+    None,
+    HasValue(Box<AyNode<TypedExpr>>),
+    Fallback {
+        value: Box<AyNode<TypedExpr>>,
+        fallback: Box<AyNode<TypedExpr>>,
+    },
+    Index {
+        value: Box<AyNode<TypedExpr>>,
+        index: Box<AyNode<TypedExpr>>,
+    },
+    FunRef(String),
 }
 impl Node for Expr {}
 
-pub fn convert(mut ast: &Vec<AyNode<BStatement>>) -> Result<Vec<AyNode<Statement>>, Trace> {
-    ast.iter()
-        .map(convert_statement)
-        .collect::<Result<Vec<AyNode<Statement>>, Trace>>()
+/// Infers the type of every expression, parameters included as they are never annotated.
+/// Functions get a single type, calling one with different types of arguments is an error.
+pub fn convert(ast: &[AyNode<BStatement>]) -> Result<Vec<AyNode<Statement>>, Trace> {
+    let mut typer = Typer::default();
+    let (ast, _) = typer.statements(ast)?;
+    typer.check_negations()?;
+
+    Ok(ast
+        .into_iter()
+        .map(|node| typer.resolve_statement(node))
+        .collect())
 }
 
-fn convert_statement(
-    AyNode { span, inner }: &AyNode<BStatement>,
-) -> Result<AyNode<Statement>, Trace> {
-    match inner {
-        BStatement::Expr(expr) => Ok(AyNode {
-            span: span.clone(),
-            inner: Statement::Expr(convert_expr(expr)?),
-        }),
-        _ => todo!(),
-    }
+fn error(span: &Span, message: &str) -> Trace {
+    Trace::new(Stage::Typing, Error::from_span(span.clone(), message))
 }
 
-fn convert_expr(AyNode { span, inner }: &AyNode<BExpr>) -> Result<TypedExpr, Trace> {
-    match inner {
-        BExpr::Number(number) => Ok(TypedExpr {
-            expr_type: AyType::Number,
-            inner: Expr::Number(*number),
-        }),
-        BExpr::String(string) => Ok(TypedExpr {
-            expr_type: AyType::String,
-            inner: Expr::String(string.clone()),
-        }),
-        BExpr::Array { items } => {
-            let items = convert_iter!(expr items)?;
-
-            Ok(TypedExpr {
-                expr_type: AyType::Array(Box::new(
-                    items
-                        .get(0)
-                        .ok_or_else(|| {
-                            Trace::new(
-                                Stage::Typing,
-                                Error::from_span(span.clone(), "Cannot defined empty arrays"),
-                            )
-                        })?
-                        .expr_type
-                        .clone(),
-                )),
-                inner: Expr::Array {
-                    items: items.clone(),
-                },
+#[derive(Default)]
+struct Typer {
+    /// What each `AyType::Var` turned out to be, if known yet.
+    found: Vec<Option<AyType>>,
+    vars: ScopeMap<String, AyType>,
+    funs: ScopeMap<String, AyType>,
+    /// Operands of negations whose type was not known yet, checked once everything is typed.
+    negations: Vec<(Span, AyType)>,
+}
+
+impl Typer {
+    fn fresh(&mut self) -> AyType {
+        self.found.push(None);
+        AyType::Var(self.found.len() - 1)
+    }
+
+    /// Fails on negations of values that turned out not to be numbers or truth values. Arrays are
+    /// rejected as well, their negation not having their type.
+    fn check_negations(&self) -> Result<(), Trace> {
+        self.negations
+            .iter()
+            .try_for_each(|(span, ty)| match self.resolve(ty) {
+                AyType::Number | AyType::Float | AyType::Bool | AyType::Var(_) => Ok(()),
+                ty => Err(error(span, &format!("Cannot negate {ty}"))),
             })
+    }
+
+    /// Replaces the type variables found so far with their types.
+    fn resolve(&self, ty: &AyType) -> AyType {
+        match ty {
+            AyType::Var(var) => match &self.found[*var] {
+                Some(ty) => self.resolve(ty),
+                None => ty.clone(),
+            },
+            AyType::Array(item) => AyType::Array(Box::new(self.resolve(item))),
+            AyType::Optional(inner) => AyType::Optional(Box::new(self.resolve(inner))),
+            AyType::Function { args, result } => AyType::Function {
+                args: args.iter().map(|arg| self.resolve(arg)).collect(),
+                result: Box::new(self.resolve(result)),
+            },
+            _ => ty.clone(),
         }
-        BExpr::Negated(expr) => {
-            let expr = convert_expr(expr)?;
-
-            Ok(TypedExpr {
-                expr_type: match expr.expr_type {
-                    AyType::Number => Ok(AyType::Number),
-                    AyType::Array(_) => Ok(AyType::Bool),
-                    _ => Err(Trace::new(
-                        Stage::Binding,
-                        Error::from_span(
-                            span.clone(),
-                            format!("Can only negate Number or Array, not {:?}", expr.expr_type)
-                                .as_ref(),
-                        ),
-                    )),
-                }?,
-                inner: todo!(),
+    }
+
+    /// Whether `var` appears in `ty`, making it infinite if they were unified.
+    fn occurs(&self, var: usize, ty: &AyType) -> bool {
+        match self.resolve(ty) {
+            AyType::Var(other) => var == other,
+            AyType::Array(inner) | AyType::Optional(inner) => self.occurs(var, &inner),
+            AyType::Function { args, result } => {
+                args.iter().any(|arg| self.occurs(var, arg)) || self.occurs(var, &result)
+            }
+            _ => false,
+        }
+    }
+
+    /// Makes `a` and `b` the same type, returning whether they can be.
+    fn unify(&mut self, a: &AyType, b: &AyType) -> bool {
+        match (self.resolve(a), self.resolve(b)) {
+            (AyType::Var(a), AyType::Var(b)) if a == b => true,
+            (AyType::Var(var), ty) | (ty, AyType::Var(var)) => {
+                let valid = !self.occurs(var, &ty);
+                if valid {
+                    self.found[var] = Some(ty);
+                }
+                valid
+            }
+            (AyType::Array(a), AyType::Array(b)) | (AyType::Optional(a), AyType::Optional(b)) => {
+                self.unify(&a, &b)
+            }
+            (
+                AyType::Function { args, result },
+                AyType::Function {
+                    args: other_args,
+                    result: other_result,
+                },
+            ) => {
+                args.len() == other_args.len()
+                    && args
+                        .iter()
+                        .zip(&other_args)
+                        .all(|(arg, other)| self.unify(arg, other))
+                    && self.unify(&result, &other_result)
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    fn expect(&mut self, span: &Span, found: &AyType, expected: &AyType) -> Result<(), Trace> {
+        match self.unify(found, expected) {
+            true => Ok(()),
+            false => Err(error(
+                span,
+                &format!(
+                    "Expected {}, found {}",
+                    self.resolve(expected),
+                    self.resolve(found)
+                ),
+            )),
+        }
+    }
+
+    /// Type of two values ending up in the same place, a value being allowed where an optional
+    /// one is expected.
+    fn join(&mut self, a: &AyType, b: &AyType) -> Option<AyType> {
+        match (self.resolve(a), self.resolve(b)) {
+            (AyType::Optional(inner), other) | (other, AyType::Optional(inner))
+                if !matches!(other, AyType::Optional(_) | AyType::Var(_)) =>
+            {
+                self.unify(&inner, &other)
+                    .then(|| self.resolve(&AyType::Optional(inner)))
+            }
+            (a, b) => self.unify(&a, &b).then(|| self.resolve(&a)),
+        }
+    }
+
+    /// Types the statements along with the value of the last one, `Unit` if there is none.
+    fn statements(
+        &mut self,
+        stmts: &[AyNode<BStatement>],
+    ) -> Result<(Vec<AyNode<Statement>>, AyType), Trace> {
+        let mut res = AyType::Unit;
+
+        // Declared ahead for calls to see functions declared later on
+        stmts.iter().for_each(|node| {
+            if let BStatement::FunDec(dec) = &node.inner {
+                self.declare(dec);
+            }
+        });
+
+        let stmts = stmts
+            .iter()
+            .map(|node| {
+                let (node, ty) = self.statement(node)?;
+                res = ty;
+                Ok(node)
             })
+            .collect::<Result<Vec<_>, Trace>>()?;
+
+        Ok((stmts, res))
+    }
+
+    /// Types the statements in a new scope.
+    fn block(
+        &mut self,
+        stmts: &[AyNode<BStatement>],
+    ) -> Result<(Vec<AyNode<Statement>>, AyType), Trace> {
+        self.vars.push_layer();
+        self.funs.push_layer();
+
+        let res = self.statements(stmts);
+
+        self.vars.pop_layer();
+        self.funs.pop_layer();
+
+        res
+    }
+
+    /// Type of the function declared by `dec` in the current scope, made of fresh variables unless
+    /// it was already declared.
+    fn declare(&mut self, dec: &BFunDec) -> AyType {
+        if let Some(fun_type) = self
+            .funs
+            .get(&dec.name)
+            .filter(|_| self.funs.contains_key_at_top(&dec.name))
+        {
+            return fun_type.clone();
         }
-        BExpr::Comparison {
-            left,
-            right,
-            operator,
-        } => {
-            let left = convert_expr(left)?;
-            let right = convert_expr(right)?;
-
-            if left.expr_type == right.expr_type {
-                Ok(TypedExpr {
-                    expr_type: AyType::Bool,
-                    inner: Expr::Comparison {
+
+        let fun_type = AyType::Function {
+            args: dec.args.iter().map(|_| self.fresh()).collect(),
+            result: Box::new(self.fresh()),
+        };
+        self.funs.define(dec.name.clone(), fun_type.clone());
+
+        fun_type
+    }
+
+    fn fun_dec(&mut self, span: &Span, dec: &BFunDec) -> Result<FunDec, Trace> {
+        // Defined before the body for recursive calls to see it
+        let fun_type = self.declare(dec);
+        let AyType::Function { args, result } = fun_type.clone() else {
+            unreachable!("functions are declared with function types")
+        };
+        let result = *result;
+
+        self.vars.push_layer();
+        self.funs.push_layer();
+
+        dec.args
+            .iter()
+            .zip(args)
+            .for_each(|(name, ty)| self.vars.define(name.clone(), ty));
+
+        let res = self.statements(&dec.helpers).and_then(|(helpers, _)| {
+            self.statements(&dec.body)
+                .map(|(body, ty)| (helpers, body, ty))
+        });
+
+        self.vars.pop_layer();
+        self.funs.pop_layer();
+
+        let (helpers, body, ty) = res?;
+        let span = body.last().map_or(span, |node| &node.span);
+        self.expect(span, &ty, &result)?;
+
+        Ok(FunDec {
+            name: dec.name.clone(),
+            args: dec.args.clone(),
+            fun_type,
+            body,
+            helpers,
+        })
+    }
+
+    fn statement(
+        &mut self,
+        AyNode { span, inner }: &AyNode<BStatement>,
+    ) -> Result<(AyNode<Statement>, AyType), Trace> {
+        let (inner, ty) = match inner {
+            BStatement::FunDec(dec) => (
+                Statement::FunDec(Rc::new(self.fun_dec(span, dec)?)),
+                AyType::Unit,
+            ),
+            BStatement::VarDec(dec) => {
+                let values = self.exprs(&dec.values)?;
+
+                dec.names.iter().zip(&values).for_each(|(name, value)| {
+                    self.vars
+                        .define(name.clone(), value.inner.expr_type.clone())
+                });
+
+                (
+                    Statement::VarDec(VarDec {
+                        names: dec.names.clone(),
+                        values,
+                    }),
+                    AyType::Unit,
+                )
+            }
+            BStatement::Assign(dec) => {
+                let values = self.exprs(&dec.values)?;
+
+                for (name, value) in dec.names.iter().zip(&values) {
+                    let ty = self
+                        .vars
+                        .get(name)
+                        .cloned()
+                        .ok_or_else(|| error(span, &format!("Unbound variable: '{name}'")))?;
+                    self.expect(&value.span, &value.inner.expr_type, &ty)?;
+                }
+
+                (
+                    Statement::Assign(VarDec {
+                        names: dec.names.clone(),
+                        values,
+                    }),
+                    AyType::Unit,
+                )
+            }
+            BStatement::Expr(expr) => {
+                let expr = self.expr(expr)?;
+                let ty = expr.inner.expr_type.clone();
+
+                (Statement::Expr(expr), ty)
+            }
+            BStatement::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                let cond = self.expr(cond)?;
+                let (then, then_type) = self.block(then)?;
+                let (otherwise, otherwise_type) = self.block(otherwise)?;
+
+                // Without a value on both sides, there is none to use
+                let ty = match (self.resolve(&then_type), self.resolve(&otherwise_type)) {
+                    (AyType::Unit, _) | (_, AyType::Unit) => AyType::Unit,
+                    _ => self.join(&then_type, &otherwise_type).ok_or_else(|| {
+                        error(
+                            span,
+                            &format!(
+                                "Branches have different types: {} and {}",
+                                self.resolve(&then_type),
+                                self.resolve(&otherwise_type)
+                            ),
+                        )
+                    })?,
+                };
+
+                (
+                    Statement::If {
+                        cond,
+                        then,
+                        otherwise,
+                    },
+                    ty,
+                )
+            }
+            BStatement::Loop { cond, body, label } => (
+                Statement::Loop {
+                    cond: cond.as_ref().map(|cond| self.expr(cond)).transpose()?,
+                    body: self.block(body)?.0,
+                    label: label.clone(),
+                },
+                AyType::Unit,
+            ),
+            BStatement::Break(label) => (Statement::Break(label.clone()), AyType::Unit),
+            BStatement::Continue(label) => (Statement::Continue(label.clone()), AyType::Unit),
+            BStatement::Assert(cond) => (Statement::Assert(self.expr(cond)?), AyType::Unit),
+        };
+
+        Ok((
+            AyNode {
+                span: span.clone(),
+                inner,
+            },
+            ty,
+        ))
+    }
+
+    fn exprs(&mut self, exprs: &[AyNode<BExpr>]) -> Result<Vec<AyNode<TypedExpr>>, Trace> {
+        exprs.iter().map(|expr| self.expr(expr)).collect()
+    }
+
+    /// Result of calling the builtin `name`, which may take any number of arguments.
+    fn builtin(
+        &mut self,
+        span: &Span,
+        name: &str,
+        args: &[AyNode<TypedExpr>],
+    ) -> Result<AyType, Trace> {
+        let types = args
+            .iter()
+            .map(|arg| (&arg.span, &arg.inner.expr_type))
+            .collect::<Vec<_>>();
+
+        let arity = |count: usize| match types.len() == count {
+            true => Ok(()),
+            false => Err(error(
+                span,
+                &format!("'{name}' expects {count} arguments, found {}", types.len()),
+            )),
+        };
+
+        match name {
+            "wìntxu" => Ok(AyType::Unit),
+            // Either all Numbers or all Floats, as with `+`
+            "sung" => {
+                let sum = match types.first().map(|(_, ty)| self.resolve(ty)) {
+                    Some(AyType::Float) => AyType::Float,
+                    _ => AyType::Number,
+                };
+                types
+                    .iter()
+                    .try_for_each(|(span, ty)| self.expect(span, ty, &sum))?;
+
+                Ok(sum)
+            }
+            "'ì'awnyu" => {
+                arity(2)?;
+                types
+                    .iter()
+                    .try_for_each(|(span, ty)| self.expect(span, ty, &AyType::Number))?;
+
+                Ok(AyType::Number)
+            }
+            _ if ComparisonOperator::from_str(name).is_ok() => {
+                arity(2)?;
+                self.join(types[0].1, types[1].1)
+                    .map(|_| AyType::Bool)
+                    .ok_or_else(|| {
+                        error(
+                            span,
+                            &format!(
+                                "Cannot compare {} and {}",
+                                self.resolve(types[0].1),
+                                self.resolve(types[1].1)
+                            ),
+                        )
+                    })
+            }
+            _ => Err(error(span, &format!("Undefined function: '{name}'"))),
+        }
+    }
+
+    /// Type of the operator `name` used as a value. Arithmetic ones only take numbers, unlike
+    /// comparisons which take any two values of the same type.
+    fn operator(&mut self, span: &Span, name: &str) -> Result<AyType, Trace> {
+        if builtins::operator(name).is_some() {
+            return Ok(AyType::Function {
+                args: vec![AyType::Number, AyType::Number],
+                result: Box::new(AyType::Number),
+            });
+        }
+
+        match ComparisonOperator::from_str(name) {
+            Ok(_) => {
+                let operand = self.fresh();
+                Ok(AyType::Function {
+                    args: vec![operand.clone(), operand],
+                    result: Box::new(AyType::Bool),
+                })
+            }
+            Err(_) => Err(error(span, &format!("'{name}' cannot be used as a value"))),
+        }
+    }
+
+    fn expr(&mut self, AyNode { span, inner }: &AyNode<BExpr>) -> Result<AyNode<TypedExpr>, Trace> {
+        let (inner, expr_type) = match inner {
+            BExpr::FunCall { tense, name, args } => {
+                let args = self.exprs(args)?;
+
+                // Binding already checked the number of arguments
+                let fun_type = self.funs.get(name).map(|ty| self.resolve(ty));
+                let ty = match (fun_type, self.vars.get(name).cloned()) {
+                    (
+                        Some(AyType::Function {
+                            args: params,
+                            result,
+                        }),
+                        _,
+                    ) => {
+                        for (arg, param) in args.iter().zip(&params) {
+                            self.expect(&arg.span, &arg.inner.expr_type, param)?;
+                        }
+
+                        *result
+                    }
+                    // Through a variable, holding a function taking these arguments
+                    (_, Some(callee)) => {
+                        let result = self.fresh();
+                        let fun_type = AyType::Function {
+                            args: args.iter().map(|arg| arg.inner.expr_type.clone()).collect(),
+                            result: Box::new(result.clone()),
+                        };
+                        self.expect(span, &fun_type, &callee)?;
+
+                        result
+                    }
+                    _ => self.builtin(span, name, &args)?,
+                };
+
+                (
+                    Expr::FunCall {
+                        tense: tense.clone(),
+                        name: name.clone(),
+                        args,
+                    },
+                    ty,
+                )
+            }
+            BExpr::Array { items } => {
+                let items = self.exprs(items)?;
+                let mut item_type = self.fresh();
+
+                for item in &items {
+                    item_type = self
+                        .join(&item_type, &item.inner.expr_type)
+                        .ok_or_else(|| {
+                            error(
+                                &item.span,
+                                &format!(
+                                    "Array items must all have the same type, found {} and {}",
+                                    self.resolve(&item_type),
+                                    self.resolve(&item.inner.expr_type)
+                                ),
+                            )
+                        })?;
+                }
+
+                (Expr::Array { items }, AyType::Array(Box::new(item_type)))
+            }
+            BExpr::Comparison {
+                left,
+                right,
+                operator,
+            } => {
+                let (left, right) = (self.expr(left)?, self.expr(right)?);
+
+                if self
+                    .join(&left.inner.expr_type, &right.inner.expr_type)
+                    .is_none()
+                {
+                    return Err(error(
+                        span,
+                        &format!(
+                            "Cannot compare {} and {}",
+                            self.resolve(&left.inner.expr_type),
+                            self.resolve(&right.inner.expr_type)
+                        ),
+                    ));
+                }
+
+                (
+                    Expr::Comparison {
                         left: Box::new(left),
                         right: Box::new(right),
                         operator: operator.clone(),
                     },
-                })
-            } else {
-                Err(Trace::new(
-                    Stage::Binding,
-                    Error::from_span(
-                        span.clone(),
-                        format!(
-                            "Cannot compare {:?} and {:?}",
-                            left.expr_type, right.expr_type
-                        )
-                        .as_ref(),
-                    ),
-                ))
+                    AyType::Bool,
+                )
+            }
+            BExpr::BinOp { op, left, right } => {
+                let (left, right) = (self.expr(left)?, self.expr(right)?);
+                self.expect(&right.span, &right.inner.expr_type, &left.inner.expr_type)?;
+
+                let concat = *op == BinaryOperator::Concat;
+                let ty = match self.resolve(&left.inner.expr_type) {
+                    AyType::Bool if op.is_logical() => AyType::Bool,
+                    AyType::Var(_) if op.is_logical() => {
+                        self.unify(&left.inner.expr_type, &AyType::Bool);
+                        AyType::Bool
+                    }
+                    ty @ (AyType::String | AyType::Array(_)) if concat => ty,
+                    // Either a string or an array, left for later uses to tell
+                    AyType::Var(_) if concat => left.inner.expr_type.clone(),
+                    ty @ (AyType::Number | AyType::Float) if !concat && !op.is_logical() => ty,
+                    AyType::Var(_) => {
+                        self.unify(&left.inner.expr_type, &AyType::Number);
+                        AyType::Number
+                    }
+                    ty => {
+                        return Err(error(
+                            span,
+                            &format!("Cannot apply `{}` to {ty}", <&str>::from(op)),
+                        ))
+                    }
+                };
+
+                (
+                    Expr::BinOp {
+                        op: *op,
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    },
+                    ty,
+                )
+            }
+            BExpr::Number(number) => (Expr::Number(*number), AyType::Number),
+            BExpr::Float(number) => (Expr::Float(*number), AyType::Float),
+            BExpr::String(string) => (Expr::String(string.clone()), AyType::String),
+            BExpr::Bool(value) => (Expr::Bool(*value), AyType::Bool),
+            BExpr::Var(name) => (
+                Expr::Var(name.clone()),
+                self.vars
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| error(span, &format!("Unbound variable: '{name}'")))?,
+            ),
+            BExpr::Negated(expr) => {
+                let expr = self.expr(expr)?;
+
+                let ty = match self.resolve(&expr.inner.expr_type) {
+                    ty @ (AyType::Number | AyType::Float | AyType::Bool) => ty,
+                    AyType::Array(_) => AyType::Bool,
+                    // A number or a truth value, negating it giving the same type
+                    AyType::Var(_) => {
+                        self.negations
+                            .push((span.clone(), expr.inner.expr_type.clone()));
+                        expr.inner.expr_type.clone()
+                    }
+                    ty => return Err(error(span, &format!("Cannot negate {ty}"))),
+                };
+
+                (Expr::Negated(Box::new(expr)), ty)
+            }
+            BExpr::None => (Expr::None, AyType::Optional(Box::new(self.fresh()))),
+            BExpr::HasValue(expr) => {
+                let expr = self.expr(expr)?;
+
+                match self.resolve(&expr.inner.expr_type) {
+                    AyType::Optional(_) => {}
+                    AyType::Var(_) => {
+                        let inner = self.fresh();
+                        self.unify(&expr.inner.expr_type, &AyType::Optional(Box::new(inner)));
+                    }
+                    ty => return Err(error(span, &format!("{ty} always has a value"))),
+                }
+
+                (Expr::HasValue(Box::new(expr)), AyType::Bool)
+            }
+            BExpr::Fallback { value, fallback } => {
+                let (value, fallback) = (self.expr(value)?, self.expr(fallback)?);
+                let ty = fallback.inner.expr_type.clone();
+
+                match self.resolve(&value.inner.expr_type) {
+                    AyType::Optional(inner) => self.expect(&fallback.span, &ty, &inner)?,
+                    AyType::Var(_) => self.expect(
+                        &value.span,
+                        &value.inner.expr_type,
+                        &AyType::Optional(Box::new(ty.clone())),
+                    )?,
+                    found => return Err(error(span, &format!("{found} always has a value"))),
+                }
+
+                (
+                    Expr::Fallback {
+                        value: Box::new(value),
+                        fallback: Box::new(fallback),
+                    },
+                    ty,
+                )
+            }
+            BExpr::Index { value, index } => {
+                let (value, index) = (self.expr(value)?, self.expr(index)?);
+                let item = self.fresh();
+
+                self.expect(
+                    &value.span,
+                    &value.inner.expr_type,
+                    &AyType::Array(Box::new(item.clone())),
+                )?;
+                self.expect(&index.span, &index.inner.expr_type, &AyType::Number)?;
+
+                (
+                    Expr::Index {
+                        value: Box::new(value),
+                        index: Box::new(index),
+                    },
+                    item,
+                )
+            }
+            BExpr::Field { name, .. } => {
+                return Err(error(span, &format!("No field named '{name}'")))
+            }
+            BExpr::FunRef(name) => (Expr::FunRef(name.clone()), self.operator(span, name)?),
+        };
+
+        Ok(AyNode {
+            span: span.clone(),
+            inner: TypedExpr {
+                expr_type: self.resolve(&expr_type),
+                inner,
+            },
+        })
+    }
+
+    /// Replaces the type variables of the whole tree now that everything has been typed, those
+    /// left being types nothing constrains, such as the one of a lone `kewu`.
+    fn resolve_statement(&self, AyNode { span, inner }: AyNode<Statement>) -> AyNode<Statement> {
+        let statements = |stmts: Vec<AyNode<Statement>>| {
+            stmts
+                .into_iter()
+                .map(|node| self.resolve_statement(node))
+                .collect::<Vec<_>>()
+        };
+        let var_dec = |dec: VarDec| VarDec {
+            names: dec.names,
+            values: dec
+                .values
+                .into_iter()
+                .map(|value| self.resolve_expr(value))
+                .collect(),
+        };
+
+        let inner = match inner {
+            Statement::FunDec(dec) => {
+                let dec = Rc::unwrap_or_clone(dec);
+
+                Statement::FunDec(Rc::new(FunDec {
+                    fun_type: self.resolve(&dec.fun_type),
+                    body: statements(dec.body),
+                    helpers: statements(dec.helpers),
+                    ..dec
+                }))
+            }
+            Statement::VarDec(dec) => Statement::VarDec(var_dec(dec)),
+            Statement::Assign(dec) => Statement::Assign(var_dec(dec)),
+            Statement::Expr(expr) => Statement::Expr(self.resolve_expr(expr)),
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => Statement::If {
+                cond: self.resolve_expr(cond),
+                then: statements(then),
+                otherwise: statements(otherwise),
+            },
+            Statement::Loop { cond, body, label } => Statement::Loop {
+                cond: cond.map(|cond| self.resolve_expr(cond)),
+                body: statements(body),
+                label,
+            },
+            Statement::Assert(cond) => Statement::Assert(self.resolve_expr(cond)),
+            Statement::Break(_) | Statement::Continue(_) => inner,
+        };
+
+        AyNode { span, inner }
+    }
+
+    fn resolve_expr(&self, AyNode { span, inner }: AyNode<TypedExpr>) -> AyNode<TypedExpr> {
+        let boxed = |expr: Box<AyNode<TypedExpr>>| Box::new(self.resolve_expr(*expr));
+        let exprs = |exprs: Vec<AyNode<TypedExpr>>| {
+            exprs
+                .into_iter()
+                .map(|expr| self.resolve_expr(expr))
+                .collect::<Vec<_>>()
+        };
+
+        let expr = match inner.inner {
+            Expr::FunCall { tense, name, args } => Expr::FunCall {
+                tense,
+                name,
+                args: exprs(args),
+            },
+            Expr::Array { items } => Expr::Array {
+                items: exprs(items),
+            },
+            Expr::Comparison {
+                left,
+                right,
+                operator,
+            } => Expr::Comparison {
+                left: boxed(left),
+                right: boxed(right),
+                operator,
+            },
+            Expr::BinOp { op, left, right } => Expr::BinOp {
+                op,
+                left: boxed(left),
+                right: boxed(right),
+            },
+            Expr::Negated(expr) => Expr::Negated(boxed(expr)),
+            Expr::HasValue(expr) => Expr::HasValue(boxed(expr)),
+            Expr::Fallback { value, fallback } => Expr::Fallback {
+                value: boxed(value),
+                fallback: boxed(fallback),
+            },
+            Expr::Index { value, index } => Expr::Index {
+                value: boxed(value),
+                index: boxed(index),
+            },
+            expr @ (Expr::Number(_)
+            | Expr::Float(_)
+            | Expr::String(_)
+            | Expr::Bool(_)
+            | Expr::Var(_)
+            | Expr::None
+            | Expr::FunRef(_)) => expr,
+        };
+
+        AyNode {
+            span,
+            inner: TypedExpr {
+                expr_type: self.resolve(&inner.expr_type),
+                inner: expr,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ast::{binding, lib::SourceCode, parsing};
+
+    use super::*;
+
+    fn typed(source: &str) -> Result<Vec<AyNode<Statement>>, Trace> {
+        let ast = parsing::parse(SourceCode::Content(source.to_owned())).unwrap();
+        convert(&binding::convert(&ast).unwrap())
+    }
+
+    fn expr_type(node: &AyNode<Statement>) -> &AyType {
+        match &node.inner {
+            Statement::Expr(expr) => &expr.inner.expr_type,
+            inner => panic!("Expected an expression, found {inner:?}"),
+        }
+    }
+
+    #[test]
+    fn well_typed() {
+        let ast = typed(
+            "ngop lì'ukìng rey fa a sì b alu\n\
+               txo a lu b, tsakrr kewu, txokefyaw sung fa a sì b\n\
+             txew.\n\
+             ngop 'u nums alu vezeykoyu 1 sì 2.\n\
+             rey fa nums[0] sì 2.\n\
+             srak rey fa 1 sì 1.\n\
+             vezeykoyu 1 sì kewu sì rey fa 1 sì 2.",
+        )
+        .unwrap();
+
+        let Statement::FunDec(dec) = &ast[0].inner else {
+            panic!("Expected a function declaration");
+        };
+
+        // The parameters are only known from their use in `sung`
+        assert_eq!(
+            dec.fun_type,
+            AyType::Function {
+                args: vec![AyType::Number, AyType::Number],
+                result: Box::new(AyType::Optional(Box::new(AyType::Number))),
             }
+        );
+        assert_eq!(
+            expr_type(&ast[2]),
+            &AyType::Optional(Box::new(AyType::Number))
+        );
+        assert_eq!(expr_type(&ast[3]), &AyType::Bool);
+        assert_eq!(expr_type(&ast[4]).to_string(), "Array of Optional Number");
+    }
+
+    #[test]
+    fn bool_literals() {
+        let ast = typed("ngop 'u a alu srane.\nvezeykoyu a sì kehe sì 1 lu 2.").unwrap();
+
+        assert_eq!(expr_type(&ast[1]).to_string(), "Array of Bool");
+
+        let trace = typed("vezeykoyu srane sì 1.").unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert_eq!(
+            err.message(),
+            "Array items must all have the same type, found Bool and Number"
+        );
+    }
+
+    #[test]
+    fn mixed_array() {
+        let trace = typed("vezeykoyu 1 sì san a sìk.").unwrap_err();
+        let (stage, err) = trace.frames().next().unwrap();
+
+        assert!(matches!(stage, Stage::Typing));
+        assert_eq!(
+            err.message(),
+            "Array items must all have the same type, found Number and String"
+        );
+        assert_eq!(err.line(), "a");
+    }
+
+    #[test]
+    fn arithmetic() {
+        let ast = typed("ngop lì'ukìng dbl fa a alu a * 2 txew.\n1.4 / 2.0.").unwrap();
+
+        let Statement::FunDec(dec) = &ast[0].inner else {
+            panic!("Expected a function declaration");
+        };
+        assert_eq!(dec.fun_type.to_string(), "(Number) -> Number");
+        assert_eq!(expr_type(&ast[1]), &AyType::Float);
+
+        // Numbers and Floats do not mix
+        let trace = typed("1 + 0.4.").unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert_eq!(err.message(), "Expected Number, found Float");
+
+        // Neither does `sung`
+        let ast = typed("sung fa 1.4 sì 2.0.").unwrap();
+        assert_eq!(expr_type(&ast[0]), &AyType::Float);
+        let trace = typed("sung fa 1.4 sì 2.").unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert_eq!(err.message(), "Expected Float, found Number");
+
+        let trace = typed("san a sìk + san b sìk.").unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert_eq!(err.message(), "Cannot apply `+` to String");
+
+        let ast = typed("san a sìk ++ san b sìk.\n(vezeykoyu 1) ++ (vezeykoyu 2).").unwrap();
+        assert_eq!(expr_type(&ast[0]), &AyType::String);
+        assert_eq!(expr_type(&ast[1]).to_string(), "Array of Number");
+
+        let trace = typed("1 ++ 2.").unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert_eq!(err.message(), "Cannot apply `++` to Number");
+    }
+
+    #[test]
+    fn operators_as_values() {
+        let source =
+            std::fs::read_to_string("./examples/features/functions/valid/higher_order.ay").unwrap();
+        let ast = typed(&source).unwrap();
+
+        let Statement::FunDec(dec) = &ast[0].inner else {
+            panic!("Expected a function declaration");
+        };
+        assert_eq!(
+            dec.fun_type.to_string(),
+            "((Number, Number) -> Number, Array of Number, Number) -> Number"
+        );
+
+        let ast = typed("(teng).\n(+).").unwrap();
+        assert_eq!(expr_type(&ast[1]).to_string(), "(Number, Number) -> Number");
+
+        let trace = typed("ngop lì'ukìng apply fa f alu f fa 1 txew.\napply fa (+).").unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert_eq!(
+            err.message(),
+            "Expected (Number) -> unknown, found (Number, Number) -> Number"
+        );
+
+        let trace =
+            typed("ngop lì'ukìng apply fa f alu f fa 1 sì 2 txew.\napply fa 1.").unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert_eq!(
+            err.message(),
+            "Expected (Number, Number) -> unknown, found Number"
+        );
+    }
+
+    #[test]
+    fn mutual_recursion() {
+        let ast = typed(
+            "kxetse fa 1.\n\
+             ngop lì'ukìng mesrr fa n alu\n\
+                 txo n lu 0, tsakrr 1, txokefyaw kxetse fa n - 1\n\
+             txew.\n\
+             ngop lì'ukìng kxetse fa n alu\n\
+                 txo n lu 0, tsakrr 0, txokefyaw mesrr fa n - 1\n\
+             txew.",
+        )
+        .unwrap();
+
+        assert_eq!(expr_type(&ast[0]), &AyType::Number);
+        for node in &ast[1..] {
+            let Statement::FunDec(dec) = &node.inner else {
+                panic!("Expected a function declaration");
+            };
+            assert_eq!(dec.fun_type.to_string(), "(Number) -> Number");
         }
-        _ => todo!(),
+
+        // Calls made before the declaration constrain it all the same
+        let trace = typed(
+            "rey fa san a sìk.\n\
+             ngop lì'ukìng rey fa a alu a * 2 txew.",
+        )
+        .unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert!(err.message().starts_with("Expected"), "{}", err.message());
+    }
+
+    #[test]
+    fn argument_types() {
+        let trace = typed(
+            "ngop lì'ukìng rey fa a alu sung fa a txew.\n\
+             rey fa san a sìk.",
+        )
+        .unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+
+        assert_eq!(err.message(), "Expected Number, found String");
+    }
+
+    #[test]
+    fn logical() {
+        let ast = typed("ngop lì'ukìng both fa a sì b alu a && b txew.").unwrap();
+
+        let Statement::FunDec(dec) = &ast[0].inner else {
+            panic!("Expected a function declaration");
+        };
+        assert_eq!(dec.fun_type.to_string(), "(Bool, Bool) -> Bool");
+
+        let trace = typed("1 && 2.").unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert_eq!(err.message(), "Cannot apply `&&` to Number");
+
+        let trace = typed("(1 lu 1) || 2.").unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert_eq!(err.message(), "Expected Bool, found Number");
+    }
+
+    #[test]
+    fn negations() {
+        let ast = typed(
+            "ngop lì'ukìng not fa b alu ke b txew.\n\
+             not fa srane.",
+        )
+        .unwrap();
+        assert_eq!(expr_type(&ast[1]), &AyType::Bool);
+
+        let trace = typed("ngop lì'ukìng not fa b alu ke b txew.\nnot fa san a sìk.").unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert_eq!(err.message(), "Cannot negate String");
     }
 }
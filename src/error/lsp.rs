@@ -0,0 +1,237 @@
+use super::trace::{Severity, Trace, TraceError};
+
+use pest::error::LineColLocation;
+
+/// Zero-based position, `character` being counted in UTF-16 code units as
+/// mandated by the LSP specification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LspPosition {
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LspRange {
+    pub start: LspPosition,
+    pub end: LspPosition,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LspSeverity {
+    Error = 1,
+    Warning = 2,
+    Information = 3,
+    Hint = 4,
+}
+
+impl From<Severity> for LspSeverity {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Error => LspSeverity::Error,
+            Severity::Warning => LspSeverity::Warning,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LspRelatedInformation {
+    pub range: LspRange,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LspDiagnostic {
+    pub range: LspRange,
+    pub severity: LspSeverity,
+    pub message: String,
+    pub stage: String,
+    /// The other frames of the same `Trace`.
+    pub related: Vec<LspRelatedInformation>,
+}
+
+impl Trace {
+    /// Converts the frames into diagnostics, deepest first. Context frames only
+    /// note where the others happened and are left as related information.
+    ///
+    /// `source` must be the text the trace was produced from, it is needed to
+    /// translate character columns into UTF-16 offsets.
+    pub fn to_lsp_diagnostics(&self, source: &str) -> Vec<LspDiagnostic> {
+        let frames = self
+            .frames()
+            .map(|(stage, err)| (stage, range(err, source), err.message().to_owned()))
+            .collect::<Vec<_>>();
+
+        frames
+            .iter()
+            .zip(self.context_frames())
+            .enumerate()
+            .filter(|(_, (_, context))| !context)
+            .map(|(index, ((stage, range, message), _))| LspDiagnostic {
+                range: *range,
+                severity: self.severity().into(),
+                message: message.clone(),
+                stage: format!("{stage:?}"),
+                related: frames
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != index)
+                    .map(|(_, (_, range, message))| LspRelatedInformation {
+                        range: *range,
+                        message: message.clone(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+impl LspPosition {
+    /// Builds a position from pest's one-based, character-counted coordinates.
+    fn from_line_col(source: &str, (line, col): (usize, usize)) -> Self {
+        let character = source
+            .lines()
+            .nth(line.saturating_sub(1))
+            .map(|text| {
+                text.chars()
+                    .take(col.saturating_sub(1))
+                    .map(char::len_utf16)
+                    .sum::<usize>()
+            })
+            .unwrap_or(col.saturating_sub(1));
+
+        LspPosition {
+            line: line.saturating_sub(1) as u32,
+            character: character as u32,
+        }
+    }
+
+    pub fn to_json(self) -> String {
+        format!(
+            "{{\"line\":{},\"character\":{}}}",
+            self.line, self.character
+        )
+    }
+}
+
+impl LspRange {
+    pub fn to_json(self) -> String {
+        format!(
+            "{{\"start\":{},\"end\":{}}}",
+            self.start.to_json(),
+            self.end.to_json()
+        )
+    }
+}
+
+impl LspDiagnostic {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"range\":{},\"severity\":{},\"message\":{},\"stage\":{},\"relatedInformation\":[{}]}}",
+            self.range.to_json(),
+            self.severity as u8,
+            json_string(&self.message),
+            json_string(&self.stage),
+            self.related
+                .iter()
+                .map(|related| format!(
+                    "{{\"range\":{},\"message\":{}}}",
+                    related.range.to_json(),
+                    json_string(&related.message)
+                ))
+                .collect::<Vec<String>>()
+                .join(",")
+        )
+    }
+}
+
+fn range(err: &dyn TraceError, source: &str) -> LspRange {
+    let (start, end) = match err.line_col() {
+        LineColLocation::Pos(pos) => (pos, pos),
+        LineColLocation::Span(start, end) => (start, end),
+    };
+
+    LspRange {
+        start: LspPosition::from_line_col(source, start),
+        end: LspPosition::from_line_col(source, end),
+    }
+}
+
+/// Quotes and escapes a string according to the JSON specification.
+pub(crate) fn json_string(string: &str) -> String {
+    let mut res = String::with_capacity(string.len() + 2);
+    res.push('"');
+
+    for c in string.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+
+    res.push('"');
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ast::{binding, lib::SourceCode, parsing};
+
+    use super::*;
+
+    #[test]
+    fn binding_error_to_diagnostic() {
+        let source = "ngop 'u a alu 1.\nngop 'u ìlä alu bee.";
+
+        let ast = parsing::parse(SourceCode::Content(source.to_owned())).unwrap();
+        let trace = binding::convert(&ast).unwrap_err();
+
+        let diagnostics = trace.to_lsp_diagnostics(source);
+        let diagnostic = diagnostics.first().unwrap();
+
+        // `bee` sits after a multi-byte character, columns must not be byte offsets
+        assert_eq!(
+            diagnostic.range,
+            LspRange {
+                start: LspPosition {
+                    line: 1,
+                    character: 16
+                },
+                end: LspPosition {
+                    line: 1,
+                    character: 19
+                },
+            }
+        );
+        assert_eq!(diagnostic.severity, LspSeverity::Error);
+        assert_eq!(diagnostic.stage, "Binding");
+
+        let json = diagnostic.to_json();
+        assert!(json.starts_with(
+            "{\"range\":{\"start\":{\"line\":1,\"character\":16},\"end\":{\"line\":1,\"character\":19}},\"severity\":1,"
+        ));
+    }
+
+    #[test]
+    fn context_frames_are_related() {
+        let source = "ngop lì'ukìng rey alu\n    ngop 'u a alu b ulte\n    a\ntxew.";
+
+        let ast = parsing::parse(SourceCode::Content(source.to_owned())).unwrap();
+        let trace = binding::convert(&ast).unwrap_err();
+        assert!(trace.context_frames().any(|context| context));
+
+        let diagnostics = trace.to_lsp_diagnostics(source);
+
+        // Only the error itself, the function it is found in being related to it
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Undefined variable: 'b'");
+        assert_eq!(diagnostics[0].range.start.line, 1);
+        assert_eq!(diagnostics[0].related.len(), 1);
+        assert_eq!(diagnostics[0].related[0].range.start.line, 0);
+    }
+}
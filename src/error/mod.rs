@@ -1,3 +1,4 @@
+pub mod lsp;
 pub mod span;
 pub mod trace;
 pub mod trace_error;
@@ -1,9 +1,44 @@
 use pest::error::LineColLocation;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Span {
     line: String,
+    #[cfg_attr(feature = "serde", serde(with = "line_col"))]
     location: LineColLocation,
+    /// Whole lines of source the span is found on, for errors to be shown in context.
+    context: String,
+    /// Path of the file the span is found in, for errors of later stages to point at it.
+    file: Option<String>,
+}
+
+/// `LineColLocation` as its start and optional end, each being a `(line, col)` pair.
+#[cfg(feature = "serde")]
+mod line_col {
+    use pest::error::LineColLocation;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    type Location = ((usize, usize), Option<(usize, usize)>);
+
+    pub fn serialize<S: Serializer>(
+        location: &LineColLocation,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match location {
+            LineColLocation::Pos(pos) => (*pos, None),
+            LineColLocation::Span(start, end) => (*start, Some(*end)),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<LineColLocation, D::Error> {
+        Ok(match Location::deserialize(deserializer)? {
+            (pos, None) => LineColLocation::Pos(pos),
+            (start, Some(end)) => LineColLocation::Span(start, end),
+        })
+    }
 }
 
 impl Span {
@@ -14,13 +49,127 @@ impl Span {
     pub fn line_col(&self) -> &LineColLocation {
         &self.location
     }
+
+    pub fn context(&self) -> &str {
+        self.context.as_ref()
+    }
+
+    pub fn start(&self) -> (usize, usize) {
+        match self.location {
+            LineColLocation::Pos(start) | LineColLocation::Span(start, _) => start,
+        }
+    }
+
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    pub fn set_file(&mut self, path: &str) {
+        self.file = Some(path.to_owned());
+    }
+
+    pub fn end(&self) -> (usize, usize) {
+        match self.location {
+            LineColLocation::Pos(end) | LineColLocation::Span(_, end) => end,
+        }
+    }
+}
+
+/// Smallest span covering both `a` and `b`, which must come from the same input, along with the
+/// text between them.
+pub fn join<'i>(a: pest::Span<'i>, b: pest::Span<'i>) -> pest::Span<'i> {
+    let start = match a.start() <= b.start() {
+        true => a.start_pos(),
+        false => b.start_pos(),
+    };
+    let end = match a.end() >= b.end() {
+        true => a.end_pos(),
+        false => b.end_pos(),
+    };
+
+    start.span(&end)
 }
 
 impl From<pest::Span<'_>> for Span {
     fn from(span: pest::Span) -> Self {
+        let input = span.get_input();
+        // A trailing line break belongs to the line it ends
+        let end = span.end() - usize::from(span.as_str().ends_with('\n'));
+        let context_start = input[..span.start()]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let context_end = input[end..]
+            .find('\n')
+            .map_or(input.len(), |index| end + index);
+
         Self {
             line: span.as_str().to_string(),
             location: LineColLocation::Span(span.start_pos().line_col(), span.end_pos().line_col()),
+            context: input[context_start..context_end.max(context_start)].to_owned(),
+            file: None,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn spans<const N: usize>(input: &str, ranges: [(usize, usize); N]) -> [pest::Span<'_>; N] {
+        ranges.map(|(start, end)| pest::Span::new(input, start, end).unwrap())
+    }
+
+    #[test]
+    fn join() {
+        let input = "ke a + b";
+
+        // Adjacent
+        let [a, b, whole] = spans(input, [(0, 3), (3, 4), (0, 4)]);
+        assert_eq!(super::join(a, b), whole);
+        assert_eq!(super::join(b, a), whole);
+
+        // Overlapping and contained
+        let [a, b, whole] = spans(input, [(0, 6), (3, 8), (0, 8)]);
+        assert_eq!(super::join(a, b), whole);
+        assert_eq!(super::join(whole, a), whole);
+        assert_eq!(super::join(a, a), a);
+
+        // Disjoint, keeping the text between them
+        let [a, b] = spans(input, [(0, 2), (7, 8)]);
+        let joined = Span::from(super::join(a, b));
+        assert_eq!(joined.as_str(), "ke a + b");
+        assert_eq!(joined.line_col(), &LineColLocation::Span((1, 1), (1, 9)));
+    }
+
+    #[test]
+    fn join_lines() {
+        let input = "ngop 'u a alu\n  b sì\n  c.";
+
+        let [a, b, whole] = spans(input, [(8, 16), (14, 21), (8, 21)]);
+        assert_eq!(super::join(a, b), whole);
+        assert_eq!(super::join(a, b).as_str(), "a alu\n  b sì");
+
+        let [a, c] = spans(input, [(8, 9), (24, 25)]);
+        let joined = Span::from(super::join(c, a));
+        assert_eq!(joined.as_str(), "a alu\n  b sì\n  c");
+        assert_eq!(joined.line_col(), &LineColLocation::Span((1, 9), (3, 4)));
+        assert_eq!((joined.start(), joined.end()), ((1, 9), (3, 4)));
+        assert_eq!(joined.context(), input);
+    }
+
+    #[test]
+    fn context() {
+        let input = "ngop 'u a alu\n  b sì\n\n  c.";
+
+        let [a, b, c] = spans(input, [(8, 9), (14, 22), (25, 26)]);
+        assert_eq!(Span::from(a).context(), "ngop 'u a alu");
+        // Up to the line break ending the span, not the line after it
+        assert_eq!(Span::from(b).context(), "  b sì");
+        assert_eq!(
+            Span::from(super::join(a, b)).context(),
+            "ngop 'u a alu\n  b sì"
+        );
+        assert_eq!(Span::from(super::join(a, c)).context(), input);
+        assert_eq!(Span::from(super::join(b, c)).context(), "  b sì\n\n  c.");
+    }
+}
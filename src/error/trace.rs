@@ -1,17 +1,19 @@
 use super::{span::Span, trace_error::Error};
 
-use crate::{highlight::highlight_aysinvi, parsing::*};
-
-use pest::{
-    error::{Error as PestError, ErrorVariant, LineColLocation},
-    iterators::{Pair, Pairs},
+use crate::{
+    ast::parsing::*,
+    highlight::{color, highlight_with, paint},
 };
 
+use pest::{error::LineColLocation, iterators::Pair};
+
 use std::fmt::Debug;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Stage {
     Unknown,
+    Reading,
     Parsing,
     AstBuilding,
     Binding,
@@ -19,40 +21,85 @@ pub enum Stage {
     Compiling,
 }
 
-pub trait TraceError: Debug {
+/// Shared between threads as part of a [`Trace`], e.g. once boxed as a `dyn std::error::Error`.
+pub trait TraceError: Debug + Send + Sync {
     fn from_span(span: Span, message: &str) -> Self
     where
         Self: Sized;
     fn line_col(&self) -> LineColLocation;
+    /// Source the error points at.
     fn line(&self) -> &str;
+    /// Whole lines of source the error is found on.
+    fn context(&self) -> &str;
     fn message(&self) -> &str;
+    /// Path of the file the error is found in, if known.
+    fn file(&self) -> Option<&str> {
+        None
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+}
+
+/// Order frames are rendered in, see [`Trace::display_order`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TraceOrder {
+    #[default]
+    DeepestFirst,
+    /// From the outermost frame, e.g. the call site, down to where the error happened.
+    ShallowestFirst,
 }
 
 #[derive(Debug, Default)]
 pub struct Trace {
-    stack: Vec<(Stage, Box<dyn TraceError>)>,
+    stack: Vec<Frame>,
+    severity: Severity,
+    /// Source file the frames' locations refer to, if known.
+    file: Option<String>,
 }
 
-impl<T: TraceError + 'static> From<(Stage, T)> for Trace {
-    fn from((stage, err): (Stage, T)) -> Self {
+/// Frames are copied as [`Error`]s, which keeps everything a trace shows of them.
+impl Clone for Trace {
+    fn clone(&self) -> Self {
         Trace {
-            stack: vec![(stage, Box::new(err))],
+            stack: self
+                .stack
+                .iter()
+                .map(|frame| Frame {
+                    err: Box::new(Error::copy_of(frame.err.as_ref())),
+                    ..*frame
+                })
+                .collect(),
+            severity: self.severity,
+            file: self.file.clone(),
         }
     }
 }
 
+impl<T: TraceError + 'static> From<(Stage, T)> for Trace {
+    fn from((stage, err): (Stage, T)) -> Self {
+        Trace::new(stage, err)
+    }
+}
+
 impl<T: TraceError + 'static> From<T> for Trace {
     fn from(err: T) -> Self {
-        Trace {
-            stack: vec![(Stage::Unknown, Box::new(err))],
-        }
+        Trace::new(Stage::Unknown, err)
     }
 }
 
 impl Trace {
+    /// The trace is in the file of `err`, if it knows it.
     pub fn new<T: TraceError + 'static>(stage: Stage, err: T) -> Self {
         Trace {
-            stack: vec![(stage, Box::new(err))],
+            file: err.file().map(str::to_owned),
+            stack: vec![Frame::new(stage, err)],
+            ..Default::default()
         }
     }
 
@@ -62,79 +109,492 @@ impl Trace {
         res
     }
 
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Sets the file the trace happened in, unless it was already known from a deeper one.
+    pub fn in_file(mut self, path: &str) -> Self {
+        self.file.get_or_insert_with(|| path.to_owned());
+        self
+    }
+
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// Iterates over the frames, deepest first.
+    pub fn frames(&self) -> impl Iterator<Item = (Stage, &dyn TraceError)> {
+        self.stack
+            .iter()
+            .map(|frame| (frame.stage, frame.err.as_ref()))
+    }
+
+    /// Whether each of the [`frames`](Self::frames) was pushed by
+    /// [`push_context`](Self::push_context), in the same order.
+    pub fn context_frames(&self) -> impl Iterator<Item = bool> + '_ {
+        self.stack.iter().map(|frame| frame.context)
+    }
+
+    /// Frames pushed afterwards are in the file of the trace, which they tell if it is not known.
     pub fn push<T: TraceError + 'static>(&mut self, stage: Stage, err: T) {
-        self.stack.push((stage, Box::new(err)))
+        if self.file.is_none() {
+            self.file = err.file().map(str::to_owned);
+        }
+        self.stack.push(Frame::new(stage, err))
+    }
+
+    /// Collapses consecutive frames with the same stage, location and message into one.
+    pub fn coalesce(&mut self) {
+        self.stack
+            .dedup_by(|frame, previous| same_frame(previous, frame));
+    }
+
+    /// Notes what was being done where the deeper frames happened, such as binding a function.
+    pub fn push_context(&mut self, stage: Stage, span: Span, note: String) {
+        self.push(stage, Error::from_span(span, note.as_ref()));
+        if let Some(frame) = self.stack.last_mut() {
+            frame.context = true;
+        }
     }
 
     pub fn push_pest_error(&mut self, stage: Stage, pair: &Pair<Rule>, message: String) {
-        self.stack.push((
+        self.stack.push(Frame::new(
             stage,
-            Box::new(Error::from_span(pair.as_span().into(), message.as_ref())),
+            Error::from_span(pair.as_span().into(), message.as_ref()),
         ))
     }
 }
 
+/// `(line, col)` of either end of a frame's location.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct JsonPosition {
+    line: usize,
+    column: usize,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct JsonFrame<'a> {
+    stage: Stage,
+    line: usize,
+    column: usize,
+    start: JsonPosition,
+    end: JsonPosition,
+    message: &'a str,
+    source: &'a str,
+}
+
+#[cfg(feature = "serde")]
+impl Trace {
+    /// Machine-readable form of the trace, its frames being listed deepest first.
+    /// A frame pointing at a single position starts and ends there.
+    pub fn to_json(&self) -> String {
+        let frames = self
+            .frames()
+            .map(|(stage, err)| {
+                let (start, end) = match err.line_col() {
+                    LineColLocation::Pos(pos) => (pos, pos),
+                    LineColLocation::Span(start, end) => (start, end),
+                };
+                let position = |(line, column)| JsonPosition { line, column };
+
+                JsonFrame {
+                    stage,
+                    line: start.0,
+                    column: start.1,
+                    start: position(start),
+                    end: position(end),
+                    message: err.message(),
+                    source: err.line(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::json!({
+            "severity": self.severity,
+            "file": self.file,
+            "frames": frames,
+        })
+        .to_string()
+    }
+}
+
+#[derive(Debug)]
+struct Frame {
+    stage: Stage,
+    err: Box<dyn TraceError>,
+    /// Only notes where the deeper frames happened, see [`Trace::push_context`].
+    context: bool,
+}
+
+impl Frame {
+    fn new<T: TraceError + 'static>(stage: Stage, err: T) -> Self {
+        Frame {
+            stage,
+            err: Box::new(err),
+            context: false,
+        }
+    }
+}
+
+fn same_frame(frame: &Frame, other: &Frame) -> bool {
+    frame.stage == other.stage
+        && frame.err.line_col() == other.err.line_col()
+        && frame.err.message() == other.err.message()
+}
+
+/// Colored unless turned off with [`set_color`](crate::highlight::set_color).
 impl std::fmt::Display for Trace {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "Deepest error first\n{}",
-            self.stack
-                .iter()
-                .map(|(stage, err)| {
-                    let line_nbr = match err.line_col() {
-                        LineColLocation::Pos((y, _)) => y,
-                        LineColLocation::Span((ys, _), _) => ys,
-                    };
-
-                    let line_nbr_len = line_nbr.to_string().len();
-
-                    let padding = " ".repeat(line_nbr_len);
-
-                    let arrow = format!("{}>", "-".repeat(line_nbr_len));
-
-                    let coords = match err.line_col() {
-                        LineColLocation::Pos((y, x)) => format!("{y}:{x}"),
-                        LineColLocation::Span((ys, xs), (ye, xe)) => {
-                            format!("{ys}:{xs} -> {ye}:{xe}")
-                        }
-                    };
-
-                    let pipe = "\x1b[2;37m|\x1b[0m";
-
-                    // ---> STAGE | COORDS
-                    //    |
-                    // NBR| LINE
-                    //    |
-                    //    = ERROR
-                    format!(
-                        "{arrow} \x1b[33m{stage:?}\x1b[0m {pipe} \x1b[34m{coords}\x1b[0m\n\
+        f.write_str(&self.to_string_colored(color()))
+    }
+}
+
+impl std::error::Error for Trace {}
+
+/// Trace rendered with its frames in the given order, see [`Trace::display_order`].
+pub struct OrderedTrace<'a> {
+    trace: &'a Trace,
+    order: TraceOrder,
+}
+
+impl std::fmt::Display for OrderedTrace<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.trace.render(color(), self.order))
+    }
+}
+
+impl Trace {
+    /// Renders the frames in `order`, the stack itself being left as is.
+    pub fn display_order(&self, order: TraceOrder) -> OrderedTrace<'_> {
+        OrderedTrace { trace: self, order }
+    }
+
+    /// Consecutive duplicate frames are only rendered once, see `Trace::coalesce`.
+    pub fn to_string_colored(&self, color: bool) -> String {
+        self.render(color, TraceOrder::DeepestFirst)
+    }
+
+    fn render(&self, color: bool, order: TraceOrder) -> String {
+        let mut frames = self
+            .stack
+            .iter()
+            .enumerate()
+            .filter(|(index, frame)| *index == 0 || !same_frame(&self.stack[index - 1], frame))
+            .map(|(_, Frame { stage, err, .. })| {
+                let ((ys, xs), (ye, xe)) = match err.line_col() {
+                    LineColLocation::Pos(pos) => (pos, pos),
+                    LineColLocation::Span(start, end) => (start, end),
+                };
+
+                let lines = err
+                    .context()
+                    .split('\n')
+                    .map(str::trim_end)
+                    .collect::<Vec<_>>();
+                let last_nbr = ys + lines.len() - 1;
+
+                let line_nbr_len = last_nbr.to_string().len();
+
+                let padding = " ".repeat(line_nbr_len);
+
+                let arrow = format!("{}>", "-".repeat(line_nbr_len));
+
+                // Frames may come from another file than the trace, e.g. a used module
+                let file = err
+                    .file()
+                    .or(self.file.as_deref())
+                    .map(|file| format!("{file}:"))
+                    .unwrap_or_default();
+
+                let coords = match err.line_col() {
+                    LineColLocation::Pos((y, x)) => format!("{file}{y}:{x}"),
+                    LineColLocation::Span((ys, xs), (ye, xe)) => {
+                        format!("{file}{ys}:{xs} -> {ye}:{xe}")
+                    }
+                };
+
+                let pipe = paint("2;37", "|", color);
+
+                // Under the span on its last line, from its start or that line's indentation
+                let underline = lines
+                    .last()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| {
+                        let from = match lines.len() {
+                            1 => xs.max(1),
+                            _ => line.chars().take_while(|c| c.is_whitespace()).count() + 1,
+                        };
+                        let to = match ye == last_nbr {
+                            true => xe,
+                            false => line.chars().count() + 1,
+                        };
+
+                        format!(
+                            "{padding}{pipe} {}{}",
+                            " ".repeat(from - 1),
+                            paint("31", &"^".repeat(to.saturating_sub(from).max(1)), color)
+                        )
+                    })
+                    .unwrap_or_else(|| format!("{padding}{pipe}"));
+
+                // ---> STAGE | COORDS
+                //    |
+                // NBR| LINE
+                //    | ^^^^
+                //    = ERROR
+                format!(
+                    "{arrow} {} {pipe} {}\n\
                          {padding}{pipe}\n\
                          {}\n\
-                         {padding}{pipe}\n\
+                         {underline}\n\
                          {padding}= {}\n",
-                        // Line number and line
-                        err.line()
-                            .split('\n')
-                            .enumerate()
-                            // line.trim().is_empty()
-                            .map(|(index, line)| if !line.trim().is_empty() {
-                                format!(
-                                    "\x1b[2;37m{}\x1b[0m{pipe} {}",
-                                    line_nbr + index,
-                                    highlight_aysinvi(line.trim_end())
-                                )
-                            } else {
-                                "".to_owned()
-                            })
-                            .filter(|line| !line.is_empty())
-                            .collect::<Vec<String>>()
-                            .join("\n"),
-                        // Error
-                        err.message()
-                    )
-                })
-                .collect::<String>(),
+                    paint("33", &format!("{stage:?}"), color),
+                    paint("34", &coords, color),
+                    // Line numbers and lines
+                    lines
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, line)| !line.trim().is_empty())
+                        .map(|(index, line)| {
+                            format!(
+                                "{}{pipe} {}",
+                                paint("2;37", &format!("{:>line_nbr_len$}", ys + index), color),
+                                highlight_with(line, color)
+                            )
+                        })
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                    // Error
+                    err.message()
+                )
+            })
+            .collect::<Vec<String>>();
+
+        if order == TraceOrder::ShallowestFirst {
+            frames.reverse();
+        }
+
+        format!(
+            "{}\n{}",
+            match (self.severity, order) {
+                (Severity::Error, TraceOrder::DeepestFirst) => "Deepest error first",
+                (Severity::Error, TraceOrder::ShallowestFirst) => "Shallowest error first",
+                (Severity::Warning, TraceOrder::DeepestFirst) => "Warning, deepest frame first",
+                (Severity::Warning, TraceOrder::ShallowestFirst) => {
+                    "Warning, shallowest frame first"
+                }
+            },
+            frames.concat()
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn boxed_error() {
+        fn bind(source: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            let ast = crate::ast::parsing::parse(crate::ast::lib::SourceCode::Content(
+                source.to_owned(),
+            ))?;
+            crate::ast::binding::convert(&ast)?;
+            Ok(())
+        }
+
+        bind("ngop 'u a alu 1.").unwrap();
+
+        let err = bind("ke a.").unwrap_err();
+        assert!(err.to_string().contains("Undefined variable: 'a'"));
+
+        let trace = err.downcast::<Trace>().unwrap();
+        assert!(matches!(trace.frames().next(), Some((Stage::Binding, _))));
+    }
+
+    #[test]
+    fn plain_rendering() {
+        let trace = crate::ast::parsing::parse(crate::ast::lib::SourceCode::Content(
+            "ngop 'u a alu.".to_owned(),
+        ))
+        .unwrap_err()
+        .in_file("a.ay");
+
+        let plain = trace.to_string_colored(false);
+
+        assert!(!plain.contains('\x1b'));
+        assert!(plain.contains("-> Parsing | a.ay:1:"));
+        assert!(plain.contains("1| ngop 'u a alu."));
+        assert!(trace.to_string_colored(true).contains('\x1b'));
+    }
+
+    #[test]
+    fn file_names() {
+        let input = "ngop 'u a alu b.\nrey fa a.";
+        let span = |start, end, file: Option<&str>| {
+            let mut span = Span::from(pest::Span::new(input, start, end).unwrap());
+            if let Some(file) = file {
+                span.set_file(file);
+            }
+            span
+        };
+
+        // Later stages tell the file through the spans of their errors
+        let trace = Trace::new(
+            Stage::Binding,
+            Error::from_span(span(14, 15, Some("a.ay")), "Unbound"),
+        );
+        assert_eq!(trace.file(), Some("a.ay"));
+        assert!(trace
+            .to_string_colored(false)
+            .contains("-> Binding | a.ay:1:15 -> 1:16"));
+
+        // Frames pushed onto a trace that does not know its file yet, or from another file
+        let mut trace = Trace::new(Stage::Compiling, Error::new("Host error"));
+        trace.push(
+            Stage::Compiling,
+            Error::from_span(span(17, 25, Some("b.ay")), "Call"),
+        );
+        trace.push(
+            Stage::Compiling,
+            Error::from_span(span(0, 4, None), "Declaration"),
+        );
+        trace.push(
+            Stage::Compiling,
+            Error::from_span(span(0, 4, Some("c.ay")), "Module"),
+        );
+
+        let plain = trace.to_string_colored(false);
+        assert_eq!(trace.file(), Some("b.ay"));
+        assert!(plain.contains("| b.ay:2:1 -> 2:9\n"));
+        assert!(plain.contains("| b.ay:1:1 -> 1:5\n"));
+        assert!(plain.contains("| c.ay:1:1 -> 1:5\n"));
+    }
+
+    #[test]
+    fn display_order() {
+        let input = "ngop 'u a alu 1.\nrey fa a.\nngop lì'ukìng rey fa b alu b txew.";
+        let span = |start, end| Span::from(pest::Span::new(input, start, end).unwrap());
+
+        let mut trace = Trace::new(Stage::Typing, Error::from_span(span(5, 15), "Deepest"));
+        trace.push(Stage::Binding, Error::from_span(span(17, 25), "Middle"));
+        trace.push(Stage::Binding, Error::from_span(span(27, 61), "Shallowest"));
+
+        let frames = |order| {
+            let rendered = trace.render(false, order);
+            let (header, body) = rendered.split_once('\n').unwrap();
+            let mut frames = Vec::<String>::new();
+
+            body.split_inclusive('\n')
+                .for_each(|line| match line.starts_with('-') {
+                    true => frames.push(line.to_owned()),
+                    false => frames.last_mut().unwrap().push_str(line),
+                });
+
+            (header.to_owned(), frames)
+        };
+
+        let (deepest_header, deepest) = frames(TraceOrder::DeepestFirst);
+        let (shallowest_header, mut shallowest) = frames(TraceOrder::ShallowestFirst);
+
+        assert_eq!(deepest_header, "Deepest error first");
+        assert_eq!(shallowest_header, "Shallowest error first");
+        assert_eq!(deepest.len(), 3);
+        assert!(deepest[0].starts_with("-> Typing | 1:6 -> 1:16\n"));
+        assert!(deepest[2].ends_with("= Shallowest\n"));
+
+        shallowest.reverse();
+        assert_eq!(shallowest, deepest);
+        assert_eq!(
+            trace.render(false, TraceOrder::DeepestFirst),
+            trace.to_string_colored(false)
+        );
+    }
+
+    #[test]
+    fn multiline_rendering() {
+        let source = format!(
+            "{}ngop 'u a alu 1.\nnìngay 1\n  lu\n      2.",
+            "\n".repeat(7)
+        );
+        let ast = crate::ast::parsing::parse(crate::ast::lib::SourceCode::Content(source)).unwrap();
+        let trace = crate::ast::binding::convert(&ast).unwrap_err();
+
+        let plain = trace.to_string_colored(false);
+
+        // Every line of the span, numbers aligned, the last one underlined
+        assert!(
+            plain.contains(
+                "-> Binding | 9:8 -> 11:8\n  \
+                 |\n \
+                 9| nìngay 1\n\
+                 10|   lu\n\
+                 11|       2.\n  \
+                 |       ^\n  \
+                 = "
+            ),
+            "{plain}"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json() {
+        let path = "./examples/features/statements/invalid/var_dec_count.ay";
+        let trace = crate::ast::parsing::parse(crate::ast::lib::SourceCode::File(path.to_owned()))
+            .unwrap_err();
+
+        let json = serde_json::from_str::<serde_json::Value>(&trace.to_json()).unwrap();
+
+        assert_eq!(json["severity"], "Error");
+        assert!(json["file"].as_str().unwrap().ends_with("var_dec_count.ay"));
+
+        let frame = &json["frames"][0];
+        assert_eq!(frame["stage"], "Parsing");
+        assert_eq!(frame["message"], "3 names but 2 values");
+        assert_eq!(frame["source"], "c");
+        assert_eq!((&frame["line"], &frame["column"]), (&1.into(), &19.into()));
+        assert_eq!(
+            frame["start"],
+            serde_json::json!({ "line": 1, "column": 19 })
+        );
+        assert_eq!(frame["end"], serde_json::json!({ "line": 1, "column": 20 }));
+
+        // Single positions start and end at the same place
+        let trace = Trace::new(
+            Stage::Binding,
+            Error::from_span(
+                crate::error::span::Span::from(pest::Span::new("a", 0, 0).unwrap()),
+                "Empty",
+            ),
+        );
+        let json = serde_json::from_str::<serde_json::Value>(&trace.to_json()).unwrap();
+        assert_eq!(json["frames"][0]["start"], json["frames"][0]["end"]);
+    }
+
+    #[test]
+    fn duplicate_frames() {
+        let frame = || Error::new("Unexpected `tsakrr`");
+
+        let mut trace = Trace::new(Stage::Parsing, frame());
+        trace.push(Stage::Parsing, frame());
+        trace.push(Stage::Binding, frame());
+
+        assert_eq!(trace.to_string().matches("Unexpected `tsakrr`").count(), 2);
+
+        trace.coalesce();
+
+        assert_eq!(
+            trace.frames().map(|(stage, _)| stage).collect::<Vec<_>>(),
+            vec![Stage::Parsing, Stage::Binding]
+        );
+    }
+}
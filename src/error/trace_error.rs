@@ -1,6 +1,6 @@
 use super::{span::Span, trace::TraceError};
 
-use crate::parsing::Rule;
+use crate::ast::parsing::Rule;
 
 use pest::error::LineColLocation;
 
@@ -8,7 +8,33 @@ use pest::error::LineColLocation;
 pub struct Error {
     line_col: LineColLocation,
     line: String,
+    context: String,
     message: String,
+    file: Option<String>,
+}
+
+impl Error {
+    /// Error that cannot be traced back to the source, e.g. coming from the host program.
+    pub fn new(message: &str) -> Self {
+        Self {
+            line_col: LineColLocation::Pos((0, 0)),
+            line: String::new(),
+            context: String::new(),
+            message: message.to_owned(),
+            file: None,
+        }
+    }
+
+    /// Copy of any error, made through what it exposes as a [`TraceError`].
+    pub fn copy_of(err: &dyn TraceError) -> Self {
+        Self {
+            line_col: err.line_col(),
+            line: err.line().to_owned(),
+            context: err.context().to_owned(),
+            message: err.message().to_owned(),
+            file: err.file().map(str::to_owned),
+        }
+    }
 }
 
 impl TraceError for Error {
@@ -19,7 +45,9 @@ impl TraceError for Error {
         Self {
             line_col: span.line_col().clone(),
             line: span.as_str().to_owned(),
+            context: span.context().to_owned(),
             message: message.to_owned(),
+            file: span.file().map(str::to_owned),
         }
     }
 
@@ -31,9 +59,17 @@ impl TraceError for Error {
         self.line.as_ref()
     }
 
+    fn context(&self) -> &str {
+        self.context.as_ref()
+    }
+
     fn message(&self) -> &str {
         self.message.as_ref()
     }
+
+    fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
 }
 
 impl From<pest::error::Error<Rule>> for Error {
@@ -41,7 +77,9 @@ impl From<pest::error::Error<Rule>> for Error {
         Self {
             line_col: err.line_col.clone(),
             line: err.line().to_owned(),
+            context: err.line().to_owned(),
             message: err.variant.message().to_string(),
+            file: None,
         }
     }
 }
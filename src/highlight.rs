@@ -1,4 +1,37 @@
-use std::collections::HashMap;
+use crate::ast::lib::number_word;
+
+use std::{
+    collections::HashMap,
+    io::IsTerminal,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Whether output gets ANSI escape codes, see [`set_color`].
+static COLOR: AtomicBool = AtomicBool::new(true);
+
+/// Turns colors on or off for everything rendered afterwards, traces included.
+pub fn set_color(enabled: bool) {
+    COLOR.store(enabled, Ordering::Relaxed);
+}
+
+pub fn color() -> bool {
+    COLOR.load(Ordering::Relaxed)
+}
+
+/// Whether colors are wanted by default: `NO_COLOR` is not set and the output is a terminal.
+pub fn detect_color() -> bool {
+    std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+        && std::io::stdout().is_terminal()
+        && std::io::stderr().is_terminal()
+}
+
+/// `text` in the ANSI style `code`, e.g. `1;33` for bold yellow, or left as is without `color`.
+pub fn paint(code: &str, text: &str, color: bool) -> String {
+    match color {
+        true => format!("\x1b[{code}m{text}\x1b[0m"),
+        false => text.to_owned(),
+    }
+}
 
 macro_rules! init_map {
     ($($($keyword:literal)|* => $type:literal ; $code:literal),* $(,)?) => {
@@ -25,23 +58,34 @@ enum ElemType {
     Word,
 }
 
+/// Colors the keywords and literals of `source`, if colors are on.
 pub fn highlight_aysinvi(source: &str) -> String {
+    highlight_with(source, color())
+}
+
+pub fn highlight_with(source: &str, color: bool) -> String {
+    if !color {
+        return source.to_owned();
+    }
+
     let mapping = init_map!(
          "ngop"
         | "'u" | "meu" | "pxeu" | "ayu"
-        | "alu" | "txew" => 0;33,
+        | "alu" | "txew" | "tsengit" => 0;33,
 
         "lì'ukìng" => 1;33,
 
          "san" | "sìk" | "ke"
         | "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9"
-        | "melo" | "pxelo"
-        | "teng" => 0;31,
+        | "'awlo" | "melo" | "pxelo" | "tsìlo" | "mrrlo" | "puxlo" | "kinglo"
+        | "teng" | "hol" | "pxay"
+        | "kewu" | "srane" | "kehe" => 0;31,
 
-        "fa" | "si" | "livu" => 0;32,
+        "fa" | "si" | "livu" | "lu" | "srak" | "fu" => 0;32,
 
-         "txo" | "tsakrr" | "txokefyaw"
-        | "leyn" | "vaykrr" | "ftang" => 0;35,
+         "txo" | "tsakrr" | "txokefyaw" | "pe"
+        | "leyn" | "vaykrr" | "ftang" | "tstxo" | "hum" | "kä"
+        | "nìngay" => 0;35,
 
         "sì" | "ulte" => 0;36,
     );
@@ -57,12 +101,14 @@ pub fn highlight_aysinvi(source: &str) -> String {
                 }
 
                 let res = if !in_string {
-                    mapping.get(*word).map_or_else(
-                        || format!("\x1b[1;34m{word}\x1b[0m"),
-                        |format| format!("\x1b[{format}m{word}\x1b[0m"),
-                    )
+                    let code = match mapping.get(*word) {
+                        Some(code) => code.as_str(),
+                        None if number_word(word).is_some() => "0;31",
+                        None => "1;34",
+                    };
+                    paint(code, word, true)
                 } else {
-                    format!("\x1b[0;31m{word}\x1b[0m")
+                    paint("0;31", word, true)
                 };
 
                 if *word == "sìk" {
@@ -0,0 +1,133 @@
+use super::value::Value;
+
+use crate::ast::lib::{Base, BinaryOperator, ComparisonOperator};
+
+use std::{io::Write, str::FromStr};
+
+use strum::IntoEnumIterator;
+
+/// Functions available without being declared, operators excluded.
+pub const NAMES: [&str; 3] = ["wìntxu", "sung", "'ì'awnyu"];
+
+/// Arithmetic operators usable as functions, such as `(+)`.
+pub const OPERATORS: [BinaryOperator; 4] = [
+    BinaryOperator::Add,
+    BinaryOperator::Subtract,
+    BinaryOperator::Multiply,
+    BinaryOperator::Divide,
+];
+
+/// Every function available without being declared.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    NAMES
+        .into_iter()
+        .chain(ComparisonOperator::iter().map(<&str>::from))
+        .chain(OPERATORS.into_iter().map(<&str>::from))
+}
+
+/// Arithmetic operator called `name`, if any.
+pub fn operator(name: &str) -> Option<BinaryOperator> {
+    OPERATORS
+        .into_iter()
+        .find(|operator| <&str>::from(operator) == name)
+}
+
+/// Calls the builtin `name`, `None` if there is no such builtin.
+/// Numbers written to `out` are rendered in `base`, non-fatal issues are pushed onto `warnings`.
+pub fn call(
+    name: &str,
+    args: Vec<Value>,
+    out: &mut impl Write,
+    base: Base,
+    warnings: &mut Vec<String>,
+) -> Option<Result<Value, String>> {
+    match name {
+        "wìntxu" => Some(print(args, out, base)),
+        "sung" => Some(add(args, warnings)),
+        "'ì'awnyu" => Some(remainder(args)),
+        _ => match (ComparisonOperator::from_str(name), operator(name)) {
+            (Ok(comparison), _) => Some(
+                operands(name, args).and_then(|[left, right]| left.compare(&comparison, &right)),
+            ),
+            (_, Some(operator)) => {
+                Some(operands(name, args).and_then(|[left, right]| left.apply(operator, &right)))
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Both operands of the operator `name`.
+fn operands(name: &str, args: Vec<Value>) -> Result<[Value; 2], String> {
+    <[Value; 2]>::try_from(args)
+        .map_err(|args| format!("'{name}' expects 2 arguments, found {}", args.len()))
+}
+
+/// Writes its arguments separated by spaces, followed by a newline.
+fn print(args: Vec<Value>, out: &mut impl Write, base: Base) -> Result<Value, String> {
+    writeln!(
+        out,
+        "{}",
+        args.iter()
+            .map(|arg| arg.display_in(base).to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    )
+    .map_err(|err| format!("Cannot print: {err}"))?;
+
+    Ok(Value::Unit)
+}
+
+/// Sums numbers of a single type, the way `+` would.
+fn add(args: Vec<Value>, warnings: &mut Vec<String>) -> Result<Value, String> {
+    let mut args = args.into_iter();
+
+    match args.next() {
+        Some(first @ (Value::Number(_) | Value::Float(_))) => args.try_fold(first, |acc, arg| {
+            let sum = acc.apply(BinaryOperator::Add, &arg)?;
+            warnings.extend(non_finite(&acc, BinaryOperator::Add, &arg, &sum));
+
+            Ok(sum)
+        }),
+        Some(first) => Err(format!("Cannot add {} value", first.type_name())),
+        None => Ok(Value::Number(0)),
+    }
+}
+
+/// Warning for Float arithmetic on finite operands giving an infinity or NaN, which is carried on
+/// with rather than reported as an error.
+pub fn non_finite(
+    left: &Value,
+    operator: BinaryOperator,
+    right: &Value,
+    result: &Value,
+) -> Option<String> {
+    match (left, right, result) {
+        (Value::Float(left), Value::Float(right), Value::Float(result))
+            if left.is_finite() && right.is_finite() && !result.is_finite() =>
+        {
+            Some(format!(
+                "`{left:?} {} {right:?}` gives {result}, not a finite Float",
+                <&str>::from(operator)
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn remainder(args: Vec<Value>) -> Result<Value, String> {
+    match args.as_slice() {
+        [Value::Number(_), Value::Number(0)] => Err("Division by zero".to_owned()),
+        [Value::Number(left), Value::Number(right)] => left
+            .checked_rem(*right)
+            .map(Value::Number)
+            .ok_or_else(|| "Remainder overflowed".to_owned()),
+        _ => Err(format!(
+            "Expected two Number arguments, found ({})",
+            args.iter()
+                .map(Value::type_name)
+                .collect::<Vec<&str>>()
+                .join(", ")
+        )),
+    }
+}
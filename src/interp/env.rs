@@ -0,0 +1,71 @@
+use crate::{ast::binding::FunDec, interp::value::Value};
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+/// Scope of a running program, linked to the one enclosing it in the source.
+///
+/// Function bodies run in a child of the scope their function is declared in rather than of the
+/// caller's, so that they see the same names as binding did.
+#[derive(Default)]
+pub(crate) struct Env {
+    vars: RefCell<HashMap<String, Value>>,
+    funs: RefCell<HashMap<String, Rc<FunDec>>>,
+    parent: Option<Rc<Env>>,
+}
+
+impl Env {
+    pub fn child(parent: &Rc<Env>) -> Rc<Env> {
+        Rc::new(Env {
+            parent: Some(parent.clone()),
+            ..Env::default()
+        })
+    }
+
+    pub fn define_var(&self, name: String, value: Value) {
+        self.vars.borrow_mut().insert(name, value);
+    }
+
+    pub fn define_fun(&self, dec: Rc<FunDec>) {
+        self.funs.borrow_mut().insert(dec.name.clone(), dec);
+    }
+
+    /// Value of the innermost variable called `name`.
+    pub fn var(&self, name: &str) -> Option<Value> {
+        match self.vars.borrow().get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.parent.as_ref()?.var(name),
+        }
+    }
+
+    /// Replaces the value of the innermost variable called `name`, returning whether there is one.
+    pub fn set_var(&self, name: &str, value: Value) -> bool {
+        match self.vars.borrow_mut().get_mut(name) {
+            Some(var) => {
+                *var = value;
+                true
+            }
+            None => self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.set_var(name, value)),
+        }
+    }
+
+    /// Innermost function called `name`, along with the scope it is declared in.
+    pub fn fun(self: &Rc<Self>, name: &str) -> Option<(Rc<FunDec>, Rc<Env>)> {
+        match self.funs.borrow().get(name) {
+            Some(dec) => Some((dec.clone(), self.clone())),
+            None => self.parent.as_ref()?.fun(name),
+        }
+    }
+}
+
+/// Variables may hold calls deferred in the very scope, which would be printed forever.
+impl std::fmt::Debug for Env {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Env")
+            .field("vars", &self.vars.borrow().keys().collect::<Vec<_>>())
+            .field("funs", &self.funs.borrow().keys().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,877 @@
+//! Tree-walking interpreter over the bound AST.
+//!
+//! The tense of a call decides when it runs:
+//! - present calls run right away;
+//! - imminent calls run once the statement making them is done, unless their value is needed
+//!   sooner;
+//! - future calls only run when their value is needed.
+//!
+//! Imminent and future calls evaluate their arguments right away and give a [`Value::Deferred`]
+//! standing for their result. It can be stored in variables and passed to declared functions as
+//! is. Anything else needs the actual value, so it forces the call, which runs at most once
+//! however many copies of the value there are. A program's result is forced as well.
+//!
+//! Function bodies run in the scope their function is declared in, whoever calls them. A deferred
+//! call keeps the function it resolved to when it was made, and with it that scope, so forcing it
+//! somewhere else does not change what it sees.
+
+pub mod builtins;
+mod env;
+pub mod value;
+
+use crate::{
+    ast::{
+        binding::{self, Expr, Statement, Tense, VarDec},
+        lib::{AyNode, Base, BinaryOperator},
+        parsing,
+    },
+    error::{
+        span::Span,
+        trace::{Severity, Stage, Trace, TraceError},
+        trace_error::Error,
+    },
+    options::CompilerOptions,
+};
+
+use self::{
+    env::Env,
+    value::{Call, Deferred, Thunk, Value},
+};
+
+use std::{io::Write, rc::Rc};
+
+/// Pending `hum` or `kä`, unwinding statements up to the loop it targets.
+#[derive(Clone, Debug, PartialEq)]
+enum Signal {
+    Break(Option<String>),
+    Continue(Option<String>),
+}
+
+/// Tree-walking interpreter over the bound AST, writing the program's output to `out`.
+pub struct Interpreter<W: Write> {
+    out: W,
+    /// Innermost scope of what is running.
+    env: Rc<Env>,
+    signal: Option<Signal>,
+    /// Imminent calls waiting for the statements that made them to be done, innermost last.
+    imminent: Vec<Deferred>,
+    /// Whether executed statements and function calls get written to `out` as well.
+    tracing: bool,
+    /// Base numbers are written to `out` in.
+    base: Base,
+    /// Non-fatal issues met while running, see `eval`.
+    warnings: Vec<Trace>,
+}
+
+impl<W: Write> Interpreter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            env: Rc::default(),
+            signal: None,
+            imminent: vec![],
+            tracing: false,
+            base: Base::Decimal,
+            warnings: vec![],
+        }
+    }
+
+    /// Writes every statement before running it, and every function call and result.
+    pub fn with_tracing(mut self, tracing: bool) -> Self {
+        self.tracing = tracing;
+        self
+    }
+
+    pub fn with_base(mut self, base: Base) -> Self {
+        self.base = base;
+        self
+    }
+
+    pub fn into_output(self) -> W {
+        self.out
+    }
+
+    pub fn output(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    /// Runs a program, returning the warnings raised while doing so alongside its result.
+    pub fn eval(&mut self, ast: &[AyNode<Statement>]) -> (Result<Value, Trace>, Vec<Trace>) {
+        let res = self.run(ast).and_then(|value| self.force(value));
+        (res, std::mem::take(&mut self.warnings))
+    }
+
+    /// Runs the statements in the current scope, returning the value of the last one.
+    pub fn run(&mut self, stmts: &[AyNode<Statement>]) -> Result<Value, Trace> {
+        let mut res = Value::Unit;
+
+        // Callable before their declaration, as binding allows
+        stmts.iter().for_each(|stmt| {
+            if let Statement::FunDec(dec) = &stmt.inner {
+                self.env.define_fun(dec.clone());
+            }
+        });
+
+        for stmt in stmts {
+            if self.signal.is_some() {
+                break;
+            }
+
+            res = self.statement(stmt)?;
+        }
+
+        Ok(res)
+    }
+
+    /// Runs the statements in a new scope.
+    fn block(&mut self, stmts: &[AyNode<Statement>]) -> Result<Value, Trace> {
+        let scope = Env::child(&self.env);
+        let parent = std::mem::replace(&mut self.env, scope);
+        let res = self.run(stmts);
+        self.env = parent;
+
+        res
+    }
+
+    /// Runs the statement, then the imminent calls it made.
+    fn statement(&mut self, stmt: &AyNode<Statement>) -> Result<Value, Trace> {
+        let (line, col) = stmt.span.start();
+        self.trace(&stmt.span, |_| {
+            format!(
+                "[{line}:{col}] {}",
+                stmt.span.as_str().lines().next().unwrap_or_default().trim()
+            )
+        })?;
+
+        let mark = self.imminent.len();
+        let res = self.execute(stmt);
+        let imminent = self.imminent.split_off(mark);
+
+        let value = res?;
+        for deferred in imminent {
+            self.force(Value::Deferred(deferred))?;
+        }
+
+        Ok(value)
+    }
+
+    fn execute(&mut self, AyNode { span, inner }: &AyNode<Statement>) -> Result<Value, Trace> {
+        match inner {
+            Statement::FunDec(dec) => {
+                self.env.define_fun(dec.clone());
+                Ok(Value::Unit)
+            }
+            Statement::VarDec(VarDec { names, values }) => {
+                let values = self.lazy_exprs(values)?;
+
+                names
+                    .iter()
+                    .zip(values)
+                    .for_each(|(name, value)| self.env.define_var(name.clone(), value));
+
+                Ok(Value::Unit)
+            }
+            Statement::Assign(VarDec { names, values }) => {
+                let values = self.lazy_exprs(values)?;
+
+                for (name, value) in names.iter().zip(values) {
+                    if !self.env.set_var(name, value) {
+                        return Err(error(span, &format!("Unbound variable: '{name}'")));
+                    }
+                }
+
+                Ok(Value::Unit)
+            }
+            Statement::Expr(expr) => self.lazy_expr(expr),
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                if self.condition(cond)? {
+                    self.block(then)
+                } else {
+                    self.block(otherwise)
+                }
+            }
+            Statement::Loop { cond, body, label } => {
+                while match cond {
+                    Some(cond) => self.condition(cond)?,
+                    None => true,
+                } {
+                    self.block(body)?;
+
+                    match self.signal.take() {
+                        None => {}
+                        Some(Signal::Break(target)) if target.is_none() || target == *label => {
+                            break
+                        }
+                        Some(Signal::Continue(target)) if target.is_none() || target == *label => {}
+                        // Targets an outer loop
+                        signal => {
+                            self.signal = signal;
+                            break;
+                        }
+                    }
+                }
+
+                Ok(Value::Unit)
+            }
+            Statement::Break(label) => {
+                self.signal = Some(Signal::Break(label.clone()));
+                Ok(Value::Unit)
+            }
+            Statement::Continue(label) => {
+                self.signal = Some(Signal::Continue(label.clone()));
+                Ok(Value::Unit)
+            }
+            Statement::Assert(cond) => {
+                if self.condition(cond)? {
+                    Ok(Value::Unit)
+                } else {
+                    Err(error(&cond.span, "Assertion failed"))
+                }
+            }
+        }
+    }
+
+    /// `kewu` has to be checked for explicitly rather than being silently falsy.
+    fn condition(&mut self, cond: &AyNode<Expr>) -> Result<bool, Trace> {
+        match self.expr(cond)? {
+            Value::None => Err(error(
+                &cond.span,
+                "Condition is kewu, check it with `srak` or give it a default with `fu`",
+            )),
+            value => Ok(value.is_truthy()),
+        }
+    }
+
+    fn exprs(&mut self, exprs: &[AyNode<Expr>]) -> Result<Vec<Value>, Trace> {
+        exprs.iter().map(|expr| self.expr(expr)).collect()
+    }
+
+    fn lazy_exprs(&mut self, exprs: &[AyNode<Expr>]) -> Result<Vec<Value>, Trace> {
+        exprs.iter().map(|expr| self.lazy_expr(expr)).collect()
+    }
+
+    /// Value of `node`, calls in the imminent or future tense being left to be forced later.
+    fn lazy_expr(&mut self, node: &AyNode<Expr>) -> Result<Value, Trace> {
+        let AyNode { span, inner } = node;
+
+        match inner {
+            Expr::FunCall { tense, name, args } => {
+                let name = self.callee(span, name)?;
+                let call = Call {
+                    span: span.clone(),
+                    dec: self.env.fun(&name),
+                    name,
+                    args: self.lazy_exprs(args)?,
+                };
+
+                match tense {
+                    Tense::Present => self.call(call),
+                    Tense::Imminent => {
+                        let deferred = Deferred::new(call);
+                        self.imminent.push(deferred.clone());
+                        Ok(Value::Deferred(deferred))
+                    }
+                    Tense::Future => Ok(Value::Deferred(Deferred::new(call))),
+                }
+            }
+            Expr::Var(name) => self
+                .env
+                .var(name)
+                .ok_or_else(|| error(span, &format!("Unbound variable: '{name}'"))),
+            _ => self.expr(node),
+        }
+    }
+
+    /// Name of the function called as `name`, that of the builtin it holds for a variable.
+    fn callee(&mut self, span: &Span, name: &str) -> Result<String, Trace> {
+        let var = match self.env.fun(name) {
+            Some(_) => None,
+            None => self.env.var(name),
+        };
+
+        match var.map(|value| self.force(value)).transpose()? {
+            None => Ok(name.to_owned()),
+            Some(Value::Function(builtin)) => Ok(builtin),
+            Some(value) => Err(error(
+                span,
+                &format!("Cannot call {} value", value.type_name()),
+            )),
+        }
+    }
+
+    /// Runs the call `value` stands for if it is deferred and did not run yet.
+    fn force(&mut self, value: Value) -> Result<Value, Trace> {
+        let Value::Deferred(Deferred(thunk)) = value else {
+            return Ok(value);
+        };
+
+        let running = match &*thunk.borrow() {
+            Thunk::Pending(call) => Thunk::Running {
+                span: call.span.clone(),
+                name: call.name.clone(),
+            },
+            Thunk::Running { span, name } => {
+                return Err(error(
+                    span,
+                    &format!("Result of '{name}' is needed to compute itself"),
+                ))
+            }
+            Thunk::Done(value) => return Ok(value.clone()),
+        };
+
+        let Thunk::Pending(call) = thunk.replace(running) else {
+            unreachable!()
+        };
+
+        let value = self.call(call).and_then(|value| self.force(value))?;
+        thunk.replace(Thunk::Done(value.clone()));
+
+        Ok(value)
+    }
+
+    fn expr(&mut self, node: &AyNode<Expr>) -> Result<Value, Trace> {
+        let AyNode { span, inner } = node;
+
+        match inner {
+            Expr::FunCall { .. } | Expr::Var(_) => {
+                let value = self.lazy_expr(node)?;
+                self.force(value)
+            }
+            Expr::Array { items } => Ok(Value::Array(self.exprs(items)?)),
+            Expr::Comparison {
+                left,
+                right,
+                operator,
+            } => {
+                let (left, right) = (self.expr(left)?, self.expr(right)?);
+                left.compare(operator, &right)
+                    .map_err(|message| error(span, &message))
+            }
+            Expr::BinOp { op, left, right } => {
+                let left = self.expr(left)?;
+                // The right operand is left alone when the left one decides the result
+                match (op, &left) {
+                    (BinaryOperator::Or, Value::Bool(true)) => return Ok(left),
+                    (BinaryOperator::And, Value::Bool(false)) => return Ok(left),
+                    _ => {}
+                }
+
+                let right = self.expr(right)?;
+                let res = left
+                    .apply(*op, &right)
+                    .map_err(|message| error(span, &message))?;
+
+                if let Some(message) = builtins::non_finite(&left, *op, &right, &res) {
+                    self.warnings
+                        .push(error(span, &message).with_severity(Severity::Warning));
+                }
+
+                Ok(res)
+            }
+            Expr::Number(number) => Ok(Value::Number(*number)),
+            Expr::Float(number) => Ok(Value::Float(*number)),
+            Expr::String(string) => Ok(Value::String(string.clone())),
+            Expr::Bool(value) => Ok(Value::Bool(*value)),
+            Expr::Negated(expr) => match self.expr(expr)? {
+                Value::Number(number) => number
+                    .checked_neg()
+                    .map(Value::Number)
+                    .ok_or_else(|| error(span, &format!("`ke {number}` overflowed"))),
+                Value::Float(number) => Ok(Value::Float(-number)),
+                Value::Bool(value) => Ok(Value::Bool(!value)),
+                Value::Array(items) => Ok(Value::Bool(items.is_empty())),
+                value => Err(error(
+                    span,
+                    &format!("Cannot negate {} value", value.type_name()),
+                )),
+            },
+            Expr::None => Ok(Value::None),
+            Expr::FunRef(name) => Ok(Value::Function(name.clone())),
+            Expr::HasValue(expr) => Ok(Value::Bool(self.expr(expr)? != Value::None)),
+            Expr::Fallback { value, fallback } => match self.expr(value)? {
+                Value::None => self.expr(fallback),
+                value => Ok(value),
+            },
+            Expr::Index { value, index } => match (self.expr(value)?, self.expr(index)?) {
+                (Value::Array(items), Value::Number(index)) => usize::try_from(index)
+                    .ok()
+                    .and_then(|index| items.get(index).cloned())
+                    .ok_or_else(|| {
+                        error(
+                            span,
+                            &format!("Index {index} out of bounds for {} items", items.len()),
+                        )
+                    }),
+                (value, index) => Err(error(
+                    span,
+                    &format!(
+                        "Cannot index {} value with {} value",
+                        value.type_name(),
+                        index.type_name()
+                    ),
+                )),
+            },
+            Expr::Field { value, name } => {
+                let value = self.expr(value)?;
+                Err(error(
+                    span,
+                    &format!("{} value has no field '{name}'", value.type_name()),
+                ))
+            }
+        }
+    }
+
+    /// Runs `call`, builtins being given forced arguments.
+    fn call(
+        &mut self,
+        Call {
+            span,
+            name,
+            dec,
+            args,
+        }: Call,
+    ) -> Result<Value, Trace> {
+        let (span, name) = (&span, name.as_str());
+
+        let Some((dec, env)) = dec else {
+            let args = args
+                .into_iter()
+                .map(|arg| self.force(arg))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut warnings = vec![];
+            let res = builtins::call(name, args, &mut self.out, self.base, &mut warnings)
+                .unwrap_or_else(|| Err(format!("Undefined function: '{name}'")))
+                .map_err(|message| error(span, &message));
+
+            self.warnings.extend(
+                warnings
+                    .iter()
+                    .map(|message| error(span, message).with_severity(Severity::Warning)),
+            );
+
+            return res;
+        };
+
+        if dec.args.len() != args.len() {
+            return Err(error(
+                span,
+                &format!(
+                    "'{name}' expects {} arguments, found {}",
+                    dec.args.len(),
+                    args.len()
+                ),
+            ));
+        }
+
+        self.trace(span, |base| {
+            format!(
+                "-> {name}({})",
+                args.iter()
+                    .map(|arg| arg.display_in(base).to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+        })?;
+
+        // The body sees the names around the declaration, not those around the call
+        let caller = std::mem::replace(&mut self.env, Env::child(&env));
+
+        dec.args
+            .iter()
+            .zip(args)
+            .for_each(|(arg, value)| self.env.define_var(arg.clone(), value));
+
+        let res = self.run(&dec.helpers).and_then(|_| self.run(&dec.body));
+
+        self.env = caller;
+
+        if let Ok(value) = &res {
+            self.trace(span, |base| {
+                format!("<- {name} = {}", value.display_in(base))
+            })?;
+        }
+
+        res
+    }
+
+    /// `line` is given the base to render numbers in.
+    fn trace(&mut self, span: &Span, line: impl FnOnce(Base) -> String) -> Result<(), Trace> {
+        if !self.tracing {
+            return Ok(());
+        }
+
+        writeln!(self.out, "{}", line(self.base))
+            .map_err(|err| error(span, &format!("Cannot trace: {err}")))
+    }
+}
+
+/// Evaluates a lone expression such as `sung fa 1 sì 2`, lighter than running a whole program.
+/// Whatever it prints goes to stdout.
+pub fn eval_expr(source: &str, options: &CompilerOptions) -> Result<Value, Trace> {
+    let expr = parsing::parse_expr(source, options.number_base_input)?;
+    let bound = binding::convert_standalone_expr(&expr)?;
+
+    Interpreter::new(std::io::stdout())
+        .with_base(options.number_base)
+        .expr(&bound)
+}
+
+fn error(span: &Span, message: &str) -> Trace {
+    Trace::new(Stage::Compiling, Error::from_span(span.clone(), message))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ast::{binding, iteration, lib::SourceCode, parsing};
+
+    use super::*;
+
+    fn run(source: &str) -> (Result<Value, Trace>, String) {
+        let ast = parsing::parse(SourceCode::Content(source.to_owned())).unwrap();
+        let bound = binding::convert(&ast).unwrap();
+
+        let mut interpreter = Interpreter::new(vec![]);
+        let res = interpreter.run(&bound);
+
+        (res, String::from_utf8(interpreter.into_output()).unwrap())
+    }
+
+    #[test]
+    fn function_result() {
+        let (res, output) = run("ngop lì'ukìng tsìng fa a alu\n\
+                 wìntxu fa a ulte\n\
+                 sung fa a sì a\n\
+             txew.\n\
+             tsìng fa 7.");
+
+        assert_eq!(res.unwrap(), Value::Number(14));
+        assert_eq!(output, "7\n");
+    }
+
+    #[test]
+    fn lexical_scoping() {
+        // The callee reads the `a` around its declaration, not the one of its caller
+        let (res, _) = run("ngop 'u a alu 1.\n\
+             ngop lì'ukìng tìng alu a txew.\n\
+             ngop lì'ukìng kan alu ngop 'u a alu 2 ulte tìng si txew.\n\
+             kan si.");
+        assert_eq!(res.unwrap(), Value::Number(1));
+
+        // Nor does it see the caller's locals or functions it could not see when bound
+        let (res, _) = run("ngop lì'ukìng tìng alu rey si txew.\n\
+             ngop lì'ukìng rey alu 1 txew.\n\
+             ngop lì'ukìng kan alu\n\
+                 ngop lì'ukìng rey alu 2 txew ulte\n\
+                 tìng si\n\
+             txew.\n\
+             kan si.");
+        assert_eq!(res.unwrap(), Value::Number(1));
+    }
+
+    #[test]
+    fn operator_as_function() {
+        let (res, output) = run("ngop 'u a alu 7.\nwìntxu fa teng fa a sì 7.\nteng fa a sì 1.");
+
+        assert_eq!(res.unwrap(), Value::Bool(false));
+        assert_eq!(output, "srane\n");
+    }
+
+    #[test]
+    fn higher_order() {
+        let source =
+            std::fs::read_to_string("./examples/features/functions/valid/higher_order.ay").unwrap();
+        let (res, _) = run(&source);
+        res.unwrap();
+
+        let (res, _) = run("ngop lì'ukìng apply fa f alu f fa 2 sì 3 txew.\napply fa (-).");
+        assert_eq!(res.unwrap(), Value::Number(-1));
+
+        let (res, _) = run("ngop 'u f alu 1.\nf fa 2.");
+        let trace = res.unwrap_err();
+        let (_, err) = trace.frames().next().unwrap();
+        assert_eq!(err.message(), "Cannot call Number value");
+    }
+
+    #[test]
+    fn where_clause() {
+        let source =
+            std::fs::read_to_string("./examples/features/functions/valid/where.ay").unwrap();
+        let (res, _) = run(&source);
+
+        assert_eq!(res.unwrap(), Value::Number(8));
+    }
+
+    #[test]
+    fn labeled_loops() {
+        let source = std::fs::read_to_string("./examples/features/loops/valid/labeled.ay").unwrap();
+        let (res, output) = run(&source);
+
+        res.unwrap();
+        assert_eq!(output, "0 0\n0 1\n1 0\n");
+    }
+
+    #[test]
+    fn execution_trace() {
+        let ast = parsing::parse(SourceCode::Content(
+            "ngop lì'ukìng tsìng fa a alu sung fa a sì 1 txew.\nwìntxu fa tsìng fa 2.".to_owned(),
+        ))
+        .unwrap();
+        let bound = binding::convert(&ast).unwrap();
+
+        let mut interpreter = Interpreter::new(vec![]).with_tracing(true);
+        interpreter.run(&bound).unwrap();
+
+        assert_eq!(
+            String::from_utf8(interpreter.into_output()).unwrap(),
+            "[1:6] lì'ukìng tsìng fa a alu sung fa a sì 1 txew\n\
+             [2:1] wìntxu fa tsìng fa 2\n\
+             -> tsìng(2)\n\
+             [1:30] sung fa a sì 1\n\
+             <- tsìng = 3\n\
+             3\n"
+        );
+    }
+
+    #[test]
+    fn output_base() {
+        let ast = parsing::parse(SourceCode::Content("wìntxu fa 7 melo.".to_owned())).unwrap();
+        let bound = binding::convert(&ast).unwrap();
+
+        let output = |base| {
+            let mut interpreter = Interpreter::new(vec![]).with_base(base);
+            interpreter.run(&bound).unwrap();
+            String::from_utf8(interpreter.into_output()).unwrap()
+        };
+
+        assert_eq!(output(Base::Octal), "16\n");
+        assert_eq!(output(Base::Decimal), "14\n");
+    }
+
+    #[test]
+    fn runtime_warnings() {
+        let source =
+            std::fs::read_to_string("./examples/features/runtime/valid/float_overflow.ay").unwrap();
+        let ast = parsing::parse(SourceCode::Content(source)).unwrap();
+        let bound = binding::convert(&ast).unwrap();
+
+        let (res, warnings) = Interpreter::new(vec![]).eval(&bound);
+
+        res.unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings
+            .iter()
+            .all(|warning| warning.severity() == Severity::Warning));
+        assert!(warnings[0]
+            .frames()
+            .any(|(_, err)| err.message().contains("`1.0 / 0.0` gives inf")));
+        assert!(warnings[1]
+            .frames()
+            .any(|(_, err)| err.message().contains("gives NaN")));
+    }
+
+    #[test]
+    fn match_arms() {
+        let source =
+            std::fs::read_to_string("./examples/features/match/valid/literals.ay").unwrap();
+        let declaration = source.split("\n\n").next().unwrap();
+
+        for (arg, expected) in [(0, "kewa"), (1, "'aw"), (2, "mune"), (7, "pxay")] {
+            let (res, _) = run(&format!("{declaration}\nname fa {arg}."));
+            assert_eq!(res.unwrap(), Value::from(expected), "name fa {arg}");
+        }
+    }
+
+    #[test]
+    fn standalone_expressions() {
+        let options = CompilerOptions::default();
+        let eval = |source| eval_expr(source, &options);
+
+        assert_eq!(eval("sung fa 1 sì 2").unwrap(), Value::Number(3));
+        assert_eq!(eval("sung fa 7 melo sì ke 1.").unwrap(), Value::Number(13));
+        assert_eq!(eval("'ì'awnyu fa 12 sì 3").unwrap(), Value::Number(1));
+        assert_eq!(eval("may' 1 sì 2 livu hol").unwrap(), Value::Bool(true));
+        assert_eq!(eval("ke 1 lu 1").unwrap(), Value::Bool(false));
+        assert_eq!(eval("ke -17").unwrap(), Value::Number(15));
+        assert_eq!(eval("-2 melo - -1").unwrap(), Value::Number(-3));
+        assert_eq!(
+            eval("vezeykoyu 1 sì 2").unwrap(),
+            Value::from(vec![1_i64, 2])
+        );
+        assert!(eval("sung fa a sì 1").is_err());
+        assert!(eval("sung fa 1. sung fa 2.").is_err());
+    }
+
+    #[test]
+    fn recursion() {
+        // Triangle numbers, as there is no multiplication for factorials
+        let (res, _) = run("ngop lì'ukìng tri fa n alu\n\
+                 txo n lu 0, tsakrr 0, txokefyaw sung fa n sì tri fa sung fa n sì ke 1\n\
+             txew.\n\
+             tri fa 12.");
+
+        assert_eq!(res.unwrap(), Value::Number(55));
+    }
+
+    #[test]
+    fn accumulating_loop() {
+        let ast = parsing::parse(SourceCode::Content(
+            "ngop lì'ukìng tsìng fa i sì total alu\n\
+                 txo may' i sì 5 livu hol,\n\
+                 tsakrr tsìng fa i + 1 sì total + i + 1,\n\
+                 txokefyaw total\n\
+             txew.\n\
+             tsìng fa 0 sì 0."
+                .to_owned(),
+        ))
+        .unwrap();
+        // The tail call becomes a loop assigning the parameters
+        let bound = iteration::iterate(&binding::convert(&ast).unwrap());
+        let Statement::FunDec(dec) = &bound[0].inner else {
+            panic!("Expected a function declaration");
+        };
+        assert!(dec
+            .body
+            .iter()
+            .any(|node| matches!(node.inner, Statement::Loop { .. })));
+
+        let res = Interpreter::new(std::io::sink()).run(&bound);
+
+        assert_eq!(res.unwrap(), Value::Number(15));
+    }
+
+    #[test]
+    fn arithmetic() {
+        let (res, _) = run("2 + 3 * 4.");
+        assert_eq!(res.unwrap(), Value::Number(14));
+
+        let (res, _) = run("ke 1.4 / 2.0 - 0.4.");
+        assert_eq!(res.unwrap(), Value::Float(-1.25));
+
+        let (res, _) = run("1 / 0.");
+        assert_eq!(
+            res.unwrap_err().frames().next().unwrap().1.message(),
+            "Division by zero"
+        );
+
+        let (res, _) = run("1 + 0.4.");
+        assert_eq!(
+            res.unwrap_err().frames().next().unwrap().1.message(),
+            "Cannot apply `+` to Number value and Float value"
+        );
+    }
+
+    #[test]
+    fn division_by_zero() {
+        let (res, _) = run("'ì'awnyu fa 7 sì 0.");
+        let trace = res.unwrap_err();
+        let (stage, err) = trace.frames().next().unwrap();
+
+        assert!(matches!(stage, Stage::Compiling));
+        assert_eq!(err.message(), "Division by zero");
+    }
+
+    #[test]
+    fn overflow() {
+        // The smallest number, one past the largest literal
+        let min = "(ke 777777777777777777777 - 1)";
+
+        let (res, _) = run(&format!("ke {min}."));
+        assert_eq!(
+            res.unwrap_err().frames().next().unwrap().1.message(),
+            "`ke -9223372036854775808` overflowed"
+        );
+
+        let (res, _) = run(&format!("'ì'awnyu fa {min} sì ke 1."));
+        assert_eq!(
+            res.unwrap_err().frames().next().unwrap().1.message(),
+            "Remainder overflowed"
+        );
+    }
+
+    #[test]
+    fn runtime_assert() {
+        let (res, _) = run("ngop 'u x alu 2.\nnìngay may' x sì 3 livu teng.");
+
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn short_circuit() {
+        let source =
+            std::fs::read_to_string("./examples/features/runtime/valid/short_circuit.ay").unwrap();
+        let (res, output) = run(&source);
+
+        res.unwrap();
+        assert_eq!(output, "3\n4\n");
+    }
+
+    /// `t.aron` prints its argument before returning it.
+    fn tenses(calls: &str) -> String {
+        let (res, output) = run(&format!(
+            "ngop lì'ukìng t.aron fa a alu\n\
+                 wìntxu fa a ulte\n\
+                 a\n\
+             txew.\n\
+             {calls}"
+        ));
+
+        res.unwrap();
+        output
+    }
+
+    #[test]
+    fn future_calls_wait_until_forced() {
+        assert_eq!(tenses("ngop 'u a alu tayaron fa 1."), "");
+        assert_eq!(
+            tenses("ngop 'u a alu tayaron fa 1.\nwìntxu fa 2.\nwìntxu fa a sì a."),
+            "2\n1\n1 1\n"
+        );
+
+        let (res, output) = run("ngop lì'ukìng t.aron alu wìntxu fa 1 txew.\ntayaron si.");
+        assert!(matches!(res.unwrap(), Value::Deferred(deferred) if !deferred.is_forced()));
+        assert_eq!(output, "");
+    }
+
+    #[test]
+    fn deferred_calls_keep_their_scope() {
+        // Forced in `kan`, where `a` is another variable
+        let (res, _) = run("ngop 'u a alu 1.\n\
+             ngop lì'ukìng t.aron alu a txew.\n\
+             ngop lì'ukìng kan fa b alu ngop 'u a alu 2 ulte b + a txew.\n\
+             kan fa (tayaron si).");
+        assert_eq!(res.unwrap(), Value::Number(3));
+    }
+
+    #[test]
+    fn present_calls_run_right_away() {
+        assert_eq!(
+            tenses("ngop 'u a alu taron fa 1.\nwìntxu fa 2.\nwìntxu fa a."),
+            "1\n2\n1\n"
+        );
+    }
+
+    #[test]
+    fn imminent_calls_run_after_their_statement() {
+        assert_eq!(
+            tenses("ngop 'u a sì b alu (tìyaron fa 1) sì taron fa 2.\nwìntxu fa 3."),
+            "2\n1\n3\n"
+        );
+    }
+
+    #[test]
+    fn program_result_is_forced() {
+        let ast = parsing::parse(SourceCode::Content(
+            "ngop lì'ukìng t.aron alu wìntxu fa 1 txew.\ntayaron si.".to_owned(),
+        ))
+        .unwrap();
+        let mut interpreter = Interpreter::new(vec![]);
+        let (res, _) = interpreter.eval(&binding::convert(&ast).unwrap());
+
+        assert_eq!(res.unwrap(), Value::Unit);
+        assert_eq!(interpreter.into_output(), b"1\n");
+    }
+}
@@ -0,0 +1,491 @@
+use crate::{
+    ast::{
+        binding::FunDec,
+        lib::{Base, BinaryOperator, ComparisonOperator},
+    },
+    error::{
+        span::Span,
+        trace::{Stage, Trace},
+        trace_error::Error,
+    },
+    interp::env::Env,
+};
+
+use std::{cell::RefCell, cmp::Ordering, rc::Rc};
+
+/// Runtime value of an expression.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Value {
+    /// Result of anything that is not an expression.
+    #[default]
+    Unit,
+    /// `kewu`, only usable through `srak`, `fu` and equality until replaced by an actual value.
+    None,
+    Bool(bool),
+    Number(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// Builtin used as a value, such as `(+)`, named after it.
+    Function(String),
+    /// Result of a call in the imminent or future tense, see [`Interpreter`](super::Interpreter).
+    Deferred(Deferred),
+}
+
+/// Call that has not run yet, shared by the copies of the value so that it runs at most once.
+#[derive(Clone, Debug)]
+pub struct Deferred(pub(crate) Rc<RefCell<Thunk>>);
+
+#[derive(Debug)]
+pub(crate) enum Thunk {
+    Pending(Call),
+    /// Being forced, needing it again means it depends on itself.
+    Running {
+        span: Span,
+        name: String,
+    },
+    Done(Value),
+}
+
+/// Call to `name`, a builtin when `dec` is `None`. Declared functions come with the scope they
+/// are declared in, resolved where the call is made.
+#[derive(Debug)]
+pub(crate) struct Call {
+    pub span: Span,
+    pub name: String,
+    pub dec: Option<(Rc<FunDec>, Rc<Env>)>,
+    pub args: Vec<Value>,
+}
+
+impl Deferred {
+    pub(crate) fn new(call: Call) -> Self {
+        Self(Rc::new(RefCell::new(Thunk::Pending(call))))
+    }
+
+    /// Whether the call already ran.
+    pub fn is_forced(&self) -> bool {
+        matches!(*self.0.borrow(), Thunk::Done(_))
+    }
+}
+
+/// Copies of the same call are equal, whether it ran or not.
+impl PartialEq for Deferred {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Unit => "Unit",
+            Value::None => "None",
+            Value::Bool(_) => "Bool",
+            Value::Number(_) => "Number",
+            Value::Float(_) => "Float",
+            Value::String(_) => "String",
+            Value::Array(_) => "Array",
+            Value::Function(_) => "Function",
+            Value::Deferred(_) => "Deferred",
+        }
+    }
+
+    /// Fails on values of different types, arrays included, instead of silently not matching.
+    /// Anything can be checked for equality with `kewu` though.
+    pub fn compare(&self, operator: &ComparisonOperator, other: &Value) -> Result<Value, String> {
+        if *operator == ComparisonOperator::Equals
+            && (*self == Value::None || *other == Value::None)
+        {
+            return Ok(Value::Bool(self == other));
+        }
+
+        if let Some((left, right)) = self.mismatched(other) {
+            return Err(format!(
+                "Cannot compare {} value with {} value",
+                left.type_name(),
+                right.type_name()
+            ));
+        }
+
+        Ok(Value::Bool(match operator {
+            ComparisonOperator::Equals => self == other,
+            ComparisonOperator::Less => self < other,
+            ComparisonOperator::Greater => self > other,
+        }))
+    }
+
+    /// Both operands have to be numbers of the same type, integer division rounding towards zero,
+    /// strings or arrays to be joined, or booleans.
+    pub fn apply(&self, operator: BinaryOperator, other: &Value) -> Result<Value, String> {
+        let mismatch = || {
+            Err(format!(
+                "Cannot apply `{}` to {} value and {} value",
+                <&str>::from(operator),
+                self.type_name(),
+                other.type_name()
+            ))
+        };
+
+        match (self, other) {
+            (Value::Number(_), Value::Number(0)) if operator == BinaryOperator::Divide => {
+                Err("Division by zero".to_owned())
+            }
+            (Value::Number(left), Value::Number(right)) => match operator {
+                BinaryOperator::Add => left.checked_add(*right),
+                BinaryOperator::Subtract => left.checked_sub(*right),
+                BinaryOperator::Multiply => left.checked_mul(*right),
+                BinaryOperator::Divide => left.checked_div(*right),
+                BinaryOperator::Concat | BinaryOperator::Or | BinaryOperator::And => {
+                    return mismatch()
+                }
+            }
+            .map(Value::Number)
+            .ok_or_else(|| format!("`{left} {} {right}` overflowed", <&str>::from(operator))),
+            (Value::Float(left), Value::Float(right)) => Ok(Value::Float(match operator {
+                BinaryOperator::Add => left + right,
+                BinaryOperator::Subtract => left - right,
+                BinaryOperator::Multiply => left * right,
+                BinaryOperator::Divide => left / right,
+                BinaryOperator::Concat | BinaryOperator::Or | BinaryOperator::And => {
+                    return mismatch()
+                }
+            })),
+            (Value::Bool(left), Value::Bool(right)) if operator.is_logical() => {
+                Ok(Value::Bool(match operator {
+                    BinaryOperator::Or => *left || *right,
+                    _ => *left && *right,
+                }))
+            }
+            (Value::String(left), Value::String(right)) if operator == BinaryOperator::Concat => {
+                Ok(Value::String(format!("{left}{right}")))
+            }
+            (Value::Array(left), Value::Array(right)) if operator == BinaryOperator::Concat => {
+                Ok(Value::Array([left.as_slice(), right].concat()))
+            }
+            _ => mismatch(),
+        }
+    }
+
+    /// First pair of values of different types found while comparing arrays element-wise.
+    fn mismatched<'a>(&'a self, other: &'a Value) -> Option<(&'a Value, &'a Value)> {
+        match (self, other) {
+            (Value::Array(left), Value::Array(right)) => left
+                .iter()
+                .zip(right)
+                .find_map(|(left, right)| left.mismatched(right)),
+            _ if std::mem::discriminant(self) == std::mem::discriminant(other) => None,
+            _ => Some((self, other)),
+        }
+    }
+
+    /// Whether the value holds when used as a condition.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Unit | Value::None => false,
+            Value::Bool(value) => *value,
+            Value::Number(number) => *number != 0,
+            Value::Float(number) => *number != 0.,
+            Value::String(string) => !string.is_empty(),
+            Value::Array(items) => !items.is_empty(),
+            Value::Function(_) => true,
+            Value::Deferred(deferred) => match &*deferred.0.borrow() {
+                Thunk::Done(value) => value.is_truthy(),
+                _ => false,
+            },
+        }
+    }
+
+    fn mismatch(&self, expected: &str) -> Trace {
+        Trace::new(
+            Stage::Compiling,
+            Error::new(
+                format!(
+                    "Cannot convert {} value to {expected}: {self:?}",
+                    self.type_name()
+                )
+                .as_ref(),
+            ),
+        )
+    }
+}
+
+/// Arrays are ordered lexicographically, values of different types are not ordered.
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Unit, Value::Unit) | (Value::None, Value::None) => Some(Ordering::Equal),
+            (Value::Bool(left), Value::Bool(right)) => left.partial_cmp(right),
+            (Value::Number(left), Value::Number(right)) => left.partial_cmp(right),
+            (Value::Float(left), Value::Float(right)) => left.partial_cmp(right),
+            (Value::String(left), Value::String(right)) => left.partial_cmp(right),
+            (Value::Array(left), Value::Array(right)) => left.partial_cmp(right),
+            _ => None,
+        }
+    }
+}
+
+/// Renders values the way they would be written in source code, numbers in decimal.
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.display_in(Base::Decimal).fmt(f)
+    }
+}
+
+/// Value rendered with its numbers written in `base`, see [`Value::display_in`].
+pub struct BaseDisplay<'a> {
+    value: &'a Value,
+    base: Base,
+}
+
+impl Value {
+    pub fn display_in(&self, base: Base) -> BaseDisplay<'_> {
+        BaseDisplay { value: self, base }
+    }
+
+    /// Numbers as they are read in source code, `7 melo` being rendered as `16`.
+    pub fn display_octal(&self) -> BaseDisplay<'_> {
+        self.display_in(Base::Octal)
+    }
+}
+
+impl std::fmt::Display for BaseDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.value, self.base) {
+            (Value::Unit, _) => Ok(()),
+            (Value::None, _) => write!(f, "kewu"),
+            (Value::Bool(true), _) => write!(f, "srane"),
+            (Value::Bool(false), _) => write!(f, "kehe"),
+            (Value::Number(number), Base::Decimal) => write!(f, "{number}"),
+            (Value::Number(number), Base::Octal) => {
+                let sign = if *number < 0 { "-" } else { "" };
+                write!(f, "{sign}{:o}", number.unsigned_abs())
+            }
+            (Value::Float(number), Base::Decimal) => write!(f, "{number}"),
+            (Value::Float(number), Base::Octal) => write!(f, "{}", octal_float(*number)),
+            (Value::String(string), _) => write!(f, "{string}"),
+            (Value::Array(items), _) if items.is_empty() => write!(f, "vezeykoyu"),
+            (Value::Array(items), base) => write!(
+                f,
+                "vezeykoyu {}",
+                items
+                    .iter()
+                    .map(|item| item.display_in(base).to_string())
+                    .collect::<Vec<String>>()
+                    .join(" sì ")
+            ),
+            (Value::Function(name), _) => write!(f, "({name})"),
+            (Value::Deferred(deferred), base) => match &*deferred.0.borrow() {
+                Thunk::Pending(Call { name, .. }) | Thunk::Running { name, .. } => {
+                    write!(f, "<{name}>")
+                }
+                Thunk::Done(value) => value.display_in(base).fmt(f),
+            },
+        }
+    }
+}
+
+/// Octal digits of `number`, the fractional part being cut once it exceeds an `f64`'s precision.
+fn octal_float(number: f64) -> String {
+    if !number.is_finite() {
+        return number.to_string();
+    }
+
+    let sign = if number.is_sign_negative() && number != 0. {
+        "-"
+    } else {
+        ""
+    };
+    let (integer, mut fraction) = (number.abs().trunc(), number.abs().fract());
+    let mut digits = String::new();
+
+    // 53 bits of mantissa take up to 18 octal digits
+    while fraction != 0. && digits.len() < 18 {
+        fraction *= 8.;
+        digits.push(char::from_digit(fraction.trunc() as u32, 8).unwrap());
+        fraction = fraction.fract();
+    }
+
+    match digits.as_str() {
+        "" => format!("{sign}{:o}", integer as u64),
+        digits => format!("{sign}{:o}.{digits}", integer as u64),
+    }
+}
+
+impl From<()> for Value {
+    fn from(_: ()) -> Self {
+        Value::Unit
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_owned())
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(value: Vec<T>) -> Self {
+        Value::Array(value.into_iter().map(Into::into).collect())
+    }
+}
+
+impl TryFrom<Value> for () {
+    type Error = Trace;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Unit => Ok(()),
+            value => Err(value.mismatch("()")),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = Trace;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Bool(value) => Ok(value),
+            value => Err(value.mismatch("bool")),
+        }
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = Trace;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Number(value) => Ok(value),
+            value => Err(value.mismatch("i64")),
+        }
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = Trace;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Float(value) => Ok(value),
+            value => Err(value.mismatch("f64")),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = Trace;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(value) => Ok(value),
+            value => Err(value.mismatch("String")),
+        }
+    }
+}
+
+impl<T: TryFrom<Value, Error = Trace>> TryFrom<Value> for Vec<T> {
+    type Error = Trace;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Array(items) => items.into_iter().map(T::try_from).collect(),
+            value => Err(value.mismatch("Vec")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip<T>(value: T)
+    where
+        T: Into<Value> + TryFrom<Value, Error = Trace> + Clone + PartialEq + std::fmt::Debug,
+    {
+        assert_eq!(T::try_from(value.clone().into()).unwrap(), value);
+    }
+
+    #[test]
+    fn round_trips() {
+        round_trip(());
+        round_trip(true);
+        round_trip(-0o17_i64);
+        round_trip(0.5);
+        round_trip("kaltxì".to_owned());
+        round_trip(vec![1_i64, 2, 3]);
+        round_trip(vec![vec!["a".to_owned()], vec![]]);
+    }
+
+    #[test]
+    fn octal_display() {
+        // `7 melo`
+        let value = Value::Number(14);
+
+        assert_eq!(value.display_octal().to_string(), "16");
+        assert_eq!(value.display_in(Base::Decimal).to_string(), "14");
+        assert_eq!(value.to_string(), "14");
+        assert_eq!(Value::Number(-8).display_octal().to_string(), "-10");
+        assert_eq!(Value::Float(8.5).display_octal().to_string(), "10.4");
+        assert_eq!(
+            Value::from(vec![8_i64, 9]).display_octal().to_string(),
+            "vezeykoyu 10 sì 11"
+        );
+    }
+
+    #[test]
+    fn concatenation() {
+        let concat = |left: Value, right: Value| left.apply(BinaryOperator::Concat, &right);
+
+        assert_eq!(
+            concat(Value::from("kal"), Value::from("txì")),
+            Ok(Value::from("kaltxì"))
+        );
+        assert_eq!(
+            concat(Value::from(vec![Value::Number(1)]), Value::Array(vec![])),
+            Ok(Value::from(vec![Value::Number(1)]))
+        );
+        assert_eq!(
+            concat(Value::Number(1), Value::Number(2)),
+            Err("Cannot apply `++` to Number value and Number value".to_owned())
+        );
+        assert!(concat(Value::from("a"), Value::Array(vec![])).is_err());
+        assert!(Value::from("a")
+            .apply(BinaryOperator::Add, &Value::from("b"))
+            .is_err());
+    }
+
+    #[test]
+    fn mismatches() {
+        assert!(i64::try_from(Value::from("7")).is_err());
+        assert!(String::try_from(Value::Number(7)).is_err());
+        assert!(bool::try_from(Value::Unit).is_err());
+        assert!(
+            Vec::<i64>::try_from(Value::from(vec![Value::Number(1), Value::Bool(true)])).is_err()
+        );
+    }
+}
@@ -0,0 +1,28 @@
+//! Parsing, binding, typing and interpretation of aysìnvi programs.
+//!
+//! A program goes through [`parse`], then [`binding::convert`] which resolves its names, after
+//! which it can be typed with [`typing::convert`] or run by an [`Interpreter`].
+
+pub mod ast;
+pub mod error;
+pub mod highlight;
+pub mod interp;
+pub mod opt;
+pub mod options;
+pub mod repl;
+pub mod visit;
+
+#[macro_use]
+extern crate pest_derive;
+
+pub use crate::{
+    ast::{
+        binding, format, inlining, iteration,
+        lib::{AyNode, AyType, BinaryOperator, ComparisonOperator, Node, SourceCode, StructuralEq},
+        lint,
+        parsing::{self, parse, parse_expr, parse_with_base},
+        typing,
+    },
+    error::trace::{Trace, TraceOrder},
+    interp::{value::Value, Interpreter},
+};
@@ -1,44 +1,106 @@
-#![allow(unused)]
-
-mod ast;
-mod error;
-mod highlight;
-
-extern crate pest;
-#[macro_use]
-extern crate pest_derive;
-
-use crate::{
-    ast::{
-        lib::{AyNode, SourceCode},
-        *,
-    },
-    error::trace::Trace,
-    highlight::highlight_aysinvi,
+use aysinvi::{
+    binding,
+    error::{trace::Stage, trace_error::Error},
+    format,
+    highlight::{self, highlight_aysinvi, paint},
+    inlining, iteration, lint, opt,
+    options::CompilerOptions,
+    parsing,
+    repl::Repl,
+    typing, AyNode, Interpreter, SourceCode, Trace, Value,
 };
 
-use pest::{
-    error::{Error, ErrorVariant},
-    iterators::{Pair, Pairs},
-    Parser,
-};
+use std::io::{IsTerminal, Read};
 
 fn main() -> Result<(), Trace> {
     macro_rules! print_ast {
-        ($ast:ident) => {
-            println!("\x1b[1m{}\x1b[0m", stringify!($ast));
+        ($ast:ident, $render:expr) => {
+            println!("{}", paint("1", stringify!($ast), highlight::color()));
             match $ast {
-                Ok(ref ast) => println!("{ast:?}"),
+                Ok(ref ast) => println!("{}", $render(ast)),
                 Err(ref trace) => println!("{trace}"),
             };
         };
     }
 
-    let ast = parsing::parse(SourceCode::File("./examples/funargs.ay".to_string()));
-    print_ast!(ast);
+    let (options, paths) =
+        CompilerOptions::from_args(std::env::args().skip(1)).unwrap_or_else(|err| {
+            eprintln!("{err}");
+            std::process::exit(1)
+        });
+
+    highlight::set_color(!options.no_color && highlight::detect_color());
+
+    if let [command] = paths.as_slice() {
+        if command == "repl" {
+            let stdin = std::io::stdin();
+            let mut repl = Repl::new(std::io::stdout(), &options);
+
+            if stdin.is_terminal() {
+                repl = repl.with_prompt("> ");
+            }
+
+            repl.run(stdin.lock()).unwrap_or_else(|err| {
+                eprintln!("Could not read the standard input: {err}");
+                std::process::exit(1)
+            });
+
+            return Ok(());
+        }
+    }
+
+    // `parse`, `bind`, `run` or `fmt` a file, `-` standing for the standard input
+    if let [command, path] = paths.as_slice() {
+        match command.as_str() {
+            "parse" => parse(path, &options).map(|_| ()),
+            "bind" => bind(path, &options).map(|_| ()),
+            "run" => run(path, &options),
+            "fmt" => source(path)
+                .and_then(|source| format::format(source, options.number_base_input))
+                .map(|formatted| print!("{formatted}")),
+            command => {
+                eprintln!("Unknown command `{command}`, expected parse|bind|run|fmt");
+                std::process::exit(1)
+            }
+        }
+        .unwrap_or_else(|trace| {
+            eprintln!("{trace}");
+            std::process::exit(1)
+        });
+
+        return Ok(());
+    }
+
+    let path = paths
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| "./examples/funargs.ay".to_owned());
+
+    parsing::print_tree(SourceCode::File(path.clone()), options.max_depth)?;
+
+    let ast = parsing::parse_with_base(SourceCode::File(path), options.number_base_input);
+    print_ast!(ast, |ast: &Vec<_>| options.ast_format.render(ast));
+
+    if let Ok(ref ast) = ast {
+        lint::lint(ast, &options.lints, options.number_base_input)
+            .iter()
+            .for_each(|warning| println!("{warning}"));
+    }
 
     let bound = binding::convert(&ast?);
-    print_ast!(bound);
+    print_ast!(bound, |bound| format!("{bound:?}"));
+
+    if let Ok(ref bound) = bound {
+        binding::tense_collisions(bound)
+            .iter()
+            .chain(binding::empty_bodies(bound).iter())
+            .chain(binding::non_boolean_conditions(bound).iter())
+            .chain(binding::unused_variables(bound).iter())
+            .for_each(|warning| println!("{warning}"));
+
+        let typed = typing::convert(bound);
+        print_ast!(typed, |typed| format!("{typed:?}"));
+    }
 
     println!(
         "{}",
@@ -52,15 +114,99 @@ fn main() -> Result<(), Trace> {
     Ok(())
 }
 
+/// Source code of the file at `path`, or of the standard input if it is `-`.
+fn source(path: &str) -> Result<SourceCode, Trace> {
+    if path != "-" {
+        return Ok(SourceCode::File(path.to_owned()));
+    }
+
+    let mut content = String::new();
+    std::io::stdin()
+        .read_to_string(&mut content)
+        .map_err(|err| {
+            Trace::new(
+                Stage::Reading,
+                Error::new(&format!("Could not read the standard input: {err}")),
+            )
+        })?;
+
+    Ok(SourceCode::Content(content))
+}
+
+fn parse(path: &str, options: &CompilerOptions) -> Result<Vec<AyNode<parsing::Statement>>, Trace> {
+    let ast = parsing::parse_with_base(source(path)?, options.number_base_input)?;
+
+    if options.print_ast {
+        println!("{}", options.ast_format.render(&ast));
+    }
+
+    Ok(ast)
+}
+
+fn bind(path: &str, options: &CompilerOptions) -> Result<Vec<AyNode<binding::Statement>>, Trace> {
+    let bound = binding::convert(&parse(path, options)?)?;
+
+    if options.print_bound {
+        println!("{bound:?}");
+    }
+
+    // Ill-typed programs are rejected before anything runs
+    typing::convert(&bound)?;
+
+    Ok(bound)
+}
+
+/// Runs the program, printing its result if it has one.
+fn run(path: &str, options: &CompilerOptions) -> Result<(), Trace> {
+    let mut bound = bind(path, options)?;
+
+    if options.inline {
+        bound = inlining::inline(&bound);
+    }
+
+    if options.iterate {
+        bound = iteration::iterate(&bound);
+    }
+
+    if options.fold {
+        bound = opt::fold_constants(&bound);
+    }
+
+    let (res, warnings) = Interpreter::new(std::io::stdout())
+        .with_tracing(options.trace_exec)
+        .with_base(options.number_base)
+        .eval(&bound);
+
+    warnings.iter().for_each(|warning| eprintln!("{warning}"));
+
+    match res? {
+        Value::Unit => {}
+        value => println!("{}", value.display_in(options.number_base)),
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
+    use aysinvi::AyNode;
+
     use super::*;
 
     const TEST_FOLDER: &str = "./examples/features";
 
-    fn run_tests<F>(path: &str, check: F)
+    fn bind(source: SourceCode) -> Result<Vec<AyNode<binding::Statement>>, Trace> {
+        binding::convert(&parsing::parse(source)?)
+    }
+
+    fn interpret(source: SourceCode) -> Result<Value, Trace> {
+        Interpreter::new(std::io::sink()).run(&bind(source)?)
+    }
+
+    fn run_tests<T, P, F>(path: &str, pipeline: P, check: F)
     where
-        F: Fn(Result<Vec<AyNode<parsing::Statement>>, Trace>) -> bool,
+        P: Fn(SourceCode) -> Result<T, Trace>,
+        F: Fn(Result<T, Trace>) -> bool,
     {
         let folder = format!("{TEST_FOLDER}/{path}");
         let mut entries = std::fs::read_dir(folder.clone())
@@ -70,7 +216,7 @@ mod test {
             let entry = entry.path().to_str().unwrap().to_string();
             eprintln!("Running test {entry}");
 
-            let res = parsing::parse(SourceCode::File(entry));
+            let res = pipeline(SourceCode::File(entry));
             if let Err(trace) = &res {
                 eprintln!("{trace}");
             }
@@ -81,11 +227,115 @@ mod test {
 
     #[test]
     fn valid_expressions() {
-        run_tests("expressions/valid", |output| output.is_ok());
+        run_tests("expressions/valid", parsing::parse, |output| output.is_ok());
     }
 
     #[test]
     fn invalid_expressions() {
-        run_tests("expressions/invalid", |output| output.is_err());
+        run_tests("expressions/invalid", parsing::parse, |output| {
+            output.is_err()
+        });
+    }
+
+    #[test]
+    fn valid_statements() {
+        run_tests("statements/valid", interpret, |output| output.is_ok());
+    }
+
+    #[test]
+    fn invalid_statements() {
+        run_tests("statements/invalid", parsing::parse, |output| {
+            output.is_err()
+        });
+    }
+
+    #[test]
+    fn valid_functions() {
+        run_tests("functions/valid", bind, |output| output.is_ok());
+    }
+
+    #[test]
+    fn invalid_functions() {
+        run_tests("functions/invalid", bind, |output| output.is_err());
+    }
+
+    #[test]
+    fn valid_conditions() {
+        run_tests("conditions/valid", bind, |output| output.is_ok());
+    }
+
+    #[test]
+    fn valid_loops() {
+        run_tests("loops/valid", bind, |output| output.is_ok());
+    }
+
+    #[test]
+    fn invalid_loops() {
+        run_tests("loops/invalid", bind, |output| output.is_err());
+    }
+
+    #[test]
+    fn valid_comparisons() {
+        run_tests("comparison/valid", interpret, |output| output.is_ok());
+    }
+
+    #[test]
+    fn invalid_comparisons() {
+        run_tests("comparison/invalid", interpret, |output| output.is_err());
+    }
+
+    #[test]
+    fn valid_optionals() {
+        run_tests("optionals/valid", interpret, |output| output.is_ok());
+    }
+
+    #[test]
+    fn invalid_optionals() {
+        run_tests("optionals/invalid", interpret, |output| output.is_err());
+    }
+
+    #[test]
+    fn valid_matches() {
+        run_tests("match/valid", interpret, |output| output.is_ok());
+    }
+
+    #[test]
+    fn invalid_matches() {
+        run_tests("match/invalid", interpret, |output| output.is_err());
+    }
+
+    #[test]
+    fn valid_runtime() {
+        run_tests("runtime/valid", interpret, |output| output.is_ok());
+    }
+
+    #[test]
+    fn valid_comments() {
+        run_tests("comments/valid", interpret, |output| output.is_ok());
+    }
+
+    #[test]
+    fn invalid_comments() {
+        run_tests("comments/invalid", parsing::parse, |output| output.is_err());
+    }
+
+    #[test]
+    fn valid_modules() {
+        run_tests("modules/valid", interpret, |output| output.is_ok());
+    }
+
+    #[test]
+    fn invalid_modules() {
+        run_tests("modules/invalid", parsing::parse, |output| output.is_err());
+    }
+
+    #[test]
+    fn valid_asserts() {
+        run_tests("assert/valid", bind, |output| output.is_ok());
+    }
+
+    #[test]
+    fn invalid_asserts() {
+        run_tests("assert/invalid", bind, |output| output.is_err());
     }
 }
@@ -0,0 +1,266 @@
+use crate::{
+    ast::{
+        binding::{Expr, FunDec, Statement, VarDec},
+        lib::AyNode,
+    },
+    interp::value::Value,
+};
+
+use std::rc::Rc;
+
+/// Replaces operations on literals by their result, keeping the span of the operation.
+///
+/// Negations of literals, comparisons between two literals and arithmetic on two literals are
+/// folded, innermost first so that `ke (1 + 2)` becomes `-3`. Operations that would fail when run,
+/// such as a division by zero or a comparison between values of different types, are left for the
+/// error to be raised at runtime.
+pub fn fold_constants(stmts: &[AyNode<Statement>]) -> Vec<AyNode<Statement>> {
+    stmts
+        .iter()
+        .map(|AyNode { span, inner }| AyNode {
+            span: span.clone(),
+            inner: match inner {
+                Statement::FunDec(dec) => Statement::FunDec(Rc::new(FunDec {
+                    name: dec.name.clone(),
+                    args: dec.args.clone(),
+                    body: fold_constants(&dec.body),
+                    helpers: fold_constants(&dec.helpers),
+                })),
+                Statement::VarDec(VarDec { names, values }) => Statement::VarDec(VarDec {
+                    names: names.clone(),
+                    values: exprs(values),
+                }),
+                Statement::Assign(VarDec { names, values }) => Statement::Assign(VarDec {
+                    names: names.clone(),
+                    values: exprs(values),
+                }),
+                Statement::Expr(node) => Statement::Expr(expr(node)),
+                Statement::Assert(node) => Statement::Assert(expr(node)),
+                Statement::If {
+                    cond,
+                    then,
+                    otherwise,
+                } => Statement::If {
+                    cond: expr(cond),
+                    then: fold_constants(then),
+                    otherwise: fold_constants(otherwise),
+                },
+                Statement::Loop { cond, body, label } => Statement::Loop {
+                    cond: cond.as_ref().map(expr),
+                    body: fold_constants(body),
+                    label: label.clone(),
+                },
+                Statement::Break(_) | Statement::Continue(_) => inner.clone(),
+            },
+        })
+        .collect()
+}
+
+fn exprs(nodes: &[AyNode<Expr>]) -> Vec<AyNode<Expr>> {
+    nodes.iter().map(expr).collect()
+}
+
+fn expr(AyNode { span, inner }: &AyNode<Expr>) -> AyNode<Expr> {
+    let inner = match inner {
+        Expr::Negated(node) => {
+            let node = expr(node);
+
+            match node.inner {
+                Expr::Number(number) if number != i64::MIN => Expr::Number(-number),
+                Expr::Float(number) => Expr::Float(-number),
+                Expr::Bool(value) => Expr::Bool(!value),
+                _ => Expr::Negated(Box::new(node)),
+            }
+        }
+        Expr::Comparison {
+            left,
+            right,
+            operator,
+        } => {
+            let (left, right) = (expr(left), expr(right));
+
+            match (literal(&left.inner), literal(&right.inner)) {
+                (Some(left), Some(right)) => left.compare(operator, &right).ok(),
+                _ => None,
+            }
+            .and_then(from_value)
+            .unwrap_or_else(|| Expr::Comparison {
+                left: Box::new(left),
+                right: Box::new(right),
+                operator: operator.clone(),
+            })
+        }
+        Expr::BinOp { op, left, right } => {
+            let (left, right) = (expr(left), expr(right));
+
+            match (literal(&left.inner), literal(&right.inner)) {
+                (Some(left), Some(right)) => left.apply(*op, &right).ok(),
+                _ => None,
+            }
+            .and_then(from_value)
+            .unwrap_or_else(|| Expr::BinOp {
+                op: *op,
+                left: Box::new(left),
+                right: Box::new(right),
+            })
+        }
+        Expr::FunCall { tense, name, args } => Expr::FunCall {
+            tense: tense.clone(),
+            name: name.clone(),
+            args: exprs(args),
+        },
+        Expr::Array { items } => Expr::Array {
+            items: exprs(items),
+        },
+        Expr::HasValue(node) => Expr::HasValue(Box::new(expr(node))),
+        Expr::Fallback { value, fallback } => Expr::Fallback {
+            value: Box::new(expr(value)),
+            fallback: Box::new(expr(fallback)),
+        },
+        Expr::Index { value, index } => Expr::Index {
+            value: Box::new(expr(value)),
+            index: Box::new(expr(index)),
+        },
+        Expr::Field { value, name } => Expr::Field {
+            value: Box::new(expr(value)),
+            name: name.clone(),
+        },
+        Expr::Number(_)
+        | Expr::Float(_)
+        | Expr::String(_)
+        | Expr::Bool(_)
+        | Expr::Var(_)
+        | Expr::None
+        | Expr::FunRef(_) => inner.clone(),
+    };
+
+    AyNode {
+        span: span.clone(),
+        inner,
+    }
+}
+
+/// Value of `expr` if it is a literal.
+fn literal(expr: &Expr) -> Option<Value> {
+    match expr {
+        Expr::Number(number) => Some(Value::Number(*number)),
+        Expr::Float(number) => Some(Value::Float(*number)),
+        Expr::String(string) => Some(Value::String(string.clone())),
+        Expr::Bool(value) => Some(Value::Bool(*value)),
+        Expr::None => Some(Value::None),
+        _ => None,
+    }
+}
+
+/// Literal standing for `value`, if it has one.
+fn from_value(value: Value) -> Option<Expr> {
+    match value {
+        Value::Number(number) => Some(Expr::Number(number)),
+        Value::Float(number) => Some(Expr::Float(number)),
+        Value::String(string) => Some(Expr::String(string)),
+        Value::Bool(value) => Some(Expr::Bool(value)),
+        Value::None => Some(Expr::None),
+        Value::Function(name) => Some(Expr::FunRef(name)),
+        Value::Unit | Value::Array(_) | Value::Deferred(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ast::{binding, lib::SourceCode, parsing},
+        interp::Interpreter,
+    };
+
+    use super::*;
+
+    fn bind(source: &str) -> Vec<AyNode<Statement>> {
+        binding::convert(&parsing::parse(SourceCode::Content(source.to_owned())).unwrap()).unwrap()
+    }
+
+    /// Folded value of the only variable declared by `source`.
+    fn folded(source: &str) -> AyNode<Expr> {
+        match &fold_constants(&bind(source))[0].inner {
+            Statement::VarDec(VarDec { values, .. }) => values[0].clone(),
+            stmt => panic!("Expected a variable declaration, found {stmt:?}"),
+        }
+    }
+
+    #[test]
+    fn negated_literals() {
+        let bound = bind("ngop 'u a alu ke 5.");
+        let Statement::VarDec(VarDec { values, .. }) = &bound[0].inner else {
+            unreachable!()
+        };
+        assert!(matches!(&values[0].inner, Expr::Negated(node) if node.inner == Expr::Number(5)));
+
+        let value = folded("ngop 'u a alu ke 5.");
+        assert_eq!(value.inner, Expr::Number(-5));
+        assert_eq!(value.span, values[0].span);
+
+        assert_eq!(folded("ngop 'u a alu ke kehe.").inner, Expr::Bool(true));
+        assert_eq!(folded("ngop 'u a alu ke ke 0.4.").inner, Expr::Float(0.5));
+    }
+
+    #[test]
+    fn literal_comparisons() {
+        assert_eq!(folded("ngop 'u a alu 1 lu 2.").inner, Expr::Bool(false));
+        assert_eq!(
+            folded("ngop 'u a alu may' 1 sì 2 livu hol.").inner,
+            Expr::Bool(true)
+        );
+        assert_eq!(folded("ngop 'u a alu kewu lu 2.").inner, Expr::Bool(false));
+        assert_eq!(
+            folded("ngop 'u a alu (ke 2) lu 1 - 3.").inner,
+            Expr::Bool(true)
+        );
+    }
+
+    #[test]
+    fn arithmetic() {
+        // 16 in octal
+        assert_eq!(folded("ngop 'u a alu 2 + 3 * 4.").inner, Expr::Number(14));
+        assert_eq!(folded("ngop 'u a alu ke (1 + 2).").inner, Expr::Number(-3));
+    }
+
+    #[test]
+    fn non_constants_pass_through() {
+        for source in [
+            "ngop 'u b alu 1.\nngop 'u a alu ke b.",
+            "ngop 'u b alu 1.\nngop 'u a alu b lu 2.",
+            "ngop 'u b alu 1.\nngop 'u a alu b + 2.",
+            // Failing at runtime
+            "ngop 'u a alu 1 / 0.",
+            "ngop 'u a alu 1 lu san a sìk.",
+            "ngop 'u a alu ke san a sìk.",
+        ] {
+            let bound = bind(source);
+            assert_eq!(fold_constants(&bound), bound, "{source}");
+        }
+
+        // Only the constant part is folded
+        let folded = folded("ngop 'u a alu wìntxu fa 1 + 2.\nngop 'u b alu 3.");
+        assert!(matches!(
+            folded.inner,
+            Expr::FunCall { args, .. } if args[0].inner == Expr::Number(3)
+        ));
+    }
+
+    #[test]
+    fn same_behaviour() {
+        let bound = bind(
+            "ngop lì'ukìng f fa n alu\n\
+                 txo n lu ke 1, tsakrr 2 * 3, txokefyaw n + (ke 4 - 1)\n\
+             txew.\n\
+             wìntxu fa (f fa 1) sì (f fa ke 1) sì (1 lu 1).",
+        );
+        let run = |ast: &[AyNode<Statement>]| {
+            let mut interpreter = Interpreter::new(vec![]);
+            interpreter.run(ast).unwrap();
+            String::from_utf8(interpreter.into_output()).unwrap()
+        };
+
+        assert_eq!(run(&bound), "-4 6 srane\n");
+        assert_eq!(run(&fold_constants(&bound)), run(&bound));
+    }
+}
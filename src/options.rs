@@ -0,0 +1,258 @@
+use crate::ast::{
+    lib::{AyNode, Base},
+    lint::Lint,
+    parsing::{pretty_print, Statement},
+};
+
+use std::str::FromStr;
+
+use strum_macros::EnumString;
+
+/// How the parsed AST gets dumped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum AstFormat {
+    /// Rust's `{:#?}`.
+    Debug,
+    /// Indented tree, one node per line.
+    Pretty,
+    /// Single line `inner@line:col` form.
+    #[default]
+    Compact,
+}
+
+impl AstFormat {
+    pub fn render(&self, ast: &[AyNode<Statement>]) -> String {
+        match self {
+            AstFormat::Debug => format!("{ast:#?}"),
+            AstFormat::Pretty => pretty_print(ast),
+            AstFormat::Compact => format!("{ast:?}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CompilerOptions {
+    pub ast_format: AstFormat,
+    /// Lints to run on the parsed AST, all but the opt-in ones by default.
+    pub lints: Vec<Lint>,
+    /// Base bare numeric literals are read in, multipliers such as `melo` are only allowed in octal.
+    pub number_base_input: Base,
+    /// Base numbers are printed in when running programs, decimal unless asked otherwise.
+    pub number_base: Base,
+    /// Whether trivial single-use functions get inlined before running.
+    pub inline: bool,
+    /// Whether tail-recursive functions get rewritten into loops before running.
+    pub iterate: bool,
+    /// Whether operations on literals get replaced by their result before running.
+    pub fold: bool,
+    /// Depth past which the parse tree dump is truncated.
+    pub max_depth: usize,
+    /// Whether `run` writes out every statement and function call it executes.
+    pub trace_exec: bool,
+    /// Turns colors off even when writing to a terminal.
+    pub no_color: bool,
+    /// Whether the `parse`, `bind` and `run` commands print the parsed AST.
+    pub print_ast: bool,
+    /// Whether the `bind` and `run` commands print the bound AST.
+    pub print_bound: bool,
+}
+
+impl Default for CompilerOptions {
+    fn default() -> Self {
+        Self {
+            ast_format: AstFormat::default(),
+            lints: Lint::defaults().collect(),
+            number_base_input: Base::default(),
+            number_base: Base::Decimal,
+            inline: false,
+            iterate: false,
+            fold: false,
+            max_depth: 64,
+            trace_exec: false,
+            no_color: false,
+            print_ast: false,
+            print_bound: false,
+        }
+    }
+}
+
+impl CompilerOptions {
+    /// Extracts the `--flag=value` options from `args`, returning the remaining positional ones.
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<(Self, Vec<String>), String> {
+        let mut options = CompilerOptions::default();
+        let mut positionals = vec![];
+
+        for arg in args {
+            match arg.split_once('=') {
+                Some(("--ast-format", format)) => {
+                    options.ast_format = AstFormat::from_str(format).map_err(|_| {
+                        format!("Unknown AST format `{format}`, expected debug|pretty|compact")
+                    })?
+                }
+                Some(("--lints", lints)) => {
+                    options.lints = lints
+                        .split(',')
+                        .filter(|lint| !lint.is_empty())
+                        .map(|lint| {
+                            Lint::from_str(lint).map_err(|_| format!("Unknown lint `{lint}`"))
+                        })
+                        .collect::<Result<Vec<Lint>, String>>()?
+                }
+                Some(("--number-base", base)) => {
+                    options.number_base_input = Base::from_str(base).map_err(|_| {
+                        format!("Unknown number base `{base}`, expected octal|decimal")
+                    })?
+                }
+                Some(("--output-base", base)) => {
+                    options.number_base = Base::from_str(base).map_err(|_| {
+                        format!("Unknown number base `{base}`, expected octal|decimal")
+                    })?
+                }
+                Some(("--inline", inline)) => {
+                    options.inline = bool::from_str(inline).map_err(|_| {
+                        format!("Invalid value `{inline}` for --inline, expected true|false")
+                    })?
+                }
+                Some(("--iterate", iterate)) => {
+                    options.iterate = bool::from_str(iterate).map_err(|_| {
+                        format!("Invalid value `{iterate}` for --iterate, expected true|false")
+                    })?
+                }
+                Some(("--fold", fold)) => {
+                    options.fold = bool::from_str(fold).map_err(|_| {
+                        format!("Invalid value `{fold}` for --fold, expected true|false")
+                    })?
+                }
+                Some(("--trace-exec", trace)) => {
+                    options.trace_exec = bool::from_str(trace).map_err(|_| {
+                        format!("Invalid value `{trace}` for --trace-exec, expected true|false")
+                    })?
+                }
+                Some(("--max-depth", depth)) => {
+                    options.max_depth = depth
+                        .parse()
+                        .map_err(|_| format!("Invalid depth `{depth}` for --max-depth"))?
+                }
+                None if arg == "--no-color" => options.no_color = true,
+                None if arg == "--print-ast" => options.print_ast = true,
+                None if arg == "--print-bound" => options.print_bound = true,
+                _ if arg.starts_with("--") => return Err(format!("Unknown option `{arg}`")),
+                _ => positionals.push(arg),
+            }
+        }
+
+        Ok((options, positionals))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ast::{lib::SourceCode, parsing::parse},
+        interp::value::Value,
+    };
+
+    use super::*;
+
+    #[test]
+    fn ast_formats_are_distinguishable() {
+        let ast = parse(SourceCode::Content(
+            "ngop lì'ukìng foo fa a alu ke a txew.".to_owned(),
+        ))
+        .unwrap();
+
+        let outputs = [AstFormat::Debug, AstFormat::Pretty, AstFormat::Compact]
+            .map(|format| format.render(&ast));
+
+        assert!(outputs[0].contains("span: Span {"));
+        assert!(outputs[1].starts_with("FunDec foo(a) @1:6\n|   Expr @1:28\n"));
+        assert!(outputs[2].starts_with("[FunDec { name: \"foo\""));
+        assert!(outputs[2].contains("}@1:6]"));
+        assert!(outputs[0] != outputs[1] && outputs[1] != outputs[2]);
+    }
+
+    #[test]
+    fn ast_format_option() {
+        let (options, positionals) = CompilerOptions::from_args(
+            ["--ast-format=pretty", "file.ay"]
+                .map(str::to_owned)
+                .into_iter(),
+        )
+        .unwrap();
+
+        assert_eq!(options.ast_format, AstFormat::Pretty);
+        assert_eq!(positionals, vec!["file.ay"]);
+        assert!(CompilerOptions::from_args(["--ast-format=xml".to_owned()].into_iter()).is_err());
+
+        let (options, _) = CompilerOptions::from_args(
+            ["--lints=double-negation,self-comparison".to_owned()].into_iter(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            options.lints,
+            vec![Lint::DoubleNegation, Lint::SelfComparison]
+        );
+
+        let (options, _) =
+            CompilerOptions::from_args(["--number-base=decimal".to_owned()].into_iter()).unwrap();
+
+        assert_eq!(options.number_base_input, Base::Decimal);
+        assert_eq!(CompilerOptions::default().number_base_input, Base::Octal);
+
+        let (options, _) =
+            CompilerOptions::from_args(["--output-base=octal".to_owned()].into_iter()).unwrap();
+
+        assert_eq!(options.number_base, Base::Octal);
+        assert_eq!(
+            Value::Number(14)
+                .display_in(options.number_base)
+                .to_string(),
+            "16"
+        );
+        assert_eq!(CompilerOptions::default().number_base, Base::Decimal);
+
+        let (options, _) =
+            CompilerOptions::from_args(["--inline=true".to_owned()].into_iter()).unwrap();
+
+        assert!(options.inline);
+        assert!(CompilerOptions::from_args(["--inline=yes".to_owned()].into_iter()).is_err());
+
+        let (options, _) =
+            CompilerOptions::from_args(["--iterate=true".to_owned()].into_iter()).unwrap();
+
+        assert!(options.iterate);
+
+        let (options, _) =
+            CompilerOptions::from_args(["--fold=true".to_owned()].into_iter()).unwrap();
+
+        assert!(options.fold);
+
+        let (options, _) =
+            CompilerOptions::from_args(["--max-depth=3".to_owned()].into_iter()).unwrap();
+
+        assert_eq!(options.max_depth, 3);
+
+        let (options, _) =
+            CompilerOptions::from_args(["--no-color".to_owned()].into_iter()).unwrap();
+
+        assert!(options.no_color);
+
+        let (options, positionals) = CompilerOptions::from_args(
+            ["bind", "--print-ast", "-", "--print-bound"]
+                .map(str::to_owned)
+                .into_iter(),
+        )
+        .unwrap();
+
+        assert!(options.print_ast && options.print_bound);
+        assert_eq!(positionals, vec!["bind", "-"]);
+
+        let (options, _) =
+            CompilerOptions::from_args(["--trace-exec=true".to_owned()].into_iter()).unwrap();
+
+        assert!(options.trace_exec);
+        assert!(CompilerOptions::from_args(["--trace-exec".to_owned()].into_iter()).is_err());
+    }
+}
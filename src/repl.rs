@@ -0,0 +1,148 @@
+use crate::{
+    ast::{
+        binding::Session,
+        lib::{Base, SourceCode},
+        parsing,
+    },
+    error::trace::Trace,
+    highlight,
+    interp::{value::Value, Interpreter},
+    options::CompilerOptions,
+};
+
+use std::io::{BufRead, Write};
+
+/// Runs one statement per line, the variables and functions declared by a line staying in scope
+/// for the following ones.
+pub struct Repl<W: Write> {
+    session: Session,
+    interpreter: Interpreter<W>,
+    number_base_input: Base,
+    number_base: Base,
+    /// Written before reading each line, e.g. when the input is a terminal.
+    prompt: Option<String>,
+    /// Whether errors are colored, see [`set_color`](highlight::set_color).
+    color: bool,
+}
+
+impl<W: Write> Repl<W> {
+    pub fn new(out: W, options: &CompilerOptions) -> Self {
+        Self {
+            session: Session::new(),
+            interpreter: Interpreter::new(out)
+                .with_tracing(options.trace_exec)
+                .with_base(options.number_base),
+            number_base_input: options.number_base_input,
+            number_base: options.number_base,
+            prompt: None,
+            color: !options.no_color && highlight::color(),
+        }
+    }
+
+    pub fn with_prompt(mut self, prompt: &str) -> Self {
+        self.prompt = Some(prompt.to_owned());
+        self
+    }
+
+    pub fn into_output(self) -> W {
+        self.interpreter.into_output()
+    }
+
+    /// Parses, binds and runs a single line, returning its value.
+    ///
+    /// Its declarations are only kept if it binds, but the effects of a statement that fails while
+    /// running are not undone.
+    pub fn line(&mut self, line: &str) -> Result<(Value, Vec<Trace>), Trace> {
+        let ast =
+            parsing::parse_with_base(SourceCode::Content(line.to_owned()), self.number_base_input)?;
+        let bound = self.session.convert(&ast)?;
+
+        let (res, warnings) = self.interpreter.eval(&bound);
+        res.map(|value| (value, warnings))
+    }
+
+    /// Runs every line of `input`, writing their values and errors to the output.
+    pub fn run(&mut self, input: impl BufRead) -> std::io::Result<()> {
+        let mut lines = input.lines();
+
+        loop {
+            if let Some(prompt) = &self.prompt {
+                write!(self.interpreter.output(), "{prompt}")?;
+                self.interpreter.output().flush()?;
+            }
+
+            let Some(line) = lines.next().transpose()? else {
+                break;
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let res = self.line(&line);
+            let out = self.interpreter.output();
+
+            match res {
+                Ok((value, warnings)) => {
+                    for warning in warnings {
+                        writeln!(out, "{}", warning.to_string_colored(self.color))?;
+                    }
+
+                    if value != Value::Unit {
+                        writeln!(out, "{}", value.display_in(self.number_base))?;
+                    }
+                }
+                Err(trace) => writeln!(out, "{}", trace.to_string_colored(self.color))?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn session(lines: &str) -> String {
+        let options = CompilerOptions {
+            no_color: true,
+            ..Default::default()
+        };
+        let mut repl = Repl::new(vec![], &options);
+        repl.run(lines.as_bytes()).unwrap();
+
+        String::from_utf8(repl.into_output()).unwrap()
+    }
+
+    #[test]
+    fn persistent_declarations() {
+        assert_eq!(
+            session(
+                "ngop 'u a alu 2.\n\
+                 \n\
+                 ngop lì'ukìng twice fa x alu x + x.\n\
+                 twice fa a.\n\
+                 a + 1."
+            ),
+            "4\n3\n"
+        );
+    }
+
+    #[test]
+    fn errors_do_not_end_the_session() {
+        let output = session(
+            "b.\n\
+             ngop 'u a alu 2.\n\
+             ngop 'u c alu a + d.\n\
+             c.\n\
+             a.",
+        );
+        let traces = output.split("Deepest error first\n").collect::<Vec<_>>();
+
+        assert_eq!(traces.len(), 4);
+        assert!(traces[1].contains("-> Binding") && traces[1].contains("'b'"));
+        assert!(traces[2].contains("'d'"));
+        assert!(traces[3].contains("'c'") && traces[3].ends_with("2\n"));
+    }
+}
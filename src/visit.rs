@@ -0,0 +1,139 @@
+//! Traversal of bound trees, for passes to only handle the nodes they care about.
+//!
+//! Overriding `visit_statement` or `visit_expr` replaces the traversal of that node, calling
+//! [`walk_statement`] or [`walk_expr`] from the override carries on with its children.
+
+use crate::ast::{
+    binding::{Expr, Statement, VarDec},
+    lib::AyNode,
+};
+
+pub trait Visitor<'ast> {
+    fn visit_statement(&mut self, node: &'ast AyNode<Statement>) {
+        walk_statement(self, node)
+    }
+
+    fn visit_expr(&mut self, node: &'ast AyNode<Expr>) {
+        walk_expr(self, node)
+    }
+}
+
+/// Visits every top-level statement of `tree`, in order.
+pub fn walk<'ast, V: Visitor<'ast> + ?Sized>(tree: &'ast [AyNode<Statement>], visitor: &mut V) {
+    tree.iter().for_each(|node| visitor.visit_statement(node))
+}
+
+/// Visits the children of `node`, a function's helpers coming before its body and a condition
+/// before its branches.
+pub fn walk_statement<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    node: &'ast AyNode<Statement>,
+) {
+    match &node.inner {
+        Statement::FunDec(dec) => {
+            walk(&dec.helpers, visitor);
+            walk(&dec.body, visitor);
+        }
+        Statement::VarDec(VarDec { values, .. }) | Statement::Assign(VarDec { values, .. }) => {
+            values.iter().for_each(|value| visitor.visit_expr(value))
+        }
+        Statement::Expr(expr) | Statement::Assert(expr) => visitor.visit_expr(expr),
+        Statement::If {
+            cond,
+            then,
+            otherwise,
+        } => {
+            visitor.visit_expr(cond);
+            walk(then, visitor);
+            walk(otherwise, visitor);
+        }
+        Statement::Loop { cond, body, .. } => {
+            if let Some(cond) = cond {
+                visitor.visit_expr(cond);
+            }
+            walk(body, visitor);
+        }
+        Statement::Break(_) | Statement::Continue(_) => {}
+    }
+}
+
+/// Visits the children of `node` from left to right.
+pub fn walk_expr<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, node: &'ast AyNode<Expr>) {
+    match &node.inner {
+        Expr::FunCall { args: items, .. } | Expr::Array { items } => {
+            items.iter().for_each(|item| visitor.visit_expr(item))
+        }
+        Expr::Comparison { left, right, .. } | Expr::BinOp { left, right, .. } => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right);
+        }
+        Expr::Fallback {
+            value: left,
+            fallback: right,
+        }
+        | Expr::Index {
+            value: left,
+            index: right,
+        } => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right);
+        }
+        Expr::Negated(expr) | Expr::HasValue(expr) | Expr::Field { value: expr, .. } => {
+            visitor.visit_expr(expr)
+        }
+        Expr::Number(_)
+        | Expr::Float(_)
+        | Expr::String(_)
+        | Expr::Bool(_)
+        | Expr::Var(_)
+        | Expr::None
+        | Expr::FunRef(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ast::{binding, lib::SourceCode, parsing};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Numbers {
+        count: usize,
+    }
+
+    impl Visitor<'_> for Numbers {
+        fn visit_expr(&mut self, node: &AyNode<Expr>) {
+            if let Expr::Number(_) = node.inner {
+                self.count += 1;
+            }
+            walk_expr(self, node)
+        }
+    }
+
+    #[test]
+    fn count_numbers() {
+        let ast = parsing::parse(SourceCode::Content(
+            "ngop lì'ukìng f fa a alu\n\
+                 ngop 'u b alu ke 1 ulte\n\
+                 a + b * c\n\
+             txew tsengit ngop 'u c alu 3.\n\
+             txo (f fa 4) lu 5, tsakrr\n\
+                 leyn vaykrr 6 lu 7 kem si\n\
+                     hum\n\
+                 ftang,\n\
+             txokefyaw\n\
+                 nìngay vezeykoyu 10 sì (11 fu 12) sì 1.5.\n\
+             ngop 'u d alu vezeykoyu 13.\n\
+             nìngay d[0] lu kewu."
+                .to_owned(),
+        ))
+        .unwrap();
+        let bound = binding::convert(&ast).unwrap();
+
+        let mut numbers = Numbers::default();
+        walk(&bound, &mut numbers);
+
+        assert_eq!(numbers.count, 11);
+    }
+}
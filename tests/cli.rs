@@ -0,0 +1,130 @@
+use assert_cmd::Command;
+
+fn aysinvi() -> Command {
+    Command::cargo_bin("aysìnvi").unwrap()
+}
+
+#[test]
+fn valid_file() {
+    for command in ["parse", "bind", "run"] {
+        aysinvi()
+            .args([command, "examples/features/runtime/valid/arithmetic.ay"])
+            .assert()
+            .success();
+    }
+}
+
+#[test]
+fn invalid_file() {
+    for command in ["run", "fmt"] {
+        let output = aysinvi()
+            .args([
+                command,
+                "examples/features/expressions/invalid/string_unterminated.ay",
+            ])
+            .assert()
+            .failure()
+            .code(1)
+            .get_output()
+            .stderr
+            .clone();
+        let stderr = String::from_utf8(output).unwrap();
+
+        assert!(stderr.starts_with("Deepest error first\n-> Parsing | "));
+        assert!(stderr.contains("string_unterminated.ay:1:5"));
+    }
+}
+
+#[test]
+fn ill_typed() {
+    for command in ["bind", "run"] {
+        let output = aysinvi()
+            .args([command, "-"])
+            .write_stdin("ngop 'u a alu vezeykoyu 1 sì san a sìk.\nwìntxu fa a.")
+            .assert()
+            .failure()
+            .stdout("")
+            .get_output()
+            .stderr
+            .clone();
+        let stderr = String::from_utf8(output).unwrap();
+
+        assert!(stderr.starts_with("Deepest error first\n-> Typing | "));
+        assert!(stderr.contains("Array items must all have the same type"));
+    }
+}
+
+#[test]
+fn unknown_command() {
+    aysinvi()
+        .args(["compile", "examples/features/runtime/valid/arithmetic.ay"])
+        .assert()
+        .failure()
+        .stderr("Unknown command `compile`, expected parse|bind|run|fmt\n");
+}
+
+#[test]
+fn format() {
+    aysinvi()
+        .args(["fmt", "-"])
+        .write_stdin("ngop  lì'ukìng rey fa n alu n*2 txew.\n\n\n'' Twice\nrey fa 1 .")
+        .assert()
+        .success()
+        .stdout("ngop lì'ukìng rey fa n alu\n    n * 2\ntxew.\n\n'' Twice\nrey fa 1.\n");
+}
+
+#[test]
+fn format_number_base() {
+    aysinvi()
+        .args(["fmt", "--number-base=decimal", "-"])
+        .write_stdin("9  +  b.")
+        .assert()
+        .success()
+        .stdout("9 + b.\n");
+}
+
+#[test]
+fn output_base() {
+    for (base, output) in [("octal", "16\n"), ("decimal", "14\n")] {
+        aysinvi()
+            .args(["run", &format!("--output-base={base}"), "-"])
+            .write_stdin("7 melo.")
+            .assert()
+            .success()
+            .stdout(output);
+    }
+}
+
+#[test]
+fn standard_input() {
+    aysinvi()
+        .args(["bind", "--print-ast", "--print-bound", "-"])
+        .write_stdin("ngop 'u a alu 2.")
+        .assert()
+        .success()
+        .stdout(
+            "[VarDec { names: [\"a\"], values: [Number(2)@1:15] }@1:6]\n\
+             [VarDec(VarDec { names: [\"a\"], values: [Number(2)@1:15] })@1:6]\n",
+        );
+
+    let output = aysinvi()
+        .args(["bind", "-"])
+        .write_stdin("ngop 'u a alu b.")
+        .assert()
+        .failure()
+        .get_output()
+        .stderr
+        .clone();
+
+    assert!(String::from_utf8(output).unwrap().contains("-> Binding"));
+}
+
+#[test]
+fn repl() {
+    aysinvi()
+        .args(["repl", "--no-color"])
+        .write_stdin("ngop 'u a alu 2.\nngop lì'ukìng twice fa x alu x + x.\ntwice fa a.\n")
+        .assert()
+        .success()
+        .stdout("4\n");
+}
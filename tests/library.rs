@@ -0,0 +1,30 @@
+use aysinvi::{
+    binding::{self, Expr, Statement},
+    parse, AyNode, Interpreter, SourceCode, Trace, Value,
+};
+
+fn bind(source: &str) -> Result<Vec<AyNode<Statement>>, Trace> {
+    binding::convert(&parse(SourceCode::Content(source.to_owned()))?)
+}
+
+#[test]
+fn parse_and_bind() {
+    let ast = bind("ngop 'u a alu 7.\nsung fa a sì 1.").unwrap();
+
+    let Statement::Expr(call) = &ast[1].inner else {
+        panic!("Expected an expression");
+    };
+    assert!(matches!(&call.inner, Expr::FunCall { name, .. } if name == "sung"));
+
+    let res = Interpreter::new(std::io::sink()).run(&ast).unwrap();
+    assert_eq!(res, Value::Number(8));
+}
+
+#[test]
+fn errors() {
+    let trace = bind("sung fa b.").unwrap_err();
+
+    assert!(trace
+        .frames()
+        .any(|(_, err)| err.message().contains("Undefined variable")));
+}
@@ -0,0 +1,26 @@
+use std::process::Command;
+
+#[test]
+fn run_arithmetic() {
+    let output = Command::new(env!("CARGO_BIN_EXE_aysìnvi"))
+        .args(["run", "./examples/arithmetic.ay"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "16\n");
+}
+
+#[test]
+fn run_failure() {
+    let output = Command::new(env!("CARGO_BIN_EXE_aysìnvi"))
+        .args(["run", "./examples/features/assert/invalid/constant.ay"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Assertion can never hold"));
+    // Not written to a terminal
+    assert!(!stderr.contains('\x1b'));
+}